@@ -6,7 +6,7 @@ extern crate ggez;
 extern crate nalgebra;
 extern crate rand;
 
-use ggez::audio;
+use ggez::audio::{self, SoundSource};
 use ggez::conf;
 use ggez::event::{self, EventHandler, KeyCode, KeyMods};
 use ggez::graphics;
@@ -24,9 +24,9 @@ pub fn main() -> GameResult {
 
     // This is also used in the loop inside `::run()` - it can be flipped off with `ctx.quit()`
     while ctx.continuing {
-        // Tell the timer stuff a frame has happened.
+        // Tell the timer and input stuff a frame has happened.
         // Without this the FPS timer functions and such won't work.
-        ctx.timer_context.tick();
+        ctx.begin_frame();
         // Handle events. Refer to `winit` docs for more information.
         events_loop.poll_events(|event| {
             // This tells `ggez` to update it's internal states, should the event require that.
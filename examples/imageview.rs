@@ -2,7 +2,7 @@ extern crate cgmath;
 extern crate ggez;
 extern crate rand;
 
-use ggez::audio;
+use ggez::audio::{self, SoundSource};
 use ggez::event;
 use ggez::filesystem;
 use ggez::graphics;
@@ -3,9 +3,11 @@
 extern crate cgmath;
 extern crate ggez;
 
-use ggez::event::{self, Axis, Button, KeyCode, KeyMods, MouseButton};
+use ggez::event::{self, Axis, Button, GamepadId, KeyCode, KeyMods, MouseButton, TouchPhase};
 use ggez::graphics::{self, DrawMode};
+use ggez::input::mouse;
 use ggez::{Context, GameResult};
+use std::path::PathBuf;
 
 struct MainState {
     pos_x: f32,
@@ -66,13 +68,25 @@ impl event::EventHandler for MainState {
         );
     }
 
+    fn raw_mouse_motion_event(&mut self, _ctx: &mut Context, dx: f32, dy: f32) {
+        println!("Raw mouse motion, dx: {}, dy: {}", dx, dy);
+    }
+
+    fn touch_event(&mut self, _ctx: &mut Context, phase: TouchPhase, id: u64, x: f32, y: f32) {
+        println!("Touch {:?}, id: {}, x: {}, y: {}", phase, id, x, y);
+    }
+
+    fn file_dropped_event(&mut self, _ctx: &mut Context, path: PathBuf) {
+        println!("File dropped: {}", path.display());
+    }
+
     fn mouse_wheel_event(&mut self, _ctx: &mut Context, x: f32, y: f32) {
         println!("Mousewheel event, x: {}, y: {}", x, y);
     }
 
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         keycode: KeyCode,
         keymod: KeyMods,
         repeat: bool,
@@ -81,6 +95,17 @@ impl event::EventHandler for MainState {
             "Key pressed: {:?}, modifier {:?}, repeat: {}",
             keycode, keymod, repeat
         );
+        match keycode {
+            KeyCode::Escape => ctx.quit(),
+            // Toggle relative mouse mode.
+            KeyCode::R if !repeat => {
+                let relative = !mouse::get_relative_mode(ctx);
+                if let Err(e) = mouse::set_relative_mode(ctx, relative) {
+                    println!("Couldn't change relative mode: {}", e);
+                }
+            }
+            _ => (),
+        }
     }
 
     fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, keymod: KeyMods) {
@@ -91,24 +116,32 @@ impl event::EventHandler for MainState {
         println!("Text input: {}", ch);
     }
 
-    fn controller_button_down_event(&mut self, _ctx: &mut Context, btn: Button, id: usize) {
+    fn controller_button_down_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
         println!("Controller button pressed: {:?} Controller_Id: {}", btn, id);
     }
 
-    fn controller_button_up_event(&mut self, _ctx: &mut Context, btn: Button, id: usize) {
+    fn controller_button_up_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
         println!(
             "Controller button released: {:?} Controller_Id: {}",
             btn, id
         );
     }
 
-    fn controller_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32, id: usize) {
+    fn controller_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) {
         println!(
             "Axis Event: {:?} Value: {} Controller_Id: {}",
             axis, value, id
         );
     }
 
+    fn controller_connected_event(&mut self, _ctx: &mut Context, id: GamepadId) {
+        println!("Controller connected: Controller_Id: {}", id);
+    }
+
+    fn controller_disconnected_event(&mut self, _ctx: &mut Context, id: GamepadId) {
+        println!("Controller disconnected: Controller_Id: {}", id);
+    }
+
     fn focus_event(&mut self, _ctx: &mut Context, gained: bool) {
         if gained {
             println!("Focus gained");
@@ -2,19 +2,43 @@
 //!
 //! It consists of two main types: `SoundData` is just raw sound data,
 //! and a `Source` is a `SoundData` connected to a particular sound
-//! channel.
+//! channel, which can change its pitch, seek, loop part of itself
+//! and fade in and out.  For long sounds such as music there's also
+//! the `StreamingSource`, which decodes its file as it plays rather
+//! than loading it all into memory first, and the `SpatialSource`,
+//! which pans and fades depending on where it is relative to the
+//! listener set on the `AudioContext`.  Sound effects that just need
+//! to be fired off, overlapping themselves if need be, can instead be
+//! played with `play_oneshot()`.
+//!
+//! Every source plays through a named mixer bus, by default
+//! `SFX_BUS` or, for a `StreamingSource`, `MUSIC_BUS`.  Each bus has
+//! its own volume, and can be muted or paused as a whole from the
+//! `AudioContext`, so an options menu can offer "music volume" and
+//! "effects volume" without keeping track of every sound.  The bus
+//! volumes are loaded from, and can be saved to, `conf::AudioSetup`.
 
+use std::collections::HashMap;
+use std::f32;
 use std::fmt;
 use std::io;
-use std::io::Read;
+use std::io::{Read, Seek};
 use std::path;
+use std::thread;
+use std::time;
 
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
 
+use mint;
+use nalgebra as na;
+use rand::{self, Rng};
 use rodio;
 
+use conf;
 use context::Context;
 use filesystem;
+use timer;
 use GameError;
 use GameResult;
 
@@ -23,7 +47,16 @@ use GameResult;
 /// You generally don't have to create this yourself, it will be part
 /// of your `Context` object.
 pub struct AudioContext {
-    device: rodio::Device,
+    /// The output device; `None` for a headless context, in which
+    /// case sounds get played into a sink that goes nowhere.
+    device: Option<rodio::Device>,
+    /// Shared with every `SpatialSource`, which checks it as it plays.
+    listener: Arc<Mutex<Listener>>,
+    /// Shared with every source, which checks its bus as it plays.
+    mixer: Arc<Mutex<Mixer>>,
+    /// The sounds playing from `play_oneshot()`, by which `SoundData`
+    /// they're playing, oldest first.
+    voices: HashMap<usize, Vec<Arc<Mutex<VoiceState>>>>,
 }
 
 impl AudioContext {
@@ -34,8 +67,167 @@ impl AudioContext {
                 "Could not initialize sound system (for some reason)",
             ))
         })?;
-        Ok(AudioContext { device: device })
+        Ok(AudioContext {
+            device: Some(device),
+            listener: Arc::new(Mutex::new(Listener::default())),
+            mixer: Arc::new(Mutex::new(Mixer::default())),
+            voices: HashMap::new(),
+        })
+    }
+
+    /// Create a new AudioContext with no output device.
+    /// Sources created from it can be played, paused and so on
+    /// as usual, they just never make a sound (and, since nothing
+    /// ever consumes their data, never finish playing either).
+    pub fn new_headless() -> AudioContext {
+        AudioContext {
+            device: None,
+            listener: Arc::new(Mutex::new(Listener::default())),
+            mixer: Arc::new(Mutex::new(Mixer::default())),
+            voices: HashMap::new(),
+        }
+    }
+
+    /// Creates a new `Sink` playing to our output device, or to
+    /// nowhere at all if we don't have one.
+    pub(crate) fn new_sink(&self) -> rodio::Sink {
+        match self.device {
+            Some(ref device) => rodio::Sink::new(device),
+            None => {
+                let (sink, _output) = rodio::Sink::new_idle();
+                sink
+            }
+        }
+    }
+
+    fn listener(&self) -> MutexGuard<Listener> {
+        lock_ignoring_poison(&self.listener)
+    }
+
+    /// Sets where the listener hearing `SpatialSource`s is.  For a 2D
+    /// game this would usually be the center of the screen, at a z of
+    /// 0.0 like everything else.
+    pub fn set_listener_position<P>(&mut self, position: P)
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        self.listener().position = na::Point3::from(position.into());
+    }
+
+    /// Gets where the listener is.
+    pub fn listener_position(&self) -> mint::Point3<f32> {
+        self.listener().position.into()
+    }
+
+    /// Sets which way the listener is facing: `forward` is the way
+    /// they're looking, and `up` the way the top of their head points.
+    /// The default is looking along -z with +y up, which puts +x on
+    /// the listener's right, so sounds to the right of the screen
+    /// come out of the right speaker.
+    pub fn set_listener_orientation<V>(&mut self, forward: V, up: V)
+    where
+        V: Into<mint::Vector3<f32>>,
+    {
+        let mut listener = self.listener();
+        listener.forward = na::Vector3::from(forward.into());
+        listener.up = na::Vector3::from(up.into());
+    }
+
+    /// Gets which way the listener is facing, as `(forward, up)`.
+    pub fn listener_orientation(&self) -> (mint::Vector3<f32>, mint::Vector3<f32>) {
+        let listener = self.listener();
+        (listener.forward.into(), listener.up.into())
+    }
+
+    fn mixer(&self) -> MutexGuard<Mixer> {
+        lock_ignoring_poison(&self.mixer)
+    }
+
+    /// Sets the volume every bus is scaled by, from 0.0 for silent to
+    /// 1.0 for full volume.
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.mixer().master_volume = volume;
+    }
+
+    /// Gets the volume every bus is scaled by.
+    pub fn master_volume(&self) -> f32 {
+        self.mixer().master_volume
+    }
+
+    /// Sets the volume of the named bus.  Buses are created the first
+    /// time they're used, with a volume of 1.0.
+    pub fn set_bus_volume(&mut self, bus: &str, volume: f32) {
+        self.mixer().bus_mut(bus).volume = volume;
+    }
+
+    /// Gets the volume of the named bus.
+    pub fn bus_volume(&self, bus: &str) -> f32 {
+        self.mixer().bus(bus).volume
     }
+
+    /// Mutes or unmutes the named bus.  A muted bus keeps its volume
+    /// for when it's unmuted.
+    pub fn set_bus_muted(&mut self, bus: &str, muted: bool) {
+        self.mixer().bus_mut(bus).muted = muted;
+    }
+
+    /// Returns whether the named bus is muted.
+    pub fn is_bus_muted(&self, bus: &str) -> bool {
+        self.mixer().bus(bus).muted
+    }
+
+    /// Pauses or resumes every sound playing through the named bus,
+    /// such as all the sound effects while the game is paused.  This
+    /// is separate from pausing a source with `SoundSource::pause()`;
+    /// a sound plays only if neither it nor its bus is paused.
+    pub fn set_bus_paused(&mut self, bus: &str, paused: bool) {
+        self.mixer().bus_mut(bus).paused = paused;
+    }
+
+    /// Returns whether the named bus is paused.
+    pub fn is_bus_paused(&self, bus: &str) -> bool {
+        self.mixer().bus(bus).paused
+    }
+
+    /// Returns the master volume and the volume of every bus, to be
+    /// saved in a `conf::Conf`.  Whether buses are paused isn't saved.
+    pub fn audio_setup(&self) -> conf::AudioSetup {
+        let mixer = self.mixer();
+        let buses = mixer
+            .buses
+            .iter()
+            .map(|(name, bus)| {
+                let setup = conf::BusSetup {
+                    volume: bus.volume,
+                    muted: bus.muted,
+                };
+                (name.clone(), setup)
+            })
+            .collect();
+        conf::AudioSetup {
+            master_volume: mixer.master_volume,
+            buses,
+        }
+    }
+
+    /// Sets the master volume and bus volumes from a `conf::AudioSetup`.
+    /// This is done with `Context::conf.audio_setup` when the `Context`
+    /// is created.  Buses it doesn't mention are left alone.
+    pub fn apply_audio_setup(&mut self, setup: &conf::AudioSetup) {
+        let mut mixer = self.mixer();
+        mixer.master_volume = setup.master_volume;
+        for (name, bus_setup) in &setup.buses {
+            let bus = mixer.bus_mut(name);
+            bus.volume = bus_setup.volume;
+            bus.muted = bus_setup.muted;
+        }
+    }
+}
+
+/// Locks a mutex holding plain data, which can't be left half
+/// updated, so a panic while it was locked doesn't matter.
+fn lock_ignoring_poison<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
 }
 
 impl fmt::Debug for AudioContext {
@@ -44,6 +236,127 @@ impl fmt::Debug for AudioContext {
     }
 }
 
+/// The bus `Source`s and `SpatialSource`s play through unless they're
+/// moved with `SoundSource::set_bus()`.
+pub const SFX_BUS: &str = "sfx";
+
+/// The bus `StreamingSource`s play through unless they're moved with
+/// `SoundSource::set_bus()`.
+pub const MUSIC_BUS: &str = "music";
+
+/// How often, in milliseconds, a playing sound checks for changes
+/// made to it from the game, such as its bus's volume or, for a
+/// `SpatialSource`, where it or the listener are.
+const CONTROL_UPDATE_MILLIS: u64 = 10;
+
+/// The settings of one mixer bus.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Bus {
+    volume: f32,
+    muted: bool,
+    paused: bool,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Bus {
+            volume: 1.0,
+            muted: false,
+            paused: false,
+        }
+    }
+}
+
+/// The master volume and the buses, by name.
+#[derive(Debug, Clone, PartialEq)]
+struct Mixer {
+    master_volume: f32,
+    buses: HashMap<String, Bus>,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        let mut mixer = Mixer {
+            master_volume: 1.0,
+            buses: HashMap::new(),
+        };
+        let _ = mixer.bus_mut(SFX_BUS);
+        let _ = mixer.bus_mut(MUSIC_BUS);
+        mixer
+    }
+}
+
+impl Mixer {
+    fn bus(&self, name: &str) -> Bus {
+        self.buses.get(name).cloned().unwrap_or_default()
+    }
+
+    fn bus_mut(&mut self, name: &str) -> &mut Bus {
+        self.buses
+            .entry(name.to_owned())
+            .or_insert_with(Bus::default)
+    }
+
+    /// Returns the volume sounds on the named bus should play at, and
+    /// whether they should be paused.
+    fn output(&self, name: &str) -> (f32, bool) {
+        let bus = self.bus(name);
+        let volume = if bus.muted {
+            0.0
+        } else {
+            self.master_volume * bus.volume
+        };
+        (volume, bus.paused)
+    }
+}
+
+/// Which bus a source plays through.  The sounds it plays share it,
+/// so moving the source to another bus affects them straight away.
+#[derive(Debug, Clone)]
+struct BusAssignment {
+    mixer: Arc<Mutex<Mixer>>,
+    bus: Arc<Mutex<String>>,
+}
+
+impl BusAssignment {
+    fn new(audio: &AudioContext, bus: &str) -> Self {
+        BusAssignment {
+            mixer: audio.mixer.clone(),
+            bus: Arc::new(Mutex::new(bus.to_owned())),
+        }
+    }
+
+    fn name(&self) -> String {
+        lock_ignoring_poison(&self.bus).clone()
+    }
+
+    fn set_name(&self, bus: &str) {
+        *lock_ignoring_poison(&self.bus) = bus.to_owned();
+    }
+
+    /// Plays the given sound on the sink through our bus, checking
+    /// the bus's volume and whether it's paused as it goes.
+    fn append<S>(&self, sink: &rodio::Sink, source: S)
+    where
+        S: rodio::Source + Send + 'static,
+        S::Item: rodio::Sample + Send,
+    {
+        use rodio::Source;
+        let mixer = self.mixer.clone();
+        let bus = self.bus.clone();
+        let output = move || lock_ignoring_poison(&mixer).output(&lock_ignoring_poison(&bus));
+        let (volume, paused) = output();
+        let period = time::Duration::from_millis(CONTROL_UPDATE_MILLIS);
+        let controlled = source.amplify(volume).pausable(paused);
+        let source = controlled.periodic_access(period, move |source| {
+            let (volume, paused) = output();
+            source.set_paused(paused);
+            source.inner_mut().set_factor(volume);
+        });
+        sink.append(source);
+    }
+}
+
 /// Static sound data stored in memory.
 /// It is Arc'ed, so cheap to clone.
 #[derive(Clone, Debug)]
@@ -73,6 +386,12 @@ impl SoundData {
 
         Ok(SoundData::from(buffer))
     }
+
+    /// Identifies the data, so that copies of it playing at once can
+    /// be counted.
+    fn key(&self) -> usize {
+        self.0.as_ptr() as usize
+    }
 }
 
 impl From<Arc<[u8]>> for SoundData {
@@ -101,22 +420,69 @@ impl AsRef<[u8]> for SoundData {
     }
 }
 
+/// The controls shared by all the kinds of sound source.
+pub trait SoundSource {
+    /// Plays the source.
+    fn play(&self) -> GameResult;
+
+    /// Sets the source to repeat playback infinitely on next `play()`
+    fn set_repeat(&mut self, repeat: bool);
+
+    /// Gets whether or not the source is set to repeat.
+    fn repeat(&self) -> bool;
+
+    /// Pauses playback
+    fn pause(&self);
+
+    /// Resumes playback
+    fn resume(&self);
+
+    /// Stops playback
+    fn stop(&self);
+
+    /// Returns whether or not the source is stopped
+    /// -- that is, has no more data to play.
+    fn stopped(&self) -> bool;
+
+    /// Gets the current volume
+    fn volume(&self) -> f32;
+
+    /// Sets the current volume
+    fn set_volume(&mut self, value: f32);
+
+    /// Get whether or not the source is paused
+    fn paused(&self) -> bool;
+
+    /// Moves the source onto the named mixer bus.  Sounds it's
+    /// already playing move too.
+    fn set_bus(&mut self, bus: &str);
+
+    /// Gets the name of the mixer bus the source plays through.
+    fn bus(&self) -> String;
+
+    /// Get whether or not the source is playing (ie, not paused
+    /// and not stopped)
+    fn playing(&self) -> bool {
+        !self.paused() && !self.stopped()
+    }
+}
+
 /// A source of audio data connected to a particular `Channel`.
 /// Will stop playing when dropped.
 // TODO: Check and see if this matches Love2d's semantics!
-// Eventually it might read from a streaming decoder of some kind,
-// but for now it is just an in-memory SoundData structure.
-// The source of a rodio decoder must be Send, which something
-// that contains a reference to a ZipFile is not, so we are going
-// to just slurp all the data into memory for now.
-// There's really a lot of work that needs to be done here, since
-// rodio has gotten better (if still somewhat arcane) and our filesystem
-// code has done the data-slurping-from-zip's for us
-// but for now it works.
+// This is just an in-memory SoundData structure, which is what you
+// want for short sound effects that get played over and over; see
+// `StreamingSource` for reading from a file as it plays.
 pub struct Source {
     data: io::Cursor<SoundData>,
     sink: rodio::Sink,
     repeat: bool,
+    bus: BusAssignment,
+    /// The pitch and fades, shared with the sounds it plays.
+    voice: Arc<Mutex<VoiceState>>,
+    playback: Arc<Mutex<PlaybackControls>>,
+    frames_played: Arc<AtomicUsize>,
+    pitch_variation: f32,
 }
 
 impl Source {
@@ -129,83 +495,231 @@ impl Source {
 
     /// Creates a new Source using the given SoundData object.
     pub fn from_data(context: &mut Context, data: SoundData) -> GameResult<Self> {
-        let sink = rodio::Sink::new(&context.audio_context.device);
+        let sink = context.audio_context.new_sink();
         let cursor = io::Cursor::new(data);
+        let voice = VoiceState {
+            volume: 1.0,
+            pitch: 1.0,
+            fade: None,
+            stopped: false,
+            finished: false,
+            generation: 0,
+        };
         Ok(Source {
             sink,
             data: cursor,
             repeat: false,
+            bus: BusAssignment::new(&context.audio_context, SFX_BUS),
+            voice: Arc::new(Mutex::new(voice)),
+            playback: Arc::new(Mutex::new(PlaybackControls::default())),
+            frames_played: Arc::new(AtomicUsize::new(0)),
+            pitch_variation: 0.0,
         })
     }
 
-    /// Plays the Source.
-    pub fn play(&self) -> GameResult {
+    fn voice(&self) -> MutexGuard<VoiceState> {
+        lock_ignoring_poison(&self.voice)
+    }
+
+    fn playback(&self) -> MutexGuard<PlaybackControls> {
+        lock_ignoring_poison(&self.playback)
+    }
+
+    /// Sets the pitch the source plays at, which also changes how fast
+    /// it plays: 2.0 plays it twice as fast and an octave higher, and
+    /// 0.5 half as fast and an octave lower.  Takes effect straight
+    /// away, even on sounds already playing.
+    pub fn set_pitch(&mut self, pitch: f32) {
+        self.voice().pitch = pitch;
+    }
+
+    /// Gets the pitch the source plays at.
+    pub fn pitch(&self) -> f32 {
+        self.voice().pitch
+    }
+
+    /// Sets how much the pitch varies at random each time the source
+    /// is played, as a fraction either way: 0.1 plays it at anywhere
+    /// from 90% to 110% of `pitch()`.  This keeps a sound effect that
+    /// plays over and over from sounding quite so repetitive.
+    pub fn set_pitch_variation(&mut self, variation: f32) {
+        self.pitch_variation = variation;
+    }
+
+    /// Gets how much the pitch varies each time the source is played.
+    pub fn pitch_variation(&self) -> f32 {
+        self.pitch_variation
+    }
+
+    /// Jumps to the given time into the sound.  If it's playing, this
+    /// happens straight away; if not, the next `play()` starts there.
+    ///
+    /// Sounds can't be decoded backwards, so seeking means decoding
+    /// from the start again up to that point.  That's done on another
+    /// thread, so as not to hold up the rest of the sound, and the
+    /// source plays silence until it's caught up, which takes a moment
+    /// for long sounds.
+    pub fn seek(&mut self, position: time::Duration) {
+        let mut playback = self.playback();
+        playback.seek_to = Some(position);
+        let frames = timer::duration_to_f64(position) * f64::from(playback.sample_rate);
+        self.frames_played.store(frames as usize, Ordering::Relaxed);
+    }
+
+    /// Returns how far into the sound the source has got, going by
+    /// the sound itself rather than how long it's been playing, so
+    /// that it keeps in step with the music through changes of pitch,
+    /// pauses and loops.  This is as far as the sound has been sent
+    /// to the speakers, which is a little ahead of what's been heard.
+    pub fn elapsed(&self) -> time::Duration {
+        let sample_rate = self.playback().sample_rate;
+        if sample_rate == 0 {
+            return time::Duration::from_secs(0);
+        }
+        let frames = self.frames_played.load(Ordering::Relaxed);
+        timer::f64_to_duration(frames as f64 / f64::from(sample_rate))
+    }
+
+    /// Returns how long the sound is, at its normal pitch.  Not every
+    /// format says, in which case the whole sound is decoded to find
+    /// out, but only the first time this is called.
+    pub fn total_duration(&self) -> GameResult<time::Duration> {
+        if let Some(duration) = self.playback().total_duration {
+            return Ok(duration);
+        }
+        let decoder = rodio::Decoder::new(self.data.clone())?;
+        let duration = match rodio::Source::total_duration(&decoder) {
+            Some(duration) => duration,
+            None => {
+                let channels = rodio::Source::channels(&decoder).max(1);
+                let sample_rate = rodio::Source::sample_rate(&decoder);
+                let frames = decoder.count() / usize::from(channels);
+                timer::f64_to_duration(frames as f64 / f64::from(sample_rate))
+            }
+        };
+        self.playback().total_duration = Some(duration);
+        Ok(duration)
+    }
+
+    /// Makes the part of the sound between `start` and `end` loop:
+    /// the sound plays up to `end`, then carries on from `start`, over
+    /// and over, so a piece of music can have an intro before the
+    /// part that repeats.  If `end` is past the end of the sound, the
+    /// loop ends with the sound.  If `end` isn't after `start`, this
+    /// clears the loop points instead.
+    ///
+    /// The first time through the looping part it's kept in memory,
+    /// so going round again doesn't mean decoding it again.
+    pub fn set_loop_points(&mut self, start: time::Duration, end: time::Duration) {
+        self.playback().loop_points = if end > start {
+            Some((start, end))
+        } else {
+            None
+        };
+    }
+
+    /// Stops looping part of the sound; anything playing carries on to
+    /// the end.
+    pub fn clear_loop_points(&mut self) {
+        self.playback().loop_points = None;
+    }
+
+    /// Gets the start and end of the looping part of the sound, if
+    /// there is one.
+    pub fn loop_points(&self) -> Option<(time::Duration, time::Duration)> {
+        self.playback().loop_points
+    }
+
+    /// Fades the source in from silence over the given time, starting
+    /// now; call it just before `play()`.  This is separate from
+    /// `volume()`, which it fades up to.
+    pub fn fade_in(&mut self, duration: time::Duration) {
+        let mut voice = self.voice();
+        voice.restart();
+        voice.volume = 0.0;
+        voice.fade = None;
+        voice.fade(1.0, duration, false);
+    }
+
+    /// Fades the source out over the given time, then stops it, along
+    /// with anything queued up to play after it.  It can be played
+    /// again as usual afterwards.
+    pub fn fade_out(&mut self, duration: time::Duration) {
+        self.voice().fade(0.0, duration, true);
+    }
+
+    /// Fades this source out while fading `next` in and starting it,
+    /// over the given time, such as to change from one piece of music
+    /// to another.
+    pub fn crossfade_to(&mut self, next: &mut Source, duration: time::Duration) -> GameResult {
+        self.fade_out(duration);
+        next.fade_in(duration);
+        next.play()
+    }
+}
+
+impl SoundSource for Source {
+    fn play(&self) -> GameResult {
         // Creating a new Decoder each time seems a little messy,
         // since it may do checking and data-type detection that is
         // redundant, but it's not super expensive.
         // See https://github.com/ggez/ggez/issues/98 for discussion
-        use rodio::Source;
-        let cursor = self.data.clone();
-        let decoder = rodio::Decoder::new(cursor)?;
-        if self.repeat {
-            let repeating = decoder.repeat_infinite();
-            self.sink.append(repeating);
-        } else {
-            self.sink.append(decoder);
-        }
+        let data = self.data.get_ref().clone();
+        let playback = PlaybackSource::new(
+            data,
+            self.playback.clone(),
+            self.frames_played.clone(),
+            self.repeat,
+        )?;
+        self.voice().restart();
+        let pitch_scale = random_pitch_scale(self.pitch_variation);
+        let source = VoiceSource::new(playback, self.voice.clone(), pitch_scale);
+        self.bus.append(&self.sink, source);
         Ok(())
     }
 
-    /// Sets the source to repeat playback infinitely on next `play()`
-    pub fn set_repeat(&mut self, repeat: bool) {
+    fn set_repeat(&mut self, repeat: bool) {
         self.repeat = repeat;
     }
 
-    /// Gets whether or not the source is set to repeat.
-    pub fn repeat(&self) -> bool {
+    fn repeat(&self) -> bool {
         self.repeat
     }
 
-    /// Pauses playback
-    pub fn pause(&self) {
+    fn pause(&self) {
         self.sink.pause()
     }
 
-    /// Resumes playback
-    pub fn resume(&self) {
+    fn resume(&self) {
         self.sink.play()
     }
 
-    /// Stops playback
-    pub fn stop(&self) {
-        self.sink.stop()
+    fn stop(&self) {
+        self.voice().stopped = true;
     }
 
-    /// Returns whether or not the source is stopped
-    /// -- that is, has no more data to play.
-    pub fn stopped(&self) -> bool {
-        self.sink.empty()
+    fn stopped(&self) -> bool {
+        self.voice().stopped || self.sink.empty()
     }
 
-    /// Gets the current volume
-    pub fn volume(&self) -> f32 {
+    fn volume(&self) -> f32 {
         self.sink.volume()
     }
 
-    /// Sets the current volume
-    pub fn set_volume(&mut self, value: f32) {
+    fn set_volume(&mut self, value: f32) {
         self.sink.set_volume(value)
     }
 
-    /// Get whether or not the source is paused
-    pub fn paused(&self) -> bool {
+    fn paused(&self) -> bool {
         self.sink.is_paused()
     }
 
-    /// Get whether or not the source is playing (ie, not paused
-    /// and not stopped)
-    pub fn playing(&self) -> bool {
-        !self.paused() && !self.stopped()
+    fn set_bus(&mut self, bus: &str) {
+        self.bus.set_name(bus)
+    }
+
+    fn bus(&self) -> String {
+        self.bus.name()
     }
 }
 
@@ -214,3 +728,1502 @@ impl fmt::Debug for Source {
         write!(f, "<Audio source: {:p}>", self)
     }
 }
+
+/// The seeking and looping controls of a `Source`, shared with the
+/// sounds it plays.
+#[derive(Debug, Default)]
+struct PlaybackControls {
+    /// Where to jump to, next time the playing sound checks.
+    seek_to: Option<time::Duration>,
+    loop_points: Option<(time::Duration, time::Duration)>,
+    /// The sound's sample rate, once it's played, for working out
+    /// `Source::elapsed()`.
+    sample_rate: u32,
+    total_duration: Option<time::Duration>,
+}
+
+/// Decodes a `Source`'s sound as it plays, seeking and looping as its
+/// `PlaybackControls` say.  Positions are counted in samples, always
+/// a whole number of frames.
+struct PlaybackSource {
+    data: SoundData,
+    decoder: rodio::Decoder<io::Cursor<SoundData>>,
+    controls: Arc<Mutex<PlaybackControls>>,
+    /// Shared with the `Source`, which reads it for `elapsed()`.
+    frames_played: Arc<AtomicUsize>,
+    repeat: bool,
+    channels: usize,
+    sample_rate: u32,
+    position: usize,
+    requested_loop: Option<(time::Duration, time::Duration)>,
+    loop_points: Option<(usize, usize)>,
+    /// The samples between the loop points, kept the first time
+    /// through to play from after that.
+    loop_buffer: Vec<i16>,
+    /// Which loop points `loop_buffer` is for.
+    buffered_loop: Option<(usize, usize)>,
+    from_buffer: bool,
+    /// A decoder being skipped ahead to `position` on another thread.
+    seeking: Option<mpsc::Receiver<(rodio::Decoder<io::Cursor<SoundData>>, usize)>>,
+    /// Samples of silence played while seeking, so that the sound
+    /// carries on at the start of a frame.
+    silent_samples: usize,
+    ended: bool,
+    until_check: usize,
+}
+
+impl PlaybackSource {
+    fn new(
+        data: SoundData,
+        controls: Arc<Mutex<PlaybackControls>>,
+        frames_played: Arc<AtomicUsize>,
+        repeat: bool,
+    ) -> GameResult<Self> {
+        let decoder = rodio::Decoder::new(io::Cursor::new(data.clone()))?;
+        let channels = usize::from(rodio::Source::channels(&decoder).max(1));
+        let sample_rate = rodio::Source::sample_rate(&decoder);
+        Ok(PlaybackSource {
+            data,
+            decoder,
+            controls,
+            frames_played,
+            repeat,
+            channels,
+            sample_rate,
+            position: 0,
+            requested_loop: None,
+            loop_points: None,
+            loop_buffer: Vec::new(),
+            buffered_loop: None,
+            from_buffer: false,
+            seeking: None,
+            silent_samples: 0,
+            ended: false,
+            until_check: 0,
+        })
+    }
+
+    /// Converts a time into a number of samples.
+    fn samples(&self, time: time::Duration) -> usize {
+        let frames = timer::duration_to_f64(time) * f64::from(self.sample_rate);
+        frames as usize * self.channels
+    }
+
+    /// Picks up any seek or change of loop points.
+    fn check_controls(&mut self) {
+        let (seek_to, requested_loop) = {
+            let mut controls = lock_ignoring_poison(&self.controls);
+            controls.sample_rate = self.sample_rate;
+            (controls.seek_to.take(), controls.loop_points)
+        };
+        if requested_loop != self.requested_loop {
+            if self.from_buffer {
+                let position = self.position;
+                self.decode_from(position);
+            }
+            let loop_points =
+                requested_loop.map(|(start, end)| (self.samples(start), self.samples(end)));
+            self.requested_loop = requested_loop;
+            self.loop_points = loop_points;
+        }
+        if let Some(time) = seek_to {
+            let target = self.samples(time);
+            self.jump_to(target);
+        }
+        let period = time::Duration::from_millis(CONTROL_UPDATE_MILLIS);
+        self.until_check = self.samples(period).max(self.channels);
+    }
+
+    /// Carries on from the given sample, from the loop buffer if it's
+    /// in there, or else by decoding.
+    fn jump_to(&mut self, target: usize) {
+        if let Some((start, end)) = self.loop_points {
+            let buffered =
+                self.buffered_loop == Some((start, end)) && self.loop_buffer.len() == end - start;
+            if buffered && target >= start && target < end {
+                self.from_buffer = true;
+                self.seeking = None;
+                self.position = target;
+                return;
+            }
+        }
+        self.decode_from(target);
+    }
+
+    /// Carries on decoding from the given sample.  Unless that's the
+    /// start, or where the decoder already is, this means starting a
+    /// new decoder on another thread and skipping it ahead to there.
+    fn decode_from(&mut self, target: usize) {
+        let in_place = !self.from_buffer && self.seeking.is_none() && target == self.position;
+        self.from_buffer = false;
+        if in_place {
+            return;
+        }
+        self.seeking = None;
+        self.position = target;
+        let data = self.data.clone();
+        if target == 0 {
+            match rodio::Decoder::new(io::Cursor::new(data)) {
+                Ok(decoder) => self.decoder = decoder,
+                Err(_) => self.ended = true,
+            }
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        let _ = thread::spawn(move || {
+            if let Ok(mut decoder) = rodio::Decoder::new(io::Cursor::new(data)) {
+                let mut position = 0;
+                while position < target && decoder.next().is_some() {
+                    position += 1;
+                }
+                let _ = sender.send((decoder, position));
+            }
+        });
+        self.seeking = Some(receiver);
+        self.silent_samples = 0;
+    }
+
+    /// Picks up the decoder from `decode_from()` once it's ready,
+    /// returning whether there's one to play from.  It's only picked
+    /// up at the start of a frame of silence, to keep the channels in
+    /// order.
+    fn finish_seeking(&mut self) -> bool {
+        if self.silent_samples % self.channels != 0 {
+            return false;
+        }
+        let result = match self.seeking {
+            Some(ref seeking) => seeking.try_recv(),
+            None => return true,
+        };
+        match result {
+            Ok((decoder, position)) => {
+                self.decoder = decoder;
+                self.position = position;
+                self.seeking = None;
+                true
+            }
+            Err(mpsc::TryRecvError::Empty) => false,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.seeking = None;
+                self.ended = true;
+                true
+            }
+        }
+    }
+
+    /// Plays a sample of silence while seeking.
+    fn silence(&mut self) -> Option<i16> {
+        self.silent_samples += 1;
+        self.until_check -= 1;
+        Some(0)
+    }
+
+    /// Gets the sample at `position`, going back to the start if it's
+    /// the end of the sound and we're repeating.
+    fn next_sample(&mut self) -> Option<i16> {
+        if self.from_buffer {
+            let start = self.loop_points.map_or(0, |(start, _)| start);
+            return Some(self.loop_buffer[self.position - start]);
+        }
+        if let Some(sample) = self.decoder.next() {
+            if let Some((start, end)) = self.loop_points {
+                if self.position == start {
+                    self.loop_buffer.clear();
+                    self.buffered_loop = Some((start, end));
+                }
+                let buffering = self.buffered_loop == Some((start, end))
+                    && self.position < end
+                    && self.loop_buffer.len() == self.position - start;
+                if buffering {
+                    self.loop_buffer.push(sample);
+                }
+            }
+            return Some(sample);
+        }
+        match self.loop_points {
+            Some((start, end)) if self.position > start => {
+                // The loop goes past the end of the sound, so it ends
+                // here instead.
+                if self.buffered_loop == Some((start, end)) {
+                    self.buffered_loop = Some((start, self.position));
+                }
+                self.loop_points = Some((start, self.position));
+                self.jump_to(start);
+            }
+            None if self.repeat && self.position > 0 => self.jump_to(0),
+            _ => return None,
+        }
+        if self.ended || self.seeking.is_some() {
+            None
+        } else {
+            self.next_sample()
+        }
+    }
+}
+
+impl Iterator for PlaybackSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.ended {
+            return None;
+        }
+        if self.until_check == 0 {
+            self.check_controls();
+        }
+        if !self.finish_seeking() {
+            return self.silence();
+        }
+        if self.ended {
+            return None;
+        }
+        if let Some((start, end)) = self.loop_points {
+            if self.position == end {
+                self.jump_to(start);
+                if self.seeking.is_some() {
+                    return self.silence();
+                }
+            }
+        }
+        let sample = self.next_sample();
+        if sample.is_none() {
+            if self.seeking.is_some() {
+                return self.silence();
+            }
+            self.ended = true;
+            return None;
+        }
+        self.position += 1;
+        self.until_check -= 1;
+        self.frames_played
+            .store(self.position / self.channels, Ordering::Relaxed);
+        sample
+    }
+}
+
+impl rodio::Source for PlaybackSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+/// The file a `StreamingSource` plays, shared with the decoders
+/// reading it on the audio thread.
+#[derive(Debug)]
+struct StreamState {
+    file: filesystem::File,
+    /// Bumped every time the stream is started or stopped, so that
+    /// decoders from an earlier playback know to finish.
+    generation: u64,
+}
+
+fn lock_stream(state: &Mutex<StreamState>) -> io::Result<MutexGuard<StreamState>> {
+    state
+        .lock()
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "Audio stream lock poisoned"))
+}
+
+/// Reads a `StreamingSource`'s file for one playback of it.  Once the
+/// stream is started again or stopped it acts as if the file ended,
+/// so the decoder using it finishes.
+#[derive(Debug, Clone)]
+struct StreamReader {
+    state: Arc<Mutex<StreamState>>,
+    generation: u64,
+}
+
+impl io::Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = lock_stream(&self.state)?;
+        if state.generation != self.generation {
+            return Ok(0);
+        }
+        state.file.read(buf)
+    }
+}
+
+impl io::Seek for StreamReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let mut state = lock_stream(&self.state)?;
+        if state.generation != self.generation {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Audio stream was restarted",
+            ));
+        }
+        state.file.seek(pos)
+    }
+}
+
+/// A decoder for one playback of a `StreamingSource`.  Repeating is
+/// done by going back to the start of the file and decoding it over
+/// again, since `rodio::Source::repeat_infinite()` keeps every sample
+/// it plays, which is just what streaming is meant to avoid.
+struct StreamDecoder {
+    reader: StreamReader,
+    decoder: rodio::Decoder<StreamReader>,
+    repeat: bool,
+}
+
+impl Iterator for StreamDecoder {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if let Some(sample) = self.decoder.next() {
+            return Some(sample);
+        }
+        if !self.repeat {
+            return None;
+        }
+        let _ = self.reader.seek(io::SeekFrom::Start(0)).ok()?;
+        self.decoder = rodio::Decoder::new(self.reader.clone()).ok()?;
+        self.decoder.next()
+    }
+}
+
+impl rodio::Source for StreamDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        rodio::Source::current_frame_len(&self.decoder)
+    }
+
+    fn channels(&self) -> u16 {
+        rodio::Source::channels(&self.decoder)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        rodio::Source::sample_rate(&self.decoder)
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        if self.repeat {
+            None
+        } else {
+            rodio::Source::total_duration(&self.decoder)
+        }
+    }
+}
+
+/// A source of audio that's decoded from a file a bit at a time as
+/// it plays, rather than being read into memory all at once like a
+/// `Source`.  Use it for music and other long sounds; it takes a
+/// little more work to play, and only plays one copy of itself at a
+/// time.  Will stop playing when dropped.
+pub struct StreamingSource {
+    state: Arc<Mutex<StreamState>>,
+    sink: rodio::Sink,
+    repeat: bool,
+    bus: BusAssignment,
+}
+
+impl StreamingSource {
+    /// Create a new StreamingSource playing the file at the given
+    /// path.  It's opened with `filesystem::open_stream()`, so files in
+    /// the resources zip file are streamed as well.
+    pub fn new<P: AsRef<path::Path>>(context: &mut Context, path: P) -> GameResult<Self> {
+        let file = filesystem::open_stream(context, path)?;
+        StreamingSource::from_file(context, file)
+    }
+
+    /// Creates a new StreamingSource playing the given file.
+    pub fn from_file(context: &mut Context, file: filesystem::File) -> GameResult<Self> {
+        let sink = context.audio_context.new_sink();
+        let state = StreamState {
+            file,
+            generation: 0,
+        };
+        Ok(StreamingSource {
+            state: Arc::new(Mutex::new(state)),
+            sink,
+            repeat: false,
+            bus: BusAssignment::new(&context.audio_context, MUSIC_BUS),
+        })
+    }
+}
+
+impl SoundSource for StreamingSource {
+    /// Plays the StreamingSource from the start, stopping whatever it
+    /// was already playing.
+    fn play(&self) -> GameResult {
+        let reader = {
+            let mut state = lock_stream(&self.state)?;
+            state.generation += 1;
+            let _ = state.file.seek(io::SeekFrom::Start(0))?;
+            StreamReader {
+                state: self.state.clone(),
+                generation: state.generation,
+            }
+        };
+        let decoder = rodio::Decoder::new(reader.clone())?;
+        let source = StreamDecoder {
+            reader,
+            decoder,
+            repeat: self.repeat,
+        };
+        self.bus.append(&self.sink, source);
+        Ok(())
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.repeat = repeat;
+    }
+
+    fn repeat(&self) -> bool {
+        self.repeat
+    }
+
+    fn pause(&self) {
+        self.sink.pause()
+    }
+
+    fn resume(&self) {
+        self.sink.play()
+    }
+
+    /// Stops playback.  Unlike with a `Source`, it can be started
+    /// again with `play()`.
+    fn stop(&self) {
+        if let Ok(mut state) = lock_stream(&self.state) {
+            state.generation += 1;
+        }
+    }
+
+    fn stopped(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.sink.set_volume(value)
+    }
+
+    fn paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn set_bus(&mut self, bus: &str) {
+        self.bus.set_name(bus)
+    }
+
+    fn bus(&self) -> String {
+        self.bus.name()
+    }
+}
+
+impl fmt::Debug for StreamingSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Audio streaming source: {:p}>", self)
+    }
+}
+
+/// Where the listener hearing `SpatialSource`s is and which way
+/// they're facing.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Listener {
+    position: na::Point3<f32>,
+    forward: na::Vector3<f32>,
+    up: na::Vector3<f32>,
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Listener {
+            position: na::Point3::origin(),
+            forward: -na::Vector3::z(),
+            up: na::Vector3::y(),
+        }
+    }
+}
+
+/// How a `SpatialSource` gets quieter the further it is from the
+/// listener.  Distances are in whatever units you give positions in,
+/// so for a 2D game they'd usually be pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Attenuation {
+    /// Always full volume, however far away.
+    None,
+    /// Full volume up to `reference_distance`, then getting quieter
+    /// in inverse proportion to the distance, more quickly the bigger
+    /// `rolloff` is.  This is how sound really behaves, with a
+    /// `rolloff` of 1.0.
+    Inverse {
+        /// The distance sounds start getting quieter at.
+        reference_distance: f32,
+        /// How quickly sounds get quieter past that.
+        rolloff: f32,
+    },
+    /// Full volume up to `reference_distance`, then getting quieter
+    /// at a steady rate until it's silent at `max_distance`.  The
+    /// easiest to reason about, since sounds past a certain distance
+    /// can't be heard at all.
+    Linear {
+        /// The distance sounds start getting quieter at.
+        reference_distance: f32,
+        /// The distance sounds can't be heard past.
+        max_distance: f32,
+    },
+    /// Full volume up to `reference_distance`, then the volume is
+    /// `(distance / reference_distance)` to the power of `-rolloff`.
+    Exponential {
+        /// The distance sounds start getting quieter at.
+        reference_distance: f32,
+        /// How quickly sounds get quieter past that.
+        rolloff: f32,
+    },
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Attenuation::Inverse {
+            reference_distance: 1.0,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl Attenuation {
+    /// Returns the volume, between 0.0 and 1.0, that a sound at the
+    /// given distance is played at.
+    pub fn gain(&self, distance: f32) -> f32 {
+        match *self {
+            Attenuation::None => 1.0,
+            Attenuation::Inverse {
+                reference_distance,
+                rolloff,
+            } => {
+                let distance = distance.max(reference_distance);
+                let falloff = reference_distance + rolloff * (distance - reference_distance);
+                if falloff > 0.0 {
+                    (reference_distance / falloff).min(1.0)
+                } else {
+                    1.0
+                }
+            }
+            Attenuation::Linear {
+                reference_distance,
+                max_distance,
+            } => {
+                if distance <= reference_distance {
+                    1.0
+                } else if distance >= max_distance {
+                    0.0
+                } else {
+                    1.0 - (distance - reference_distance) / (max_distance - reference_distance)
+                }
+            }
+            Attenuation::Exponential {
+                reference_distance,
+                rolloff,
+            } => {
+                if distance <= reference_distance || reference_distance <= 0.0 {
+                    1.0
+                } else {
+                    (distance / reference_distance).powf(-rolloff).min(1.0)
+                }
+            }
+        }
+    }
+}
+
+/// Where a `SpatialSource` is and how it fades with distance.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Emitter {
+    position: na::Point3<f32>,
+    attenuation: Attenuation,
+}
+
+/// Works out the volume of the left and right channels for a sound
+/// from the given emitter, using constant-power panning so sounds
+/// don't get quieter as they pass in front of the listener.
+fn spatial_gains(listener: &Listener, emitter: &Emitter) -> (f32, f32) {
+    let offset = emitter.position - listener.position;
+    let distance = offset.norm();
+    let gain = emitter.attenuation.gain(distance);
+    let right = listener.forward.cross(&listener.up);
+    let pan = match (offset.try_normalize(1.0e-6), right.try_normalize(1.0e-6)) {
+        (Some(direction), Some(right)) => direction.dot(&right),
+        _ => 0.0,
+    };
+    let angle = (pan + 1.0) * f32::consts::FRAC_PI_4;
+    (gain * angle.cos(), gain * angle.sin())
+}
+
+/// A source of audio with a position, which is panned between the
+/// left and right speakers depending on where it is relative to the
+/// listener set with `AudioContext::set_listener_position()`, and
+/// gets quieter with distance according to its `Attenuation`.  Moving
+/// it or the listener while it's playing takes effect straight away.
+///
+/// Sounds are mixed down to mono before being positioned.
+pub struct SpatialSource {
+    data: io::Cursor<SoundData>,
+    sink: rodio::Sink,
+    repeat: bool,
+    emitter: Arc<Mutex<Emitter>>,
+    listener: Arc<Mutex<Listener>>,
+    bus: BusAssignment,
+    /// Stops the sounds it plays, without stopping the sink for good.
+    voice: Arc<Mutex<VoiceState>>,
+}
+
+impl SpatialSource {
+    /// Create a new SpatialSource from the given file, positioned at
+    /// the origin.
+    pub fn new<P: AsRef<path::Path>>(context: &mut Context, path: P) -> GameResult<Self> {
+        let path = path.as_ref();
+        let data = SoundData::new(context, path)?;
+        SpatialSource::from_data(context, data)
+    }
+
+    /// Creates a new SpatialSource using the given SoundData object,
+    /// positioned at the origin.
+    pub fn from_data(context: &mut Context, data: SoundData) -> GameResult<Self> {
+        let sink = context.audio_context.new_sink();
+        Ok(SpatialSource::with_sink(&context.audio_context, data, sink))
+    }
+
+    fn with_sink(audio: &AudioContext, data: SoundData, sink: rodio::Sink) -> Self {
+        let emitter = Emitter {
+            position: na::Point3::origin(),
+            attenuation: Attenuation::default(),
+        };
+        let voice = VoiceState {
+            volume: 1.0,
+            pitch: 1.0,
+            fade: None,
+            stopped: false,
+            finished: false,
+            generation: 0,
+        };
+        SpatialSource {
+            data: io::Cursor::new(data),
+            sink,
+            repeat: false,
+            emitter: Arc::new(Mutex::new(emitter)),
+            listener: audio.listener.clone(),
+            bus: BusAssignment::new(audio, SFX_BUS),
+            voice: Arc::new(Mutex::new(voice)),
+        }
+    }
+
+    /// Sets where the sound is coming from.
+    pub fn set_position<P>(&mut self, position: P)
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        lock_ignoring_poison(&self.emitter).position = na::Point3::from(position.into());
+    }
+
+    /// Gets where the sound is coming from.
+    pub fn position(&self) -> mint::Point3<f32> {
+        lock_ignoring_poison(&self.emitter).position.into()
+    }
+
+    /// Sets how the sound gets quieter with distance.
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        lock_ignoring_poison(&self.emitter).attenuation = attenuation;
+    }
+
+    /// Gets how the sound gets quieter with distance.
+    pub fn attenuation(&self) -> Attenuation {
+        lock_ignoring_poison(&self.emitter).attenuation
+    }
+
+    /// Returns the volume of the left and right channels that the
+    /// sound would be played at right now, not counting `volume()`.
+    pub fn channel_volumes(&self) -> (f32, f32) {
+        let listener = *lock_ignoring_poison(&self.listener);
+        let emitter = *lock_ignoring_poison(&self.emitter);
+        spatial_gains(&listener, &emitter)
+    }
+
+    /// Plays the given sound positioned by our emitter, much like
+    /// `rodio::SpatialSink` does.
+    fn append_spatial<S>(&self, source: S)
+    where
+        S: rodio::Source + Send + 'static,
+        S::Item: rodio::Sample + Send,
+    {
+        use rodio::Source;
+        let (left, right) = self.channel_volumes();
+        let emitter = self.emitter.clone();
+        let listener = self.listener.clone();
+        let period = time::Duration::from_millis(CONTROL_UPDATE_MILLIS);
+        let volumes = rodio::source::ChannelVolume::new(source, vec![left, right]);
+        let source = volumes.periodic_access(period, move |source| {
+            let listener = *lock_ignoring_poison(&listener);
+            let emitter = *lock_ignoring_poison(&emitter);
+            let (left, right) = spatial_gains(&listener, &emitter);
+            source.set_volume(0, left);
+            source.set_volume(1, right);
+        });
+        self.bus.append(&self.sink, source);
+    }
+}
+
+impl SoundSource for SpatialSource {
+    fn play(&self) -> GameResult {
+        use rodio::Source;
+        let decoder = rodio::Decoder::new(self.data.clone())?;
+        lock_ignoring_poison(&self.voice).restart();
+        let voice = self.voice.clone();
+        if self.repeat {
+            self.append_spatial(VoiceSource::new(decoder.repeat_infinite(), voice, 1.0));
+        } else {
+            self.append_spatial(VoiceSource::new(decoder, voice, 1.0));
+        }
+        Ok(())
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.repeat = repeat;
+    }
+
+    fn repeat(&self) -> bool {
+        self.repeat
+    }
+
+    fn pause(&self) {
+        self.sink.pause()
+    }
+
+    fn resume(&self) {
+        self.sink.play()
+    }
+
+    fn stop(&self) {
+        lock_ignoring_poison(&self.voice).stopped = true;
+    }
+
+    fn stopped(&self) -> bool {
+        lock_ignoring_poison(&self.voice).stopped || self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.sink.set_volume(value)
+    }
+
+    fn paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    fn set_bus(&mut self, bus: &str) {
+        self.bus.set_name(bus)
+    }
+
+    fn bus(&self) -> String {
+        self.bus.name()
+    }
+}
+
+impl fmt::Debug for SpatialSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Audio spatial source: {:p}>", self)
+    }
+}
+
+/// What `play_oneshot()` does when a sound is already playing as many
+/// times at once as its `PlayParams::max_voices` allows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VoiceStealing {
+    /// Stop the copy that's been playing longest to make room.
+    Oldest,
+    /// Stop the quietest copy to make room.
+    Quietest,
+    /// Don't play the new copy.
+    Reject,
+}
+
+impl Default for VoiceStealing {
+    fn default() -> Self {
+        VoiceStealing::Oldest
+    }
+}
+
+/// A builder structure containing settings for a sound played with
+/// `play_oneshot()`.
+///
+/// Defaults:
+///
+/// ```rust,ignore
+/// PlayParams {
+///     volume: 1.0,
+///     pitch: 1.0,
+///     pitch_variation: 0.0,
+///     bus: SFX_BUS.to_owned(),
+///     max_voices: 8,
+///     stealing: VoiceStealing::Oldest,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayParams {
+    /// How loud to play the sound.
+    pub volume: f32,
+    /// How fast to play the sound, which also changes its pitch: 2.0
+    /// plays it twice as fast and an octave higher.
+    pub pitch: f32,
+    /// How much to vary the pitch at random, as a fraction either way;
+    /// see `Source::set_pitch_variation()`.
+    pub pitch_variation: f32,
+    /// The mixer bus to play the sound through.
+    pub bus: String,
+    /// The most copies of the same `SoundData` that `play_oneshot()`
+    /// plays at once; 0 means no limit.
+    pub max_voices: usize,
+    /// What to do when there are already `max_voices` copies playing.
+    pub stealing: VoiceStealing,
+}
+
+impl Default for PlayParams {
+    fn default() -> Self {
+        PlayParams {
+            volume: 1.0,
+            pitch: 1.0,
+            pitch_variation: 0.0,
+            bus: SFX_BUS.to_owned(),
+            max_voices: 8,
+            stealing: VoiceStealing::Oldest,
+        }
+    }
+}
+
+impl PlayParams {
+    /// Same as `PlayParams::default()`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the volume.
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Set the pitch.
+    pub fn pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// Set how much the pitch varies at random.
+    pub fn pitch_variation(mut self, variation: f32) -> Self {
+        self.pitch_variation = variation;
+        self
+    }
+
+    /// Set the mixer bus.
+    pub fn bus(mut self, bus: &str) -> Self {
+        self.bus = bus.to_owned();
+        self
+    }
+
+    /// Set the most copies of the sound that can play at once, and
+    /// what to do when there are already that many.
+    pub fn max_voices(mut self, max_voices: usize, stealing: VoiceStealing) -> Self {
+        self.max_voices = max_voices;
+        self.stealing = stealing;
+        self
+    }
+}
+
+/// Picks a random pitch multiplier up to `variation` either side of 1.0.
+fn random_pitch_scale(variation: f32) -> f32 {
+    if variation > 0.0 {
+        rand::thread_rng().gen_range(1.0 - variation, 1.0 + variation)
+    } else {
+        1.0
+    }
+}
+
+/// A volume fade in progress.
+#[derive(Debug, Copy, Clone)]
+struct Fade {
+    from: f32,
+    to: f32,
+    /// How much of the fade has been played so far.
+    played: time::Duration,
+    duration: time::Duration,
+    stop_after: bool,
+}
+
+/// The volume, pitch and fading of a sound played with
+/// `play_oneshot()` or by a `Source`, shared by the `Voice` handle or
+/// `Source` and the sound itself.
+#[derive(Debug)]
+struct VoiceState {
+    volume: f32,
+    pitch: f32,
+    fade: Option<Fade>,
+    /// Set from the `Voice`, or when the sound is stolen.
+    stopped: bool,
+    /// Set once the sound runs out of data.
+    finished: bool,
+    /// Bumped whenever it's restarted after stopping, so that sounds
+    /// still queued from before don't start up again too.
+    generation: u64,
+}
+
+impl VoiceState {
+    /// Moves any fade along by the given amount of time played.  This
+    /// goes by what's actually been played rather than the clock, so
+    /// a fade waits while the sound is paused.
+    fn advance_fade(&mut self, played: time::Duration) {
+        if let Some(ref mut fade) = self.fade {
+            fade.played += played;
+        }
+    }
+
+    /// Finishes any fade that's been played through, returning the
+    /// volume to play at right now.
+    fn current_volume(&mut self) -> f32 {
+        if let Some(fade) = self.fade {
+            if fade.played < fade.duration {
+                let t = timer::duration_to_f64(fade.played) / timer::duration_to_f64(fade.duration);
+                return fade.from + (fade.to - fade.from) * t as f32;
+            }
+            self.volume = fade.to;
+            self.fade = None;
+            if fade.stop_after {
+                self.stopped = true;
+            }
+        }
+        self.volume
+    }
+
+    /// Starts fading from the current volume to the given one.
+    fn fade(&mut self, to: f32, duration: time::Duration, stop_after: bool) {
+        let from = self.current_volume();
+        self.fade = Some(Fade {
+            from,
+            to,
+            played: time::Duration::from_secs(0),
+            duration,
+            stop_after,
+        });
+    }
+
+    /// Gets ready to play again after being stopped or faded out,
+    /// keeping any fade in that's been set up.
+    fn restart(&mut self) {
+        if self.done() {
+            self.stopped = false;
+            self.finished = false;
+            self.volume = 1.0;
+            self.generation += 1;
+            if self.fade.map_or(false, |fade| fade.stop_after) {
+                self.fade = None;
+            }
+        }
+    }
+
+    /// Returns whether the sound has stopped, or will as soon as it
+    /// notices its fade out has finished.
+    fn done(&self) -> bool {
+        let faded_out = self.fade.map_or(false, |fade| {
+            fade.stop_after && fade.played >= fade.duration
+        });
+        self.stopped || self.finished || faded_out
+    }
+}
+
+/// Plays a sound for `play_oneshot()`, checking its `VoiceState` every
+/// `CONTROL_UPDATE_MILLIS`.  Frames are cut short at each check, so
+/// that a change of pitch (which is done by changing the sample rate)
+/// is picked up straight away.
+struct VoiceSource<S> {
+    input: S,
+    state: Arc<Mutex<VoiceState>>,
+    gain: f32,
+    pitch: f32,
+    /// This copy's random pitch variation.
+    pitch_scale: f32,
+    stopped: bool,
+    /// The `VoiceState::generation` this copy was started in.
+    generation: u64,
+    /// Whether it's played anything yet.  It may have been queued up
+    /// behind other sounds for a while, so it checks its `VoiceState`
+    /// again when it starts.
+    started: bool,
+    until_update: usize,
+    /// Samples played since the last update.
+    played: usize,
+}
+
+impl<S> VoiceSource<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn new(input: S, state: Arc<Mutex<VoiceState>>, pitch_scale: f32) -> Self {
+        let generation = lock_ignoring_poison(&state).generation;
+        let mut source = VoiceSource {
+            input,
+            state,
+            gain: 1.0,
+            pitch: 1.0,
+            pitch_scale,
+            stopped: false,
+            generation,
+            started: false,
+            until_update: 0,
+            played: 0,
+        };
+        source.update();
+        source
+    }
+
+    fn update(&mut self) {
+        let channels = usize::from(self.input.channels().max(1));
+        let sample_rate = rodio::Source::sample_rate(self);
+        let frames = (self.played / channels) as u64;
+        let played = time::Duration::from_nanos(frames * 1_000_000_000 / u64::from(sample_rate));
+        self.played = 0;
+        let mut state = lock_ignoring_poison(&self.state);
+        state.advance_fade(played);
+        self.gain = state.current_volume();
+        self.pitch = state.pitch * self.pitch_scale;
+        self.stopped = state.stopped || state.generation != self.generation;
+        let frames = u64::from(self.input.sample_rate()) * CONTROL_UPDATE_MILLIS / 1000;
+        self.until_update = (frames as usize).max(1) * channels;
+    }
+}
+
+impl<S> Iterator for VoiceSource<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        if !self.started {
+            self.started = true;
+            self.update();
+        }
+        if self.stopped {
+            return None;
+        }
+        match self.input.next() {
+            Some(sample) => {
+                self.until_update -= 1;
+                self.played += 1;
+                if self.until_update == 0 {
+                    self.update();
+                }
+                Some(rodio::Sample::amplify(sample, self.gain))
+            }
+            None => {
+                lock_ignoring_poison(&self.state).finished = true;
+                self.stopped = true;
+                None
+            }
+        }
+    }
+}
+
+impl<S> rodio::Source for VoiceSource<S>
+where
+    S: rodio::Source,
+    S::Item: rodio::Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.stopped {
+            return Some(0);
+        }
+        match self.input.current_frame_len() {
+            Some(len) => Some(len.min(self.until_update)),
+            None => Some(self.until_update),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        ((self.input.sample_rate() as f32 * self.pitch) as u32).max(1)
+    }
+
+    fn total_duration(&self) -> Option<time::Duration> {
+        None
+    }
+}
+
+/// A handle to a sound played with `play_oneshot()`.  The sound keeps
+/// playing if this is dropped.
+#[derive(Debug)]
+pub struct Voice {
+    state: Arc<Mutex<VoiceState>>,
+}
+
+impl Voice {
+    fn state(&self) -> MutexGuard<VoiceState> {
+        lock_ignoring_poison(&self.state)
+    }
+
+    /// Stops the sound.
+    pub fn stop(&self) {
+        self.state().stopped = true;
+    }
+
+    /// Returns whether the sound has stopped, whether because it was
+    /// stopped, was stolen by another copy of itself, was rejected by
+    /// `VoiceStealing::Reject`, or just finished.
+    pub fn stopped(&self) -> bool {
+        self.state().done()
+    }
+
+    /// Gets the volume the sound is playing at, part way through any
+    /// fade.
+    pub fn volume(&self) -> f32 {
+        self.state().current_volume()
+    }
+
+    /// Sets the volume, cancelling any fade.
+    pub fn set_volume(&mut self, volume: f32) {
+        let mut state = self.state();
+        state.fade = None;
+        state.volume = volume;
+    }
+
+    /// Fades from the current volume to the given one over the given
+    /// time.
+    pub fn fade_to(&mut self, volume: f32, duration: time::Duration) {
+        self.state().fade(volume, duration, false);
+    }
+
+    /// Fades the sound out over the given time, then stops it.
+    pub fn fade_out(&mut self, duration: time::Duration) {
+        self.state().fade(0.0, duration, true);
+    }
+
+    /// Gets the pitch the sound is playing at.
+    pub fn pitch(&self) -> f32 {
+        self.state().pitch
+    }
+
+    /// Changes the pitch, and speed, of the sound.
+    pub fn set_pitch(&mut self, pitch: f32) {
+        self.state().pitch = pitch;
+    }
+}
+
+/// Plays a copy of the sound that carries on by itself, without a
+/// `Source` to keep alive, so that the same sound effect can overlap
+/// itself without managing a pool of `Source`s.  Returns a `Voice`
+/// that can stop, fade or re-pitch it, which can just be dropped if
+/// you don't need it.
+///
+/// At most `params.max_voices` copies of the same `SoundData` (or
+/// clones of it) play at once; beyond that, one is stopped to make
+/// room, or the new one isn't played, according to `params.stealing`.
+/// If it isn't played, the returned `Voice` is already stopped.
+pub fn play_oneshot(
+    context: &mut Context,
+    data: &SoundData,
+    params: PlayParams,
+) -> GameResult<Voice> {
+    let audio = &mut context.audio_context;
+    let decoder = rodio::Decoder::new(io::Cursor::new(data.clone()))?;
+    let state = Arc::new(Mutex::new(VoiceState {
+        volume: params.volume,
+        pitch: params.pitch,
+        fade: None,
+        stopped: false,
+        finished: false,
+        generation: 0,
+    }));
+    audio.voices.retain(|_, voices| {
+        voices.retain(|voice| !lock_ignoring_poison(voice).done());
+        !voices.is_empty()
+    });
+    {
+        let voices = audio.voices.entry(data.key()).or_insert_with(Vec::new);
+        if params.max_voices > 0 && voices.len() >= params.max_voices {
+            let victim = match params.stealing {
+                VoiceStealing::Oldest => 0,
+                VoiceStealing::Quietest => {
+                    let mut quietest = 0;
+                    let mut quietest_volume = f32::INFINITY;
+                    for (i, voice) in voices.iter().enumerate() {
+                        let volume = lock_ignoring_poison(voice).current_volume();
+                        if volume < quietest_volume {
+                            quietest = i;
+                            quietest_volume = volume;
+                        }
+                    }
+                    quietest
+                }
+                VoiceStealing::Reject => {
+                    lock_ignoring_poison(&state).stopped = true;
+                    return Ok(Voice { state });
+                }
+            };
+            lock_ignoring_poison(&voices.remove(victim)).stopped = true;
+        }
+        voices.push(state.clone());
+    }
+    let sink = audio.new_sink();
+    let bus = BusAssignment::new(audio, &params.bus);
+    let pitch_scale = random_pitch_scale(params.pitch_variation);
+    bus.append(&sink, VoiceSource::new(decoder, state.clone(), pitch_scale));
+    sink.detach();
+    Ok(Voice { state })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes a mono 16-bit WAV file at 1000 Hz whose samples count up
+    /// from 0, so that where playback is can be read off the samples.
+    fn counting_wav(len: u32) -> SoundData {
+        fn put(wav: &mut Vec<u8>, value: u32, bytes: u32) {
+            for i in 0..bytes {
+                wav.push((value >> (8 * i)) as u8);
+            }
+        }
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        put(&mut wav, 36 + len * 2, 4);
+        wav.extend_from_slice(b"WAVEfmt ");
+        put(&mut wav, 16, 4);
+        put(&mut wav, 1, 2);
+        put(&mut wav, 1, 2);
+        put(&mut wav, 1000, 4);
+        put(&mut wav, 2000, 4);
+        put(&mut wav, 2, 2);
+        put(&mut wav, 16, 2);
+        wav.extend_from_slice(b"data");
+        put(&mut wav, len * 2, 4);
+        for sample in 0..len {
+            put(&mut wav, sample, 2);
+        }
+        SoundData::from(wav)
+    }
+
+    fn playback(
+        data: &SoundData,
+        controls: PlaybackControls,
+        repeat: bool,
+    ) -> (PlaybackSource, Arc<AtomicUsize>) {
+        let frames_played = Arc::new(AtomicUsize::new(0));
+        let controls = Arc::new(Mutex::new(controls));
+        let source =
+            PlaybackSource::new(data.clone(), controls, frames_played.clone(), repeat).unwrap();
+        (source, frames_played)
+    }
+
+    #[test]
+    fn headless_playback_seeks_and_loops() {
+        let data = counting_wav(100);
+        let ms = time::Duration::from_millis;
+
+        let (source, frames_played) = playback(&data, PlaybackControls::default(), false);
+        assert!(source.eq(0..100));
+        assert_eq!(frames_played.load(Ordering::Relaxed), 100);
+
+        let (source, _) = playback(&data, PlaybackControls::default(), true);
+        assert!(source.take(150).eq((0..100).chain(0..50)));
+
+        let controls = PlaybackControls {
+            seek_to: Some(ms(80)),
+            ..PlaybackControls::default()
+        };
+        // The seek is done on another thread, with silence until it's
+        // ready.
+        let (source, _) = playback(&data, controls, false);
+        assert!(source.skip_while(|&sample| sample == 0).eq(80..100));
+
+        let controls = PlaybackControls {
+            loop_points: Some((ms(20), ms(50))),
+            ..PlaybackControls::default()
+        };
+        let (source, frames_played) = playback(&data, controls, false);
+        let expected = (0..50).chain(20..50).chain(20..50).chain(20..30);
+        assert!(source.take(130).eq(expected));
+        assert_eq!(frames_played.load(Ordering::Relaxed), 30);
+
+        // A loop that runs past the end of the sound ends with it.
+        let controls = PlaybackControls {
+            loop_points: Some((ms(90), ms(200))),
+            ..PlaybackControls::default()
+        };
+        let (source, _) = playback(&data, controls, false);
+        assert!(source.take(120).eq((0..100).chain(90..100).chain(90..100)));
+    }
+
+    #[test]
+    fn headless_voice_source_follows_its_controls() {
+        let state = Arc::new(Mutex::new(VoiceState {
+            volume: 0.5,
+            pitch: 2.0,
+            fade: None,
+            stopped: false,
+            finished: false,
+            generation: 0,
+        }));
+        let input = rodio::buffer::SamplesBuffer::new(2, 1000, vec![100i16; 100]);
+        let mut source = VoiceSource::new(input, state.clone(), 1.0);
+        assert_eq!(rodio::Source::sample_rate(&source), 2000);
+        assert_eq!(rodio::Source::current_frame_len(&source), Some(20));
+        assert_eq!(source.next(), Some(50));
+
+        lock_ignoring_poison(&state).pitch = 1.0;
+        for _ in 0..19 {
+            let _ = source.next();
+        }
+        assert_eq!(rodio::Source::sample_rate(&source), 1000);
+        lock_ignoring_poison(&state).stopped = true;
+        assert_eq!(source.by_ref().take(30).count(), 20);
+        assert_eq!(source.next(), None);
+        assert!(!lock_ignoring_poison(&state).finished);
+    }
+
+    #[test]
+    fn headless_queued_voices_notice_stops_when_they_start() {
+        let state = Arc::new(Mutex::new(VoiceState {
+            volume: 1.0,
+            pitch: 1.0,
+            fade: None,
+            stopped: false,
+            finished: false,
+            generation: 0,
+        }));
+        let input = rodio::buffer::SamplesBuffer::new(1, 1000, vec![100i16; 100]);
+        let mut queued = VoiceSource::new(input, state.clone(), 1.0);
+        lock_ignoring_poison(&state).stopped = true;
+        assert_eq!(queued.next(), None);
+    }
+
+    #[test]
+    fn headless_voice_fades_follow_frames_played() {
+        let state = Arc::new(Mutex::new(VoiceState {
+            volume: 1.0,
+            pitch: 1.0,
+            fade: None,
+            stopped: false,
+            finished: false,
+            generation: 0,
+        }));
+        lock_ignoring_poison(&state).fade(0.0, time::Duration::from_millis(20), true);
+        let input = rodio::buffer::SamplesBuffer::new(1, 1000, vec![100i16; 100]);
+        let mut source = VoiceSource::new(input, state.clone(), 1.0);
+
+        // Nothing's been played yet, however long we wait.
+        ::std::thread::sleep(time::Duration::from_millis(30));
+        assert_eq!(lock_ignoring_poison(&state).current_volume(), 1.0);
+
+        // Half the fade has been played after 10ms worth of frames.
+        assert_eq!(source.by_ref().take(10).count(), 10);
+        assert_eq!(lock_ignoring_poison(&state).current_volume(), 0.5);
+        assert!(!lock_ignoring_poison(&state).done());
+        assert_eq!(source.by_ref().take(10).count(), 10);
+        assert!(lock_ignoring_poison(&state).done());
+        assert_eq!(source.next(), None);
+
+        // Restarting doesn't bring back copies from before it stopped.
+        lock_ignoring_poison(&state).restart();
+        let input = rodio::buffer::SamplesBuffer::new(1, 1000, vec![100i16; 100]);
+        let mut old = VoiceSource::new(input, state.clone(), 1.0);
+        {
+            let mut state = lock_ignoring_poison(&state);
+            state.stopped = true;
+            state.restart();
+        }
+        assert_eq!(old.next(), None);
+        assert!(!lock_ignoring_poison(&state).done());
+    }
+
+    #[test]
+    fn headless_spatial_source_plays_again_after_stopping() {
+        let audio = AudioContext::new_headless();
+        let (sink, mut output) = rodio::Sink::new_idle();
+        let data = SoundData::from_bytes(include_bytes!("../resources/pew.ogg"));
+        let source = SpatialSource::with_sink(&audio, data, sink);
+        source.play().unwrap();
+        source.stop();
+        assert!(source.stopped());
+        source.play().unwrap();
+        assert!(source.playing());
+        assert!(output.by_ref().take(44100).any(|sample| sample != 0.0));
+    }
+
+    #[test]
+    fn headless_mixer_output_scales_by_master_volume() {
+        let mut mixer = Mixer::default();
+        mixer.master_volume = 0.5;
+        mixer.bus_mut(MUSIC_BUS).volume = 0.5;
+        assert_eq!(mixer.output(MUSIC_BUS), (0.25, false));
+        assert_eq!(mixer.output(SFX_BUS), (0.5, false));
+        assert_eq!(mixer.output("new bus"), (0.5, false));
+        mixer.bus_mut(SFX_BUS).muted = true;
+        mixer.bus_mut(SFX_BUS).paused = true;
+        assert_eq!(mixer.output(SFX_BUS), (0.0, true));
+        assert!(!mixer.buses.contains_key("new bus"));
+    }
+
+    #[test]
+    fn headless_attenuation_models() {
+        let inverse = Attenuation::Inverse {
+            reference_distance: 10.0,
+            rolloff: 1.0,
+        };
+        assert_eq!(inverse.gain(5.0), 1.0);
+        assert_eq!(inverse.gain(20.0), 0.5);
+        let linear = Attenuation::Linear {
+            reference_distance: 10.0,
+            max_distance: 110.0,
+        };
+        assert_eq!(linear.gain(0.0), 1.0);
+        assert_eq!(linear.gain(60.0), 0.5);
+        assert_eq!(linear.gain(200.0), 0.0);
+        let exponential = Attenuation::Exponential {
+            reference_distance: 10.0,
+            rolloff: 2.0,
+        };
+        assert_eq!(exponential.gain(20.0), 0.25);
+        assert_eq!(Attenuation::None.gain(1.0e6), 1.0);
+    }
+
+    #[test]
+    fn headless_spatial_gains_pan_with_listener() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1.0e-5;
+        let mut listener = Listener::default();
+        let mut emitter = Emitter {
+            position: na::Point3::new(100.0, 0.0, 0.0),
+            attenuation: Attenuation::None,
+        };
+        let (left, right) = spatial_gains(&listener, &emitter);
+        assert!(close(left, 0.0) && close(right, 1.0));
+
+        // Straight ahead is split evenly, at the same total power.
+        emitter.position = na::Point3::new(0.0, 0.0, -100.0);
+        let (left, right) = spatial_gains(&listener, &emitter);
+        assert!(close(left, right) && close(left * left + right * right, 1.0));
+
+        // Turning around swaps the sides.
+        listener.forward = na::Vector3::z();
+        emitter.position = na::Point3::new(100.0, 0.0, 0.0);
+        let (left, right) = spatial_gains(&listener, &emitter);
+        assert!(close(left, 1.0) && close(right, 0.0));
+    }
+}
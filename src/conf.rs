@@ -10,6 +10,7 @@
 //! rest be default) and provides a nice way to specify settings that
 //! can be tweaked such as window resolution, multisampling options, etc.
 
+use std::collections::BTreeMap;
 use std::io;
 use toml;
 
@@ -288,6 +289,167 @@ impl NumSamples {
     }
 }
 
+/// How `event::run()` calls `EventHandler::update()`.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, SmartDefault)]
+#[serde(tag = "type")]
+pub enum UpdateMode {
+    /// Call `update()` exactly once per frame, however long the
+    /// frame took.  Use `timer::get_delta()` to scale your logic.
+    #[default]
+    Variable,
+    /// Call `update()` as many times per frame as needed to run
+    /// at a fixed rate, carrying leftover time to the next frame.
+    /// `timer::get_update_alpha()` then tells `draw()` how far
+    /// between two updates it is.
+    ///
+    /// If the game falls far enough behind that it would need more
+    /// than `max_updates_per_frame` updates to catch up, the excess
+    /// time is dropped instead, so a slow frame can't snowball
+    /// into ever-slower ones.
+    Fixed {
+        /// How many updates to run per second.  Building a `Context`
+        /// fails if this is 0.
+        #[default = r#"60"#]
+        updates_per_second: u32,
+        /// The most updates to run in a single frame.
+        #[default = r#"5"#]
+        max_updates_per_frame: u32,
+    },
+}
+
+/// A builder structure containing settings for how `event::run()`
+/// drives the game's main loop.
+///
+/// Defaults:
+///
+/// ```rust,ignore
+/// RunSetup {
+///     update_mode: UpdateMode::Variable,
+///     fps_limit: 0,
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, SmartDefault, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RunSetup {
+    /// How often `update()` is called.
+    #[default = r#"UpdateMode::Variable"#]
+    pub update_mode: UpdateMode,
+    /// The most frames per second to draw; 0 means no limit.
+    /// When set, `event::run()` sleeps off whatever is left of each
+    /// frame's time budget after drawing it.  Vsync is still the
+    /// better way to cap your framerate if you can use it, but this
+    /// keeps the game from burning 100% CPU without it.
+    #[default = r#"0"#]
+    pub fps_limit: u32,
+}
+
+impl RunSetup {
+    /// Call `update()` once per frame.
+    pub fn variable_update(mut self) -> Self {
+        self.update_mode = UpdateMode::Variable;
+        self
+    }
+
+    /// Call `update()` at a fixed rate, running at most
+    /// `max_updates_per_frame` updates in one frame.
+    /// `updates_per_second` must be more than 0, or building the
+    /// `Context` fails.
+    pub fn fixed_update(mut self, updates_per_second: u32, max_updates_per_frame: u32) -> Self {
+        self.update_mode = UpdateMode::Fixed {
+            updates_per_second,
+            max_updates_per_frame,
+        };
+        self
+    }
+
+    /// Set the maximum frames per second; 0 means no limit.
+    pub fn fps_limit(mut self, fps_limit: u32) -> Self {
+        self.fps_limit = fps_limit;
+        self
+    }
+}
+
+/// The settings of one audio mixer bus.
+///
+/// Defaults:
+///
+/// ```rust,ignore
+/// BusSetup {
+///     volume: 1.0,
+///     muted: false,
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, SmartDefault, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BusSetup {
+    /// How loud the bus is, from 0.0 for silent to 1.0 for full
+    /// volume.
+    #[default = r#"1.0"#]
+    pub volume: f32,
+    /// Whether the bus is silenced, without forgetting its volume.
+    #[default = r#"false"#]
+    pub muted: bool,
+}
+
+fn default_buses() -> BTreeMap<String, BusSetup> {
+    let mut buses = BTreeMap::new();
+    let _ = buses.insert("music".to_owned(), BusSetup::default());
+    let _ = buses.insert("sfx".to_owned(), BusSetup::default());
+    buses
+}
+
+/// A builder structure containing the volumes of the audio mixer,
+/// so that they can be saved and restored along with the rest of the
+/// game's settings.  See `audio::AudioContext` for how buses are used.
+///
+/// Defaults:
+///
+/// ```rust,ignore
+/// AudioSetup {
+///     master_volume: 1.0,
+///     buses: {
+///         "music": BusSetup::default(),
+///         "sfx": BusSetup::default(),
+///     },
+/// }
+/// ```
+#[derive(Debug, Clone, SmartDefault, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AudioSetup {
+    /// The volume every bus is scaled by.
+    #[default = r#"1.0"#]
+    pub master_volume: f32,
+    /// The settings of each bus, by name.  Buses not listed here
+    /// start out with the default settings.
+    #[default = r#"default_buses()"#]
+    pub buses: BTreeMap<String, BusSetup>,
+}
+
+impl AudioSetup {
+    /// Set the master volume.
+    pub fn master_volume(mut self, volume: f32) -> Self {
+        self.master_volume = volume;
+        self
+    }
+
+    /// Set the volume of the named bus.
+    pub fn bus_volume(mut self, bus: &str, volume: f32) -> Self {
+        self.buses
+            .entry(bus.to_owned())
+            .or_insert_with(BusSetup::default)
+            .volume = volume;
+        self
+    }
+
+    /// Set whether the named bus is muted.
+    pub fn bus_muted(mut self, bus: &str, muted: bool) -> Self {
+        self.buses
+            .entry(bus.to_owned())
+            .or_insert_with(BusSetup::default)
+            .muted = muted;
+        self
+    }
+}
+
 /// A structure containing configuration data
 /// for the game engine.
 ///
@@ -298,6 +460,8 @@ impl NumSamples {
 ///     window_mode: WindowMode::default(),
 ///     window_setup: WindowSetup::default(),
 ///     backend: Backend::OpenGL{ major: 3, minor: 2, srgb: true},
+///     run_setup: RunSetup::default(),
+///     audio_setup: AudioSetup::default(),
 /// }
 /// ```
 #[derive(Serialize, Deserialize, Debug, PartialEq, SmartDefault)]
@@ -308,6 +472,12 @@ pub struct Conf {
     pub window_setup: WindowSetup,
     /// Backend configuration
     pub backend: Backend,
+    /// Main loop settings
+    #[serde(default)]
+    pub run_setup: RunSetup,
+    /// Audio mixer volumes
+    #[serde(default)]
+    pub audio_setup: AudioSetup,
 }
 
 impl Conf {
@@ -349,4 +519,47 @@ mod tests {
         let c2 = conf::Conf::from_toml_file(&mut reader).unwrap();
         assert_eq!(c1, c2);
     }
+
+    /// Makes sure fixed-timestep settings survive a round trip,
+    /// and that config files written before `run_setup` existed
+    /// still load.
+    #[test]
+    fn headless_run_setup_round_trip() {
+        let mut c1 = conf::Conf::new();
+        c1.run_setup = c1.run_setup.fixed_update(30, 3);
+        let mut writer = Vec::new();
+        let _c = c1.to_toml_file(&mut writer).unwrap();
+        let mut reader = writer.as_slice();
+        let c2 = conf::Conf::from_toml_file(&mut reader).unwrap();
+        assert_eq!(c1, c2);
+
+        let text = String::from_utf8(writer).unwrap();
+        let old_style: String = text.split("[run_setup").next().unwrap().to_owned();
+        let c3 = conf::Conf::from_toml_file(&mut old_style.as_bytes()).unwrap();
+        assert_eq!(c3.run_setup, conf::RunSetup::default());
+    }
+
+    /// Makes sure mixer settings survive a round trip, and that
+    /// config files written before `audio_setup` existed still load.
+    #[test]
+    fn headless_audio_setup_round_trip() {
+        let mut c1 = conf::Conf::new();
+        c1.audio_setup = c1
+            .audio_setup
+            .master_volume(0.8)
+            .bus_volume("music", 0.5)
+            .bus_muted("sfx", true)
+            .bus_volume("voice", 0.25);
+        let mut writer = Vec::new();
+        let _c = c1.to_toml_file(&mut writer).unwrap();
+        let mut reader = writer.as_slice();
+        let c2 = conf::Conf::from_toml_file(&mut reader).unwrap();
+        assert_eq!(c1, c2);
+        assert_eq!(c2.audio_setup.buses.len(), 3);
+
+        let text = String::from_utf8(writer).unwrap();
+        let old_style: String = text.split("[audio_setup").next().unwrap().to_owned();
+        let c3 = conf::Conf::from_toml_file(&mut old_style.as_bytes()).unwrap();
+        assert_eq!(c3.audio_setup, conf::AudioSetup::default());
+    }
 }
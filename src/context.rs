@@ -1,15 +1,16 @@
 use winit;
-use winit::dpi;
 
 use std::fmt;
+use std::sync::mpsc;
 
 use audio;
 use conf;
-use event::winit_event;
+use event::{winit_event, AnyUserEvent, GameEvent};
 use filesystem::Filesystem;
 use graphics::{self, Point2};
-use input::{gamepad, keyboard, mouse};
+use input::{actions, gamepad, keyboard, mouse, recording, text, touch};
 use timer;
+use GameError;
 use GameResult;
 
 /// A `Context` is an object that holds on to global resources.
@@ -22,11 +23,15 @@ use GameResult;
 /// drawing things, playing sounds, or loading resources (which then
 /// need to be transformed into a format the hardware likes) will need
 /// to access the `Context`.
+///
+/// A `Context` can also be built without any window, graphics or
+/// sound hardware at all with `ContextBuilder::build_headless()`.
+/// See `Context::is_headless()` for what that entails.
 pub struct Context {
     /// Filesystem state
     pub filesystem: Filesystem,
-    /// Graphics state
-    pub(crate) gfx_context: graphics::GraphicsContext,
+    /// Graphics state; `None` if the context is headless.
+    pub(crate) gfx_context: Option<graphics::GraphicsContext>,
     /// Timer state
     pub timer_context: timer::TimeContext,
     /// Audio context
@@ -37,6 +42,18 @@ pub struct Context {
     pub mouse_context: mouse::MouseContext,
     /// Gamepad context
     pub gamepad_context: gamepad::GamepadContext,
+    /// Input recording and replay context
+    pub recording_context: recording::RecordingContext,
+    /// Input action mapping context
+    pub action_context: actions::ActionContext,
+    /// Text entry and clipboard context
+    pub text_context: text::TextContext,
+    /// Touchscreen context
+    pub touch_context: touch::TouchContext,
+    /// Where `event::UserEventSender`s send their events to.
+    pub(crate) user_event_sender: mpsc::Sender<AnyUserEvent>,
+    /// Where the event loop picks user events up from.
+    pub(crate) user_event_receiver: mpsc::Receiver<AnyUserEvent>,
 
     /// The Conf object the Context was created with
     pub conf: conf::Conf,
@@ -61,7 +78,8 @@ impl Context {
     /// Usually called by `ContextBuilder::build()`.
     fn from_conf(conf: conf::Conf, fs: Filesystem) -> GameResult<(Context, winit::EventsLoop)> {
         let debug_id = DebugId::new();
-        let audio_context = audio::AudioContext::new()?;
+        let mut audio_context = audio::AudioContext::new()?;
+        audio_context.apply_audio_setup(&conf.audio_setup);
         let events_loop = winit::EventsLoop::new();
         let timer_context = timer::TimeContext::new();
         let backend_spec = graphics::GlBackendSpec::from(conf.backend);
@@ -75,17 +93,24 @@ impl Context {
         let mouse_context = mouse::MouseContext::new();
         let keyboard_context = keyboard::KeyboardContext::new();
         let gamepad_context = gamepad::GamepadContext::new()?;
+        let (user_event_sender, user_event_receiver) = mpsc::channel();
 
         let ctx = Context {
             conf,
             filesystem: fs,
-            gfx_context: graphics_context,
+            gfx_context: Some(graphics_context),
             continuing: true,
             timer_context,
             audio_context,
             keyboard_context,
             gamepad_context,
             mouse_context,
+            recording_context: recording::RecordingContext::new(),
+            action_context: actions::ActionContext::new(),
+            text_context: text::TextContext::new(),
+            touch_context: touch::TouchContext::new(),
+            user_event_sender,
+            user_event_receiver,
 
             debug_id,
         };
@@ -93,6 +118,37 @@ impl Context {
         Ok((ctx, events_loop))
     }
 
+    /// Creates a new headless Context, with no window, graphics,
+    /// audio output or gamepads.
+    /// Usually called by `ContextBuilder::build_headless()`.
+    fn headless_from_conf(conf: conf::Conf, fs: Filesystem) -> GameResult<Context> {
+        let debug_id = DebugId::new();
+        let (user_event_sender, user_event_receiver) = mpsc::channel();
+        let mut audio_context = audio::AudioContext::new_headless();
+        audio_context.apply_audio_setup(&conf.audio_setup);
+        let ctx = Context {
+            conf,
+            filesystem: fs,
+            gfx_context: None,
+            continuing: true,
+            timer_context: timer::TimeContext::new(),
+            audio_context,
+            keyboard_context: keyboard::KeyboardContext::new(),
+            gamepad_context: gamepad::GamepadContext::new_headless(),
+            mouse_context: mouse::MouseContext::new(),
+            recording_context: recording::RecordingContext::new(),
+            action_context: actions::ActionContext::new(),
+            text_context: text::TextContext::new_headless(),
+            touch_context: touch::TouchContext::new(),
+            user_event_sender,
+            user_event_receiver,
+
+            debug_id,
+        };
+
+        Ok(ctx)
+    }
+
     /// Tries to create a new Context by loading a config
     /// file from its default path, using the given `Conf`
     /// object as a default if none is found.
@@ -130,6 +186,44 @@ impl Context {
         self.continuing = false;
     }
 
+    /// Returns whether this `Context` was created with
+    /// `ContextBuilder::build_headless()`.
+    ///
+    /// A headless context has working `filesystem`, `timer`,
+    /// keyboard and mouse state, but no window.  Sounds play to
+    /// nowhere and gamepads never connect.  Functions in the
+    /// `graphics` module (or anything else that needs the window)
+    /// return a `GameError::RenderError` if they return a
+    /// `GameResult`, render statistics are all zero, and the rest
+    /// panic.
+    pub fn is_headless(&self) -> bool {
+        self.gfx_context.is_none()
+    }
+
+    /// Returns the graphics state, panicking if there is none.
+    pub(crate) fn gfx(&self) -> &graphics::GraphicsContext {
+        self.gfx_context.as_ref().expect(HEADLESS_GRAPHICS_ERROR)
+    }
+
+    /// Returns the graphics state mutably, panicking if there is none.
+    pub(crate) fn gfx_mut(&mut self) -> &mut graphics::GraphicsContext {
+        self.gfx_context.as_mut().expect(HEADLESS_GRAPHICS_ERROR)
+    }
+
+    /// Returns the graphics state, or an error if there is none.
+    pub(crate) fn try_gfx(&self) -> GameResult<&graphics::GraphicsContext> {
+        self.gfx_context
+            .as_ref()
+            .ok_or_else(|| GameError::RenderError(HEADLESS_GRAPHICS_ERROR.to_owned()))
+    }
+
+    /// Returns the graphics state mutably, or an error if there is none.
+    pub(crate) fn try_gfx_mut(&mut self) -> GameResult<&mut graphics::GraphicsContext> {
+        self.gfx_context
+            .as_mut()
+            .ok_or_else(|| GameError::RenderError(HEADLESS_GRAPHICS_ERROR.to_owned()))
+    }
+
     /// Feeds an `Event` into the `Context` so it can update any internal
     /// state it needs to, such as detecting window resizes.  If you are
     /// rolling your own event loop, you should call this on the events
@@ -139,56 +233,167 @@ impl Context {
     /// for ggez's optional overriding of hidpi.  For full discussion see
     /// <https://github.com/tomaka/winit/issues/591#issuecomment-403096230>.
     pub fn process_event(&mut self, event: &winit::Event) -> winit::Event {
-        let event = self.gfx_context.hack_event_hidpi(event);
-        match event.clone() {
-            winit_event::Event::WindowEvent { event, .. } => match event {
+        let (event, _game_event) = self.process_winit_event(event);
+        event
+    }
+
+    /// Tells the `Context` a new frame is starting: ticks the timer,
+    /// zeroes the relative mode mouse delta, and forgets which keys
+    /// and buttons were "just pressed" or "just released" last frame.  `event::run()` calls this for you;
+    /// if you write your own main loop, call it once at the top of
+    /// each frame, before processing that frame's events.
+    ///
+    /// With `UpdateMode::Fixed`, a frame may run no updates at all, so
+    /// the "just pressed" and "just released" state is kept until one
+    /// has seen it; `event::run()` then calls `clear_input_edges()`
+    /// after the frame's first update.
+    pub fn begin_frame(&mut self) {
+        self.timer_context.tick();
+        self.mouse_context.reset_delta();
+        if self.conf.run_setup.update_mode == conf::UpdateMode::Variable {
+            self.clear_input_edges();
+        }
+    }
+
+    /// Forgets which keys and buttons were "just pressed" or "just
+    /// released".  `begin_frame()` does this for you unless you're
+    /// using `UpdateMode::Fixed`, in which case a main loop of your
+    /// own should call it after each `update()`.
+    pub fn clear_input_edges(&mut self) {
+        self.keyboard_context.begin_frame();
+        self.mouse_context.begin_frame();
+        self.gamepad_context.begin_frame();
+    }
+
+    /// Does the work of `process_event()`, also returning the
+    /// `GameEvent` the `winit` event turned into, if any, so
+    /// `event::run()` can hand it to the `EventHandler`.
+    pub(crate) fn process_winit_event(
+        &mut self,
+        event: &winit::Event,
+    ) -> (winit::Event, Option<GameEvent>) {
+        let event = match self.gfx_context {
+            Some(ref gfx) => gfx.hack_event_hidpi(event),
+            None => event.clone(),
+        };
+        if let winit_event::Event::WindowEvent { ref event, .. } = event {
+            match *event {
                 winit_event::WindowEvent::Resized(_) => {
-                    self.gfx_context.resize_viewport();
-                }
-                winit_event::WindowEvent::CursorMoved {
-                    position: dpi::LogicalPosition { x, y },
-                    ..
-                } => {
-                    self.mouse_context
-                        .set_last_position(Point2::new(x as f32, y as f32));
+                    if let Some(ref mut gfx) = self.gfx_context {
+                        gfx.resize_viewport();
+                    }
                 }
-                winit_event::WindowEvent::MouseInput { button, state, .. } => {
-                    let pressed = match state {
-                        winit_event::ElementState::Pressed => true,
-                        winit_event::ElementState::Released => false,
-                    };
-                    self.mouse_context.set_button(button, pressed);
+                winit_event::WindowEvent::HiDpiFactorChanged(factor) => {
+                    let hidpi = self.conf.window_mode.hidpi;
+                    if let Some(ref mut gfx) = self.gfx_context {
+                        gfx.set_os_hidpi_factor(factor as f32, hidpi);
+                    }
                 }
                 _ => (),
-            },
-            winit_event::Event::DeviceEvent { event, .. } => match event {
-                winit_event::DeviceEvent::MouseMotion { delta: (x, y) } => {
-                    self.mouse_context
-                        .set_last_delta(Point2::new(x as f32, y as f32));
+            }
+        }
+        let game_event = GameEvent::from_winit(self, &event)
+            .filter(|e| self.recording_context.accepts_live_event(e));
+        if let Some(ref game_event) = game_event {
+            self.process_game_event(game_event);
+        }
+        (event, game_event)
+    }
+
+    /// Feeds a `GameEvent` into the `Context` so it can update its
+    /// keyboard, mouse and touch state, exactly as if the event had come from
+    /// `process_event()`.  Use this to simulate input; if you want the
+    /// matching `EventHandler` callback called as well, use
+    /// `event::send_event()` instead.
+    ///
+    /// If input is being recorded, the event is recorded too.
+    pub fn process_game_event(&mut self, event: &GameEvent) {
+        self.recording_context.record_event(event);
+        self.text_context.handle_event(event);
+        self.update_input_state(event);
+    }
+
+    /// Updates the keyboard, mouse, touch and gamepad state from an
+    /// event.
+    fn update_input_state(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::MouseMotion { x, y, dx, dy } => {
+                self.mouse_context.set_last_position(Point2::new(x, y));
+                if !self.mouse_context.is_relative() {
+                    self.mouse_context.set_last_delta(Point2::new(dx, dy));
                 }
-                winit_event::DeviceEvent::Key(winit_event::KeyboardInput {
-                    state,
-                    virtual_keycode: Some(keycode),
-                    modifiers,
-                    ..
-                }) => {
-                    let pressed = match state {
-                        winit_event::ElementState::Pressed => true,
-                        winit_event::ElementState::Released => false,
-                    };
-                    self.keyboard_context
-                        .set_modifiers(keyboard::KeyMods::from(modifiers));
-                    self.keyboard_context.set_key(keycode, pressed);
+            }
+            GameEvent::RawMouseMotion { dx, dy } => {
+                if self.mouse_context.is_relative() {
+                    self.mouse_context.add_delta(dx, dy);
                 }
-                _ => (),
-            },
-
+            }
+            GameEvent::MouseButtonDown { button, x, y } => {
+                self.mouse_context.set_last_position(Point2::new(x, y));
+                self.mouse_context.set_button(button, true);
+            }
+            GameEvent::MouseButtonUp { button, x, y } => {
+                self.mouse_context.set_last_position(Point2::new(x, y));
+                self.mouse_context.set_button(button, false);
+            }
+            GameEvent::KeyDown {
+                keycode,
+                scancode,
+                keymods,
+                ..
+            } => {
+                self.keyboard_context.set_modifiers(keymods);
+                if let Some(keycode) = keycode {
+                    self.keyboard_context.set_key(keycode, true);
+                    self.keyboard_context.learn_scancode(scancode, keycode);
+                }
+                self.keyboard_context.set_scancode(scancode, true);
+            }
+            GameEvent::KeyUp {
+                keycode,
+                scancode,
+                keymods,
+            } => {
+                self.keyboard_context.set_modifiers(keymods);
+                if let Some(keycode) = keycode {
+                    self.keyboard_context.set_key(keycode, false);
+                }
+                self.keyboard_context.set_scancode(scancode, false);
+            }
+            GameEvent::ControllerButtonDown { button, id } => {
+                self.gamepad_context.set_button(id, button, true);
+            }
+            GameEvent::ControllerButtonUp { button, id } => {
+                self.gamepad_context.set_button(id, button, false);
+            }
+            GameEvent::ControllerAxis { axis, value, id } => {
+                self.gamepad_context.set_axis(id, axis, value);
+            }
+            GameEvent::ControllerConnected { id } => {
+                self.gamepad_context.set_connected(id, true);
+            }
+            GameEvent::ControllerDisconnected { id } => {
+                self.gamepad_context.set_connected(id, false);
+            }
+            GameEvent::Touch { id, phase, x, y } => {
+                let pos = Point2::new(x, y);
+                let window_pos = touch::screen_to_window(self, pos);
+                let mouse_pos = mouse::get_position(self);
+                let emulated = self
+                    .touch_context
+                    .set_touch(id, phase, pos, window_pos, mouse_pos);
+                if let Some(ref mouse_event) = emulated {
+                    self.update_input_state(mouse_event);
+                }
+            }
             _ => (),
-        };
-        event
+        }
     }
 }
 
+const HEADLESS_GRAPHICS_ERROR: &str =
+    "Tried to use graphics with a headless Context; there is no window to draw to!";
+
 use std::path;
 
 /// A builder object for creating a `Context`.
@@ -237,6 +442,18 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets the main loop settings
+    pub fn run_setup(mut self, setup: conf::RunSetup) -> Self {
+        self.conf.run_setup = setup;
+        self
+    }
+
+    /// Sets the audio mixer volumes
+    pub fn audio_setup(mut self, setup: conf::AudioSetup) -> Self {
+        self.conf.audio_setup = setup;
+        self
+    }
+
     /// Add a new read-only filesystem path to the places to search
     /// for resources.
     pub fn add_resource_path<T>(mut self, path: T) -> Self
@@ -258,6 +475,26 @@ impl ContextBuilder {
 
     /// Build the Context.
     pub fn build(self) -> GameResult<(Context, winit::EventsLoop)> {
+        let (config, fs) = self.load_filesystem()?;
+        Context::from_conf(config, fs)
+    }
+
+    /// Build a headless Context, which has no window, graphics,
+    /// sound output or gamepads and so doesn't need the hardware
+    /// for any of them.  This is handy for running game logic in
+    /// tests or on a dedicated server; see `Context::is_headless()`
+    /// for what does and doesn't work, and `event::run_headless()`
+    /// for a main loop to go with it.
+    ///
+    /// The window settings in the `Conf` are ignored.
+    pub fn build_headless(self) -> GameResult<Context> {
+        let (config, fs) = self.load_filesystem()?;
+        Context::headless_from_conf(config, fs)
+    }
+
+    /// Sets up the filesystem with the requested resource paths
+    /// and loads the config file from it, if asked to.
+    fn load_filesystem(self) -> GameResult<(conf::Conf, Filesystem)> {
         let mut fs = Filesystem::new(self.game_id, self.author)?;
 
         let config = if self.load_conf_file {
@@ -265,12 +502,21 @@ impl ContextBuilder {
         } else {
             self.conf
         };
+        if let conf::UpdateMode::Fixed {
+            updates_per_second: 0,
+            ..
+        } = config.run_setup.update_mode
+        {
+            return Err(GameError::ConfigError(
+                "UpdateMode::Fixed needs more than 0 updates per second".to_owned(),
+            ));
+        }
 
         for path in &self.paths {
             fs.mount(path, true);
         }
 
-        Context::from_conf(config, fs)
+        Ok((config, fs))
     }
 }
 
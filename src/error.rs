@@ -246,3 +246,10 @@ impl From<gilrs::Error> for GameError {
         GameError::GamepadError(errstr)
     }
 }
+
+impl From<gilrs::ff::Error> for GameError {
+    fn from(s: gilrs::ff::Error) -> GameError {
+        let errstr = format!("Force feedback error: {}", s);
+        GameError::GamepadError(errstr)
+    }
+}
@@ -16,10 +16,15 @@
 //! See the `eventloop` example for an implementation.
 
 use gilrs;
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path;
+use std::sync::mpsc;
 use winit;
 use winit::dpi;
 
-pub use input::keyboard::{KeyCode, KeyMods};
+pub use input::keyboard::{KeyCode, KeyMods, ScanCode};
 /// A mouse button.
 pub use winit::MouseButton;
 
@@ -27,11 +32,13 @@ pub use winit::MouseButton;
 pub use gilrs::Axis;
 /// A button of some device (controller, joystick...).
 pub use gilrs::Button;
+pub use input::gamepad::GamepadId;
+pub use input::touch::TouchPhase;
 
 /// `winit` events; nested in a module for re-export neatness.
 pub mod winit_event {
     pub use super::winit::{
-        DeviceEvent, ElementState, Event, KeyboardInput, ModifiersState, MouseScrollDelta,
+        DeviceEvent, ElementState, Event, KeyboardInput, ModifiersState, MouseScrollDelta, Touch,
         TouchPhase, WindowEvent,
     };
 }
@@ -40,9 +47,18 @@ use self::winit_event::*;
 /// `winit` event loop.
 pub use winit::EventsLoop;
 
+use conf::UpdateMode;
 use context::Context;
+use input::{actions, keyboard, recording, touch};
+use timer;
+use GameError;
 use GameResult;
 
+/// A user event on its way from a `UserEventSender` to the
+/// `EventHandler`, with its type erased so the `Context` can carry
+/// any kind.
+pub(crate) type AnyUserEvent = Box<dyn Any + Send>;
+
 /// A trait defining event callbacks; your primary interface with
 /// `ggez`'s event loop.  Have a type implement this trait and
 /// override at least the update() and draw() methods, then pass it to
@@ -51,7 +67,11 @@ use GameResult;
 /// The default event handlers do nothing, apart from
 /// `key_down_event()`, which will by default exit the game if the escape
 /// key is pressed.  Just override the methods you want to do things with.
-pub trait EventHandler {
+///
+/// `E` is the type of event `user_event()` receives from
+/// `UserEventSender`s.  It defaults to `()` for games that don't send
+/// any.
+pub trait EventHandler<E = ()> {
     /// Called upon each logic update to the game.
     /// This should be where the game's logic takes place.
     fn update(&mut self, _ctx: &mut Context) -> GameResult;
@@ -60,6 +80,9 @@ pub trait EventHandler {
     /// You probably want to start this with
     /// `graphics::clear()` and end it with
     /// `graphics::present()` and `timer::yield_now()`
+    ///
+    /// When running with a fixed update rate, `timer::get_update_alpha()`
+    /// tells you how far you are between two updates.
     fn draw(&mut self, _ctx: &mut Context) -> GameResult;
 
     /// A mouse button was pressed
@@ -86,6 +109,12 @@ pub trait EventHandler {
     /// and relative x and y coordinates compared to its last position.
     fn mouse_motion_event(&mut self, _ctx: &mut Context, _x: f32, _y: f32, _dx: f32, _dy: f32) {}
 
+    /// The mouse was moved while in relative mode (see
+    /// `mouse::set_relative_mode()`).  `dx` and `dy` are raw motion from
+    /// the device, so they keep coming even when the cursor can't move
+    /// any further.
+    fn raw_mouse_motion_event(&mut self, _ctx: &mut Context, _dx: f32, _dy: f32) {}
+
     /// The mousewheel was scrolled, vertically (y, positive away from and negative toward the user)
     /// or horizontally (x, positive to the right and negative to the left).
     fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, _y: f32) {}
@@ -106,18 +135,60 @@ pub trait EventHandler {
     /// A keyboard button was released.
     fn key_up_event(&mut self, _ctx: &mut Context, _keycode: KeyCode, _keymods: KeyMods) {}
 
+    /// A keyboard button was pressed, identified by its scancode,
+    /// which says where the key is rather than what's printed on it.
+    /// This is called for every key press, right after
+    /// `key_down_event()`, and also for keys that don't have a
+    /// `KeyCode` at all.
+    fn scancode_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _scancode: ScanCode,
+        _keymods: KeyMods,
+        _repeat: bool,
+    ) {
+    }
+
+    /// A keyboard button was released, identified by its scancode.
+    fn scancode_up_event(&mut self, _ctx: &mut Context, _scancode: ScanCode, _keymods: KeyMods) {}
+
     /// A unicode character was received, usually from keyboard input.
     /// This is the intended way of facilitating text input.
     fn text_input_event(&mut self, _ctx: &mut Context, _character: char) {}
 
     /// A controller button was pressed; id identifies which controller.
-    fn controller_button_down_event(&mut self, _ctx: &mut Context, _btn: Button, _id: usize) {}
+    fn controller_button_down_event(&mut self, _ctx: &mut Context, _btn: Button, _id: GamepadId) {}
+
+    /// A controller button is being held down and has repeated, the
+    /// same way a held keyboard key does.
+    fn controller_button_repeat_event(&mut self, _ctx: &mut Context, _btn: Button, _id: GamepadId) {
+    }
 
     /// A controller button was released.
-    fn controller_button_up_event(&mut self, _ctx: &mut Context, _btn: Button, _id: usize) {}
+    fn controller_button_up_event(&mut self, _ctx: &mut Context, _btn: Button, _id: GamepadId) {}
 
     /// A controller axis moved.
-    fn controller_axis_event(&mut self, _ctx: &mut Context, _axis: Axis, _value: f32, _id: usize) {}
+    fn controller_axis_event(
+        &mut self,
+        _ctx: &mut Context,
+        _axis: Axis,
+        _value: f32,
+        _id: GamepadId,
+    ) {
+    }
+
+    /// A controller was plugged in.
+    fn controller_connected_event(&mut self, _ctx: &mut Context, _id: GamepadId) {}
+
+    /// A controller was unplugged.  Its `GamepadId` stays reserved for
+    /// it in case it comes back.
+    fn controller_disconnected_event(&mut self, _ctx: &mut Context, _id: GamepadId) {}
+
+    /// A finger touched, moved on or lifted off the touchscreen.  `id`
+    /// tells fingers apart for as long as they're touching; `x` and
+    /// `y` are in screen coordinates, as in
+    /// `graphics::get_screen_coordinates()`.
+    fn touch_event(&mut self, _ctx: &mut Context, _phase: TouchPhase, _id: u64, _x: f32, _y: f32) {}
 
     /// Called when the window is shown or hidden.
     fn focus_event(&mut self, _ctx: &mut Context, _gained: bool) {}
@@ -132,121 +203,735 @@ pub trait EventHandler {
     /// Called when the user resizes the window, or when it is resized
     /// via `graphics::set_mode()`.
     fn resize_event(&mut self, _ctx: &mut Context, _width: f32, _height: f32) {}
+
+    /// Called when the window is moved; `x` and `y` are the new
+    /// position of its top left corner on the desktop.
+    fn window_moved_event(&mut self, _ctx: &mut Context, _x: f32, _y: f32) {}
+
+    /// Called when the window's contents need to be drawn again, for
+    /// instance after being uncovered.  `draw()` is called every frame
+    /// anyway, so most games won't need this.
+    fn refresh_event(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the operating system's hidpi factor for the window
+    /// changes, such as when it's dragged to a monitor with a
+    /// different pixel density.  By the time this is called,
+    /// `graphics::get_os_hidpi_factor()` (and `get_hidpi_factor()`,
+    /// if `conf::WindowMode::hidpi` is on) return the new factor.
+    fn hidpi_factor_changed_event(&mut self, _ctx: &mut Context, _factor: f32) {}
+
+    /// Called when the mouse cursor enters or leaves the window.
+    fn mouse_enter_or_leave_event(&mut self, _ctx: &mut Context, _entered: bool) {}
+
+    /// A file was dropped onto the window.  If several files are
+    /// dropped at once, this is called once for each of them.
+    fn file_dropped_event(&mut self, _ctx: &mut Context, _path: path::PathBuf) {}
+
+    /// A file is being dragged over the window.  It will be followed
+    /// by either `file_dropped_event()` or `file_hover_cancelled_event()`.
+    fn file_hovered_event(&mut self, _ctx: &mut Context, _path: path::PathBuf) {}
+
+    /// The file being dragged over the window was dragged away again
+    /// without being dropped.
+    fn file_hover_cancelled_event(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the application is suspended or resumed, which
+    /// mostly happens on mobile platforms when the app goes to the
+    /// background.  This is a good time to pause the game.
+    fn suspend_event(&mut self, _ctx: &mut Context, _suspended: bool) {}
+
+    /// An event sent with a `UserEventSender`, perhaps from another
+    /// thread, has arrived.  Events arrive in the order they were
+    /// sent, before the `update()` of the frame they arrive in.
+    fn user_event(&mut self, _ctx: &mut Context, _event: E) {}
+}
+
+/// An event, in terms of the `EventHandler` callback it results in.
+///
+/// `event::run()` turns the `winit` and `gilrs` events it receives
+/// into these before handing them to your `EventHandler`.  You can
+/// also make your own and feed them through the exact same machinery
+/// with `send_event()`, which is handy for testing input handling
+/// without a real keyboard attached.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GameEvent {
+    /// A mouse button was pressed at the given position.
+    MouseButtonDown {
+        /// The button pressed.
+        button: MouseButton,
+        /// X position of the cursor.
+        x: f32,
+        /// Y position of the cursor.
+        y: f32,
+    },
+    /// A mouse button was released at the given position.
+    MouseButtonUp {
+        /// The button released.
+        button: MouseButton,
+        /// X position of the cursor.
+        x: f32,
+        /// Y position of the cursor.
+        y: f32,
+    },
+    /// The mouse cursor moved to the given position,
+    /// by the given amount.
+    MouseMotion {
+        /// New X position of the cursor.
+        x: f32,
+        /// New Y position of the cursor.
+        y: f32,
+        /// Change in X position since the last motion.
+        dx: f32,
+        /// Change in Y position since the last motion.
+        dy: f32,
+    },
+    /// The mouse was moved in relative mode.
+    RawMouseMotion {
+        /// Raw horizontal motion from the device.
+        dx: f32,
+        /// Raw vertical motion from the device.
+        dy: f32,
+    },
+    /// The mousewheel was scrolled.
+    MouseWheel {
+        /// Horizontal scroll amount.
+        x: f32,
+        /// Vertical scroll amount.
+        y: f32,
+    },
+    /// A keyboard key was pressed.
+    KeyDown {
+        /// The key pressed, or `None` if the system doesn't know
+        /// which `KeyCode` it is.
+        keycode: Option<KeyCode>,
+        /// The physical key pressed.
+        scancode: ScanCode,
+        /// The modifiers active at the time.
+        keymods: KeyMods,
+        /// Whether this is a key repeat from the key being held down.
+        repeat: bool,
+    },
+    /// A keyboard key was released.
+    KeyUp {
+        /// The key released, or `None` if the system doesn't know
+        /// which `KeyCode` it is.
+        keycode: Option<KeyCode>,
+        /// The physical key released.
+        scancode: ScanCode,
+        /// The modifiers active at the time.
+        keymods: KeyMods,
+    },
+    /// A unicode character was received.
+    TextInput(char),
+    /// A controller button was pressed.
+    ControllerButtonDown {
+        /// The button pressed.
+        button: Button,
+        /// Which controller it was.
+        id: GamepadId,
+    },
+    /// A held controller button repeated.
+    ControllerButtonRepeat {
+        /// The button held.
+        button: Button,
+        /// Which controller it was.
+        id: GamepadId,
+    },
+    /// A controller button was released.
+    ControllerButtonUp {
+        /// The button released.
+        button: Button,
+        /// Which controller it was.
+        id: GamepadId,
+    },
+    /// A controller axis moved.
+    ControllerAxis {
+        /// The axis moved.
+        axis: Axis,
+        /// Its new value.
+        value: f32,
+        /// Which controller it was.
+        id: GamepadId,
+    },
+    /// A controller was connected.
+    ControllerConnected {
+        /// Which controller it was.
+        id: GamepadId,
+    },
+    /// A controller was disconnected.
+    ControllerDisconnected {
+        /// Which controller it was.
+        id: GamepadId,
+    },
+    /// A finger touched, moved on or lifted off the touchscreen.
+    Touch {
+        /// Which finger it was.
+        id: u64,
+        /// What the finger did.
+        phase: TouchPhase,
+        /// X position in screen coordinates.
+        x: f32,
+        /// Y position in screen coordinates.
+        y: f32,
+    },
+    /// The window gained or lost focus.
+    Focus(bool),
+    /// The window was resized to the given size.
+    Resize {
+        /// New width of the window.
+        width: f32,
+        /// New height of the window.
+        height: f32,
+    },
+    /// The user asked to close the game.
+    Quit,
+}
+
+impl GameEvent {
+    /// A non-repeat `KeyDown` event for the given key, with the
+    /// scancode it has on a US keyboard; handy for simulating input.
+    pub fn key_down(keycode: KeyCode, keymods: KeyMods) -> GameEvent {
+        GameEvent::KeyDown {
+            keycode: Some(keycode),
+            scancode: keyboard::us_layout_scancode(keycode).unwrap_or(0),
+            keymods,
+            repeat: false,
+        }
+    }
+
+    /// A `KeyUp` event for the given key, with the scancode it has on
+    /// a US keyboard.
+    pub fn key_up(keycode: KeyCode, keymods: KeyMods) -> GameEvent {
+        GameEvent::KeyUp {
+            keycode: Some(keycode),
+            scancode: keyboard::us_layout_scancode(keycode).unwrap_or(0),
+            keymods,
+        }
+    }
+
+    /// Turns a `winit` event into a `GameEvent`, if it's one we care
+    /// about.  This looks at the current mouse and keyboard state to
+    /// fill in cursor positions, deltas and key repeats, so it must be
+    /// called *before* the `Context` has processed the event.
+    pub(crate) fn from_winit(ctx: &Context, event: &Event) -> Option<GameEvent> {
+        use input::mouse;
+
+        let event = match *event {
+            Event::WindowEvent { ref event, .. } => event,
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+                ..
+            } if mouse::get_relative_mode(ctx) => {
+                return Some(GameEvent::RawMouseMotion {
+                    dx: dx as f32,
+                    dy: dy as f32,
+                })
+            }
+            _ => return None,
+        };
+        match *event {
+            WindowEvent::Resized(dpi::LogicalSize { width, height }) => Some(GameEvent::Resize {
+                width: width as f32,
+                height: height as f32,
+            }),
+            WindowEvent::CloseRequested => Some(GameEvent::Quit),
+            WindowEvent::Focused(gained) => Some(GameEvent::Focus(gained)),
+            WindowEvent::ReceivedCharacter(ch) => Some(GameEvent::TextInput(ch)),
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: keycode,
+                        scancode,
+                        modifiers,
+                    },
+                ..
+            } => Some(GameEvent::KeyDown {
+                keycode,
+                scancode,
+                keymods: modifiers.into(),
+                repeat: match keycode {
+                    Some(keycode) => ctx.keyboard_context.is_repeat_press(keycode),
+                    None => ctx.keyboard_context.is_scancode_pressed(scancode),
+                },
+            }),
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Released,
+                        virtual_keycode: keycode,
+                        scancode,
+                        modifiers,
+                    },
+                ..
+            } => Some(GameEvent::KeyUp {
+                keycode,
+                scancode,
+                keymods: modifiers.into(),
+            }),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x, y),
+                    MouseScrollDelta::PixelDelta(dpi::LogicalPosition { x, y }) => {
+                        (x as f32, y as f32)
+                    }
+                };
+                Some(GameEvent::MouseWheel { x, y })
+            }
+            WindowEvent::MouseInput {
+                state: element_state,
+                button,
+                ..
+            } => {
+                let position = mouse::get_position(ctx);
+                match element_state {
+                    ElementState::Pressed => Some(GameEvent::MouseButtonDown {
+                        button,
+                        x: position.x,
+                        y: position.y,
+                    }),
+                    ElementState::Released => Some(GameEvent::MouseButtonUp {
+                        button,
+                        x: position.x,
+                        y: position.y,
+                    }),
+                }
+            }
+            WindowEvent::Touch(Touch {
+                phase,
+                location: dpi::LogicalPosition { x, y },
+                id,
+                ..
+            }) => {
+                let position = touch::window_to_screen(ctx, x as f32, y as f32);
+                Some(GameEvent::Touch {
+                    id,
+                    phase,
+                    x: position.x,
+                    y: position.y,
+                })
+            }
+            WindowEvent::CursorMoved {
+                position: dpi::LogicalPosition { x, y },
+                ..
+            } => {
+                let last_position = mouse::get_position(ctx);
+                Some(GameEvent::MouseMotion {
+                    x: x as f32,
+                    y: y as f32,
+                    dx: x as f32 - last_position.x,
+                    dy: y as f32 - last_position.y,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Turns a `gilrs` event into a `GameEvent`, if it's one we care
+    /// about.  Axis values get the gamepad deadzone applied.
+    pub(crate) fn from_gilrs(ctx: &Context, event: &gilrs::Event) -> Option<GameEvent> {
+        let id = GamepadId(event.id);
+        match event.event {
+            gilrs::EventType::ButtonPressed(button, _) => {
+                Some(GameEvent::ControllerButtonDown { button, id })
+            }
+            gilrs::EventType::ButtonRepeated(button, _) => {
+                Some(GameEvent::ControllerButtonRepeat { button, id })
+            }
+            gilrs::EventType::ButtonReleased(button, _) => {
+                Some(GameEvent::ControllerButtonUp { button, id })
+            }
+            gilrs::EventType::AxisChanged(axis, value, _) => {
+                let value = ctx.gamepad_context.apply_deadzone(value);
+                Some(GameEvent::ControllerAxis { axis, value, id })
+            }
+            gilrs::EventType::Connected => Some(GameEvent::ControllerConnected { id }),
+            gilrs::EventType::Disconnected => Some(GameEvent::ControllerDisconnected { id }),
+            _ => None,
+        }
+    }
+}
+
+/// Sends events of type `T` to the game's `EventHandler::user_event()`
+/// callback from anywhere, including other threads, and wakes up the
+/// event loop so it notices.  Handy for letting the game know a
+/// background asset loader has finished, or that a network message
+/// has come in:
+///
+/// ```rust,ignore
+/// let sender = UserEventSender::new::<MainState>(ctx, &events_loop);
+/// thread::spawn(move || {
+///     let level = load_level("level1.toml");
+///     sender.send(LevelLoaded(level)).unwrap();
+/// });
+/// ```
+///
+/// Senders can be cloned, and each clone sends to the same place.
+///
+/// A sender is made for a particular `EventHandler` type, and `T` is
+/// its user event type.  If the game is run with some other type of
+/// `EventHandler` that doesn't take `T`, the events are dropped with a
+/// warning.
+pub struct UserEventSender<T> {
+    sender: mpsc::Sender<AnyUserEvent>,
+    proxy: Option<winit::EventsLoopProxy>,
+    // `fn(T)` so the sender is `Send` whatever `T` is.
+    event_type: PhantomData<fn(T)>,
+}
+
+impl<T> UserEventSender<T>
+where
+    T: Any + Send,
+{
+    /// Creates a sender for the game running on the given context
+    /// and event loop, whose state is an `S`.
+    pub fn new<S>(ctx: &Context, events_loop: &EventsLoop) -> Self
+    where
+        S: EventHandler<T>,
+    {
+        UserEventSender {
+            sender: ctx.user_event_sender.clone(),
+            proxy: Some(events_loop.create_proxy()),
+            event_type: PhantomData,
+        }
+    }
+
+    /// Creates a sender for a headless context, which has no event
+    /// loop to wake up; the events are picked up at the start of the
+    /// next frame of `run_headless()` or `run_script()`.
+    pub fn new_headless<S>(ctx: &Context) -> Self
+    where
+        S: EventHandler<T>,
+    {
+        UserEventSender {
+            sender: ctx.user_event_sender.clone(),
+            proxy: None,
+            event_type: PhantomData,
+        }
+    }
+
+    /// Sends an event.  Fails with `GameError::EventLoopError` if the
+    /// game has already shut down.
+    pub fn send(&self, event: T) -> GameResult {
+        self.sender.send(Box::new(event)).map_err(|_| {
+            GameError::EventLoopError(
+                "Tried to send a user event to a Context that no longer exists.".to_owned(),
+            )
+        })?;
+        if let Some(ref proxy) = self.proxy {
+            proxy.wakeup()?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Clone for UserEventSender<T> {
+    fn clone(&self) -> Self {
+        UserEventSender {
+            sender: self.sender.clone(),
+            proxy: self.proxy.clone(),
+            event_type: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for UserEventSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<UserEventSender: {:p}>", self)
+    }
+}
+
+/// Hands any user events that have arrived to the state object,
+/// dropping any that it doesn't take.
+fn dispatch_user_events<S, E>(ctx: &mut Context, state: &mut S)
+where
+    S: EventHandler<E>,
+    E: Any,
+{
+    while let Ok(event) = ctx.user_event_receiver.try_recv() {
+        match event.downcast::<E>() {
+            Ok(event) => state.user_event(ctx, *event),
+            Err(_) => warn!("Dropped a user event of a type the EventHandler doesn't take."),
+        }
+    }
+}
+
+/// Feeds a `GameEvent` to the given state object, the same way
+/// `run()` does for real input: first the `Context` updates its
+/// keyboard and mouse state from it with `Context::process_game_event()`,
+/// then the matching `EventHandler` callback is called.
+///
+/// This works on headless contexts too, so you can write tests like:
+///
+/// ```rust,ignore
+/// let ctx = &mut ContextBuilder::new("test", "me").build_headless()?;
+/// let state = &mut MyGame::new();
+/// event::send_event(ctx, state, GameEvent::key_down(KeyCode::Space, KeyMods::NONE));
+/// assert!(keyboard::is_key_pressed(ctx, KeyCode::Space));
+/// assert!(state.player.is_jumping());
+/// ```
+pub fn send_event<S, E>(ctx: &mut Context, state: &mut S, event: GameEvent)
+where
+    S: EventHandler<E>,
+{
+    ctx.process_game_event(&event);
+    dispatch_event(ctx, state, event);
+}
+
+/// Calls the `EventHandler` callback matching the given event.
+fn dispatch_event<S, E>(ctx: &mut Context, state: &mut S, event: GameEvent)
+where
+    S: EventHandler<E>,
+{
+    match event {
+        GameEvent::MouseButtonDown { button, x, y } => {
+            state.mouse_button_down_event(ctx, button, x, y)
+        }
+        GameEvent::MouseButtonUp { button, x, y } => state.mouse_button_up_event(ctx, button, x, y),
+        GameEvent::MouseMotion { x, y, dx, dy } => state.mouse_motion_event(ctx, x, y, dx, dy),
+        GameEvent::RawMouseMotion { dx, dy } => state.raw_mouse_motion_event(ctx, dx, dy),
+        GameEvent::MouseWheel { x, y } => state.mouse_wheel_event(ctx, x, y),
+        GameEvent::KeyDown {
+            keycode,
+            scancode,
+            keymods,
+            repeat,
+        } => {
+            if let Some(keycode) = keycode {
+                state.key_down_event(ctx, keycode, keymods, repeat);
+            }
+            state.scancode_down_event(ctx, scancode, keymods, repeat);
+        }
+        GameEvent::KeyUp {
+            keycode,
+            scancode,
+            keymods,
+        } => {
+            if let Some(keycode) = keycode {
+                state.key_up_event(ctx, keycode, keymods);
+            }
+            state.scancode_up_event(ctx, scancode, keymods);
+        }
+        GameEvent::TextInput(ch) => state.text_input_event(ctx, ch),
+        GameEvent::ControllerButtonDown { button, id } => {
+            state.controller_button_down_event(ctx, button, id)
+        }
+        GameEvent::ControllerButtonRepeat { button, id } => {
+            state.controller_button_repeat_event(ctx, button, id)
+        }
+        GameEvent::ControllerButtonUp { button, id } => {
+            state.controller_button_up_event(ctx, button, id)
+        }
+        GameEvent::ControllerAxis { axis, value, id } => {
+            state.controller_axis_event(ctx, axis, value, id)
+        }
+        GameEvent::ControllerConnected { id } => state.controller_connected_event(ctx, id),
+        GameEvent::ControllerDisconnected { id } => state.controller_disconnected_event(ctx, id),
+        GameEvent::Touch { id, phase, x, y } => {
+            state.touch_event(ctx, phase, id, x, y);
+            if let Some(mouse_event) = ctx.touch_context.take_emulated_event() {
+                dispatch_event(ctx, state, mouse_event);
+            }
+        }
+        GameEvent::Focus(gained) => state.focus_event(ctx, gained),
+        GameEvent::Resize { width, height } => state.resize_event(ctx, width, height),
+        GameEvent::Quit => {
+            if !state.quit_event(ctx) {
+                ctx.quit();
+            }
+        }
+    }
+}
+
+/// Calls the `EventHandler` callback for a `winit` event that doesn't
+/// have a `GameEvent`: those about the window itself rather than input.
+fn dispatch_window_event<S, E>(ctx: &mut Context, state: &mut S, event: Event)
+where
+    S: EventHandler<E>,
+{
+    match event {
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::Moved(dpi::LogicalPosition { x, y }) => {
+                state.window_moved_event(ctx, x as f32, y as f32)
+            }
+            WindowEvent::Refresh => state.refresh_event(ctx),
+            WindowEvent::HiDpiFactorChanged(factor) => {
+                state.hidpi_factor_changed_event(ctx, factor as f32)
+            }
+            WindowEvent::CursorEntered { .. } => state.mouse_enter_or_leave_event(ctx, true),
+            WindowEvent::CursorLeft { .. } => state.mouse_enter_or_leave_event(ctx, false),
+            WindowEvent::DroppedFile(path) => state.file_dropped_event(ctx, path),
+            WindowEvent::HoveredFile(path) => state.file_hovered_event(ctx, path),
+            WindowEvent::HoveredFileCancelled => state.file_hover_cancelled_event(ctx),
+            event => trace!("ignoring window event {:?}", event),
+        },
+        Event::Suspended(suspended) => state.suspend_event(ctx, suspended),
+        // Sent by a `UserEventSender`; the events themselves get
+        // picked up once polling is done.
+        Event::Awakened => (),
+        event => trace!("ignoring event {:?}", event),
+    }
 }
 
 /// Runs the game's main loop, calling event callbacks on the given state
 /// object as events occur.
 ///
-/// It does not try to do any type of framerate limiting.  See the
-/// documentation for the `timer` module for more info.
-pub fn run<S>(ctx: &mut Context, events_loop: &mut EventsLoop, state: &mut S) -> GameResult
+/// It does not try to do any type of framerate limiting unless
+/// `conf::RunSetup::fps_limit` is set.  See the documentation for
+/// the `timer` module for more info.
+///
+/// How often `update()` is called is controlled by
+/// `conf::RunSetup::update_mode`; see `conf::UpdateMode` for the options.
+pub fn run<S, E>(ctx: &mut Context, events_loop: &mut EventsLoop, state: &mut S) -> GameResult
 where
-    S: EventHandler,
+    S: EventHandler<E>,
+    E: Any,
 {
-    use input::{keyboard, mouse};
-
     while ctx.continuing {
-        ctx.timer_context.tick();
+        begin_frame(ctx, state);
         events_loop.poll_events(|event| {
-            let event = ctx.process_event(&event);
-            match event {
-                Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::Resized(dpi::LogicalSize { width, height }) => {
-                        state.resize_event(ctx, width as f32, height as f32);
-                    }
-                    WindowEvent::CloseRequested => {
-                        if !state.quit_event(ctx) {
-                            ctx.quit();
-                        }
-                    }
-                    WindowEvent::Focused(gained) => {
-                        state.focus_event(ctx, gained);
-                    }
-                    WindowEvent::ReceivedCharacter(ch) => {
-                        state.text_input_event(ctx, ch);
-                    }
-                    WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(keycode),
-                                modifiers,
-                                ..
-                            },
-                        ..
-                    } => {
-                        let repeat = keyboard::is_key_repeated(ctx);
-                        state.key_down_event(ctx, keycode, modifiers.into(), repeat);
-                    }
-                    WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Released,
-                                virtual_keycode: Some(keycode),
-                                modifiers,
-                                ..
-                            },
-                        ..
-                    } => {
-                        state.key_up_event(ctx, keycode, modifiers.into());
-                    }
-                    WindowEvent::MouseWheel { delta, .. } => {
-                        let (x, y) = match delta {
-                            MouseScrollDelta::LineDelta(x, y) => (x, y),
-                            MouseScrollDelta::PixelDelta(dpi::LogicalPosition { x, y }) => {
-                                (x as f32, y as f32)
-                            }
-                        };
-                        state.mouse_wheel_event(ctx, x, y);
-                    }
-                    WindowEvent::MouseInput {
-                        state: element_state,
-                        button,
-                        ..
-                    } => {
-                        let position = mouse::get_position(ctx);
-                        match element_state {
-                            ElementState::Pressed => {
-                                state.mouse_button_down_event(ctx, button, position.x, position.y)
-                            }
-                            ElementState::Released => {
-                                state.mouse_button_up_event(ctx, button, position.x, position.y)
-                            }
-                        }
-                    }
-                    WindowEvent::CursorMoved { .. } => {
-                        let position = mouse::get_position(ctx);
-                        let delta = mouse::get_delta(ctx);
-                        state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
-                    }
-                    x => {
-                        trace!("ignoring window event {:?}", x);
-                    }
-                },
-                Event::DeviceEvent { event, .. } => match event {
-                    _ => (),
-                },
-                Event::Awakened => (),
-                Event::Suspended(_) => (),
+            let (event, game_event) = ctx.process_winit_event(&event);
+            match game_event {
+                Some(game_event) => dispatch_event(ctx, state, game_event),
+                None => dispatch_window_event(ctx, state, event),
             }
         });
-        while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad_context.gilrs.next_event() {
-            match event {
-                gilrs::EventType::ButtonPressed(button, _) => {
-                    state.controller_button_down_event(ctx, button, id);
-                }
-                gilrs::EventType::ButtonReleased(button, _) => {
-                    state.controller_button_up_event(ctx, button, id);
-                }
-                gilrs::EventType::AxisChanged(axis, value, _) => {
-                    state.controller_axis_event(ctx, axis, value, id);
-                }
-                _ => {}
+        while let Some(event) = ctx.gamepad_context.next_event() {
+            let game_event = GameEvent::from_gilrs(ctx, &event)
+                .filter(|e| ctx.recording_context.accepts_live_event(e));
+            if let Some(game_event) = game_event {
+                send_event(ctx, state, game_event);
             }
         }
-        state.update(ctx)?;
+        dispatch_user_events(ctx, state);
+        run_updates(ctx, state)?;
         state.draw(ctx)?;
+        let fps_limit = ctx.conf.run_setup.fps_limit;
+        ctx.timer_context.sleep_until_frame_end(fps_limit);
+    }
+
+    Ok(())
+}
+
+/// Runs the game's main loop like `run()`, but takes its events from
+/// `script` instead of a `winit` event loop.  Each item the script
+/// yields is the list of events for one frame; they are fed through
+/// `send_event()` before that frame's `update()`.  The loop stops
+/// when the script runs out or `Context::quit()` is called, whichever
+/// comes first.
+///
+/// `draw()` is called each frame unless the context is headless.
+/// Frame timing still comes from the real clock, but no framerate
+/// limiting is done, so scripts run as fast as your game allows.
+///
+/// Meant for writing deterministic tests of input handling:
+///
+/// ```rust,ignore
+/// let press = GameEvent::key_down(KeyCode::Left, KeyMods::NONE);
+/// let release = GameEvent::key_up(KeyCode::Left, KeyMods::NONE);
+/// event::run_script(ctx, state, vec![vec![press], vec![], vec![release]])?;
+/// assert_eq!(state.player_x, -3.0);
+/// ```
+pub fn run_script<S, E, I>(ctx: &mut Context, state: &mut S, script: I) -> GameResult
+where
+    S: EventHandler<E>,
+    E: Any,
+    I: IntoIterator<Item = Vec<GameEvent>>,
+{
+    for frame in script {
+        if !ctx.continuing {
+            break;
+        }
+        begin_frame(ctx, state);
+        for event in frame {
+            send_event(ctx, state, event);
+        }
+        dispatch_user_events(ctx, state);
+        run_updates(ctx, state)?;
+        if !ctx.is_headless() {
+            state.draw(ctx)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the game's main loop without a window, calling `update()`
+/// on the given state object but never `draw()`, until
+/// `Context::quit()` is called.  Meant for headless contexts built
+/// with `ContextBuilder::build_headless()`, such as a dedicated game
+/// server or a test harness.
+///
+/// There's no window to get input events from, so the only callbacks
+/// this ever makes are `update()` and `user_event()`.  The
+/// `conf::RunSetup` update mode and framerate limit are obeyed just
+/// like in `run()`; a server will generally want to set an
+/// `fps_limit` so it doesn't spin a CPU core at 100%.
+pub fn run_headless<S, E>(ctx: &mut Context, state: &mut S) -> GameResult
+where
+    S: EventHandler<E>,
+    E: Any,
+{
+    while ctx.continuing {
+        begin_frame(ctx, state);
+        dispatch_user_events(ctx, state);
+        run_updates(ctx, state)?;
+        let fps_limit = ctx.conf.run_setup.fps_limit;
+        ctx.timer_context.sleep_until_frame_end(fps_limit);
     }
 
     Ok(())
 }
+
+/// Starts a new frame: ticks the timer, resets the "just pressed"
+/// and "just released" input state, and lets any input recording
+/// or replay know about it.  When replaying, this is also where the
+/// recorded events for the frame get sent to the state object.
+fn begin_frame<S, E>(ctx: &mut Context, state: &mut S)
+where
+    S: EventHandler<E>,
+{
+    ctx.begin_frame();
+    if let Some(events) = recording::begin_frame(ctx) {
+        for event in events {
+            send_event(ctx, state, event);
+        }
+    }
+}
+
+/// Calls `update()` on the state object as many times as the
+/// current `UpdateMode` says this frame should have, bringing input
+/// actions up to date before each one.
+///
+/// With fixed updates, input that arrived on frames with no update
+/// is still "just pressed" for the next update that does run, and
+/// only that one.
+fn run_updates<S, E>(ctx: &mut Context, state: &mut S) -> GameResult
+where
+    S: EventHandler<E>,
+{
+    match ctx.conf.run_setup.update_mode {
+        UpdateMode::Variable => {
+            actions::update(ctx);
+            state.update(ctx)
+        }
+        UpdateMode::Fixed {
+            updates_per_second,
+            max_updates_per_frame,
+        } => {
+            let mut updates = 0;
+            while timer::check_fixed_update_time(ctx, updates_per_second) {
+                actions::update(ctx);
+                state.update(ctx)?;
+                ctx.clear_input_edges();
+                updates += 1;
+                if updates >= max_updates_per_frame {
+                    ctx.timer_context
+                        .discard_residual_update_steps(updates_per_second);
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+}
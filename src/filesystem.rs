@@ -76,6 +76,14 @@ impl io::Read for File {
     }
 }
 
+impl io::Seek for File {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match *self {
+            File::VfsFile(ref mut f) => f.seek(pos),
+        }
+    }
+}
+
 impl io::Write for File {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         match *self {
@@ -168,6 +176,11 @@ impl Filesystem {
         self.vfs.open(path.as_ref()).map(|f| File::VfsFile(f))
     }
 
+    /// Opens the given path for streaming.  See `filesystem::open_stream()`.
+    pub(crate) fn open_stream<P: AsRef<path::Path>>(&mut self, path: P) -> GameResult<File> {
+        self.vfs.open_stream(path.as_ref()).map(|f| File::VfsFile(f))
+    }
+
     /// Opens a file in the user directory with the given `filesystem::OpenOptions`.
     /// Note that even if you open a file read-only, it can only access
     /// files in the user directory.
@@ -333,6 +346,15 @@ pub fn open<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<File
     ctx.filesystem.open(path)
 }
 
+/// Opens the given path for reading a bit at a time, like `open()`
+/// but without reading the whole file into memory first when it's in
+/// the resources zip file.  Reading it is a bit slower, and seeking
+/// backwards in it much slower, so use it for big files that are
+/// read from start to finish, like music.
+pub fn open_stream<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<File> {
+    ctx.filesystem.open_stream(path)
+}
+
 /// Opens a file in the user directory with the given `filesystem::OpenOptions`.
 /// Note that even if you open a file read-only, it can only access
 /// files in the user directory.
@@ -54,8 +54,9 @@ impl Canvas {
         };
         let kind = Kind::D2(width, height, aa);
         let levels = 1;
-        let color_format = ctx.gfx_context.color_format();
-        let factory = &mut ctx.gfx_context.factory;
+        let gfx = ctx.try_gfx_mut()?;
+        let color_format = gfx.color_format();
+        let factory = &mut gfx.factory;
         let texture_create_info = gfx::texture::Info {
             kind: kind,
             levels: levels,
@@ -83,7 +84,7 @@ impl Canvas {
             image: Image {
                 texture: resource,
                 texture_handle: tex,
-                sampler_info: ctx.gfx_context.default_sampler_info,
+                sampler_info: gfx.default_sampler_info,
                 blend_mode: None,
                 width,
                 height,
@@ -146,10 +147,11 @@ pub fn set_canvas(ctx: &mut Context, target: Option<&Canvas>) {
     match target {
         Some(surface) => {
             surface.debug_id.assert(ctx);
-            ctx.gfx_context.data.out = surface.target.clone();
+            ctx.gfx_mut().data.out = surface.target.clone();
         }
         None => {
-            ctx.gfx_context.data.out = ctx.gfx_context.screen_render_target.clone();
+            let gfx = ctx.gfx_mut();
+            gfx.data.out = gfx.screen_render_target.clone();
         }
     };
 }
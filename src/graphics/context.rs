@@ -57,6 +57,12 @@ where
     pub(crate) shaders: Vec<Box<dyn ShaderHandle<B>>>,
 
     pub(crate) glyph_brush: GlyphBrush<'static, B::Resources, B::Factory>,
+
+    pub(crate) render_counter:
+        RenderCounter<gfx::handle::ShaderResourceView<B::Resources, ShaderResourceType>>,
+    /// Created the first time a frame is presented, since finding out
+    /// whether timer queries are supported needs the GL device.
+    pub(crate) gpu_timer: Option<GpuTimer>,
 }
 
 impl<B> fmt::Debug for GraphicsContextGeneric<B>
@@ -290,6 +296,9 @@ where
             shaders: vec![draw],
 
             glyph_brush,
+
+            render_counter: RenderCounter::new(),
+            gpu_timer: None,
         };
         gfx.set_window_mode(window_mode)?;
 
@@ -385,6 +394,14 @@ where
         let id = (*self.current_shader.borrow()).unwrap_or(self.default_shader);
         let shader_handle = &self.shaders[id];
 
+        let instances = slice.instances.map(|(count, _)| count).unwrap_or(1);
+        self.render_counter.record_draw(
+            slice.end - slice.start,
+            instances,
+            &self.data.tex.0,
+            id,
+            shader_handle.get_blend_mode(),
+        );
         shader_handle.draw(&mut self.encoder, slice, &self.data)?;
         Ok(())
     }
@@ -530,6 +547,15 @@ where
         }
     }
 
+    /// Updates the hidpi factor after the OS changes it, such as when
+    /// the window moves to a different monitor.
+    pub(crate) fn set_os_hidpi_factor(&mut self, os_hidpi_factor: f32, hidpi: bool) {
+        self.os_hidpi_factor = os_hidpi_factor;
+        if hidpi {
+            self.hidpi_factor = os_hidpi_factor;
+        }
+    }
+
     /// This is a filthy hack allow users to override hidpi
     /// scaling if they want to.  Everything that winit touches
     /// is scaled by the hidpi factor that it uses, such as monitor
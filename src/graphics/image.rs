@@ -159,10 +159,11 @@ impl Image {
         rgba: &[u8],
     ) -> GameResult<Self> {
         let debug_id = DebugId::get(context);
-        let color_format = context.gfx_context.color_format();
+        let gfx = context.try_gfx_mut()?;
+        let color_format = gfx.color_format();
         Self::make_raw(
-            &mut *context.gfx_context.factory,
-            &context.gfx_context.default_sampler_info,
+            &mut *gfx.factory,
+            &gfx.default_sampler_info,
             width,
             height,
             rgba,
@@ -176,7 +177,7 @@ impl Image {
         use gfx::memory::Typed;
         use gfx::traits::FactoryExt;
 
-        let gfx = &mut ctx.gfx_context;
+        let gfx = ctx.try_gfx_mut()?;
         let w = self.width;
         let h = self.height;
 
@@ -324,7 +325,7 @@ impl Drawable for Image {
         self.debug_id.assert(ctx);
 
         // println!("Matrix: {:#?}", param.matrix);
-        let gfx = &mut ctx.gfx_context;
+        let gfx = ctx.try_gfx_mut()?;
         let src_width = param.src.w;
         let src_height = param.src.h;
         // We have to mess with the scale to make everything
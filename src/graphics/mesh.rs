@@ -348,7 +348,8 @@ impl MeshBuilder {
     /// Takes the accumulated geometry and load it into GPU memory,
     /// creating a single `Mesh`.
     pub fn build(&self, ctx: &mut Context) -> GameResult<Mesh> {
-        let (vbuf, slice) = ctx.gfx_context
+        let (vbuf, slice) = ctx
+            .try_gfx_mut()?
             .factory
             .create_vertex_buffer_with_slice(&self.buffer.vertices[..], &self.buffer.indices[..]);
 
@@ -488,7 +489,8 @@ impl Mesh {
         V: Into<Vertex> + Clone,
     {
         let verts: Vec<Vertex> = verts.iter().cloned().map(|v| v.into()).collect();
-        let (vbuf, slice) = ctx.gfx_context
+        let (vbuf, slice) = ctx
+            .gfx_mut()
             .factory
             .create_vertex_buffer_with_slice(&verts[..], indices);
         Mesh {
@@ -507,7 +509,7 @@ impl Drawable for Mesh {
     {
         let param = param.into();
         self.debug_id.assert(ctx);
-        let gfx = &mut ctx.gfx_context;
+        let gfx = ctx.try_gfx_mut()?;
         gfx.update_instance_properties(param)?;
 
         gfx.data.vbuf = self.buffer.clone();
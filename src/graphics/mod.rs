@@ -9,6 +9,7 @@
 use std::collections::HashMap;
 use std::convert::From;
 use std::fmt;
+use std::time;
 use std::u16;
 
 use gfx;
@@ -31,6 +32,7 @@ mod drawparam;
 mod image;
 mod mesh;
 mod shader;
+mod stats;
 mod text;
 mod types;
 use mint;
@@ -44,6 +46,7 @@ pub use self::drawparam::*;
 pub use self::image::*;
 pub use self::mesh::*;
 pub use self::shader::*;
+pub use self::stats::*;
 pub use self::text::*;
 pub use self::types::*;
 
@@ -361,7 +364,7 @@ impl From<gfx::buffer::CreationError> for GameError {
 /// Clear the screen to the background color.
 /// TODO: Into<Color> ?
 pub fn clear(ctx: &mut Context, color: Color) {
-    let gfx = &mut ctx.gfx_context;
+    let gfx = ctx.gfx_mut();
     // SRGB BUGGO: Only convert when drawing on srgb surface?
     // I actually can't make it make any difference; fiddle more.
     let linear_color: types::LinearColor = color.into();
@@ -390,15 +393,26 @@ where
 /// Tells the graphics system to actually put everything on the screen.
 /// Call this at the end of your `EventHandler`'s `draw()` method.
 ///
-/// Unsets any active canvas.
+/// Unsets any active canvas, and starts the `RenderStats` over for
+/// the next frame.
 pub fn present(ctx: &mut Context) -> GameResult<()> {
-    let gfx = &mut ctx.gfx_context;
+    let gfx = ctx.try_gfx_mut()?;
     gfx.data.out = gfx.screen_render_target.clone();
+    if gfx.gpu_timer.is_none() {
+        gfx.gpu_timer = Some(GpuTimer::new(&mut gfx.device));
+    }
+    if let Some(ref mut timer) = gfx.gpu_timer {
+        timer.begin(&mut gfx.device);
+    }
     // We might want to give the user more control over when the
     // encoder gets flushed eventually, if we want them to be able
     // to do their own gfx drawing.  HOWEVER, the whole pipeline type
     // thing is a bigger hurdle, so this is fine for now.
     gfx.encoder.flush(&mut *gfx.device);
+    if let Some(ref mut timer) = gfx.gpu_timer {
+        timer.end(&mut gfx.device);
+    }
+    gfx.render_counter.finish_frame();
     gfx.window.swap_buffers()?;
     gfx.device.cleanup();
     Ok(())
@@ -410,7 +424,7 @@ pub fn screenshot(ctx: &mut Context) -> GameResult<Image> {
     use gfx::memory::Bind;
     let debug_id = DebugId::get(ctx);
 
-    let gfx = &mut ctx.gfx_context;
+    let gfx = ctx.try_gfx_mut()?;
     let (w, h, _depth, aa) = gfx.data.out.get_dimensions();
     let surface_format = gfx.color_format();
     let gfx::format::Format(surface_type, channel_type) = surface_format;
@@ -581,7 +595,7 @@ pub fn rectangle(ctx: &mut Context, color: Color, mode: DrawMode, rect: Rect) ->
 
 /// Get the default filter mode for new images.
 pub fn get_default_filter(ctx: &Context) -> FilterMode {
-    let gfx = &ctx.gfx_context;
+    let gfx = ctx.gfx();
     gfx.default_sampler_info.filter.into()
 }
 
@@ -589,28 +603,59 @@ pub fn get_default_filter(ctx: &Context) -> FilterMode {
 /// It is supposed to be human-readable and will change; do not try to parse
 /// information out of it!
 pub fn get_renderer_info(ctx: &Context) -> GameResult<String> {
-    let backend_info = ctx.gfx_context.backend_spec.get_info(&*ctx.gfx_context.device);
+    let gfx = ctx.try_gfx()?;
+    let backend_info = gfx.backend_spec.get_info(&*gfx.device);
     Ok(format!(
         "Requested OpenGL {}.{} Core profile, actually got {}.",
-        ctx.gfx_context.backend_spec.major, ctx.gfx_context.backend_spec.minor,
+        gfx.backend_spec.major, gfx.backend_spec.minor,
         backend_info
     ))
 }
 
+/// Returns how much rendering work has been done so far in the frame
+/// being drawn, since the last call to `present()`.  A headless
+/// `Context` never draws anything, so its stats are all zero.
+pub fn get_render_stats(ctx: &Context) -> RenderStats {
+    ctx.try_gfx()
+        .map(|gfx| gfx.render_counter.current())
+        .unwrap_or_default()
+}
+
+/// Returns how much rendering work was done in the frame last
+/// finished by `present()`.
+pub fn get_last_render_stats(ctx: &Context) -> RenderStats {
+    ctx.try_gfx()
+        .map(|gfx| gfx.render_counter.last())
+        .unwrap_or_default()
+}
+
+/// Returns how long the GPU took to draw a recent frame, as measured
+/// by OpenGL timer queries.  The GPU runs behind the CPU, so this is
+/// usually the frame from one or two `present()`s ago.
+///
+/// Returns `None` if the driver doesn't support timer queries, no
+/// frame's timing has come back yet, or the `Context` is headless.
+pub fn get_gpu_frame_time(ctx: &Context) -> Option<time::Duration> {
+    ctx.try_gfx()
+        .ok()
+        .and_then(|gfx| gfx.gpu_timer.as_ref())
+        .and_then(|timer| timer.last_time())
+}
+
 /// Returns a rectangle defining the coordinate system of the screen.
 /// It will be `Rect { x: left, y: top, w: width, h: height }`
 ///
 /// If the Y axis increases downwards, the `height` of the Rect
 /// will be negative.
 pub fn get_screen_coordinates(ctx: &Context) -> Rect {
-    ctx.gfx_context.screen_rect
+    ctx.gfx().screen_rect
 }
 
 /// Sets the default filter mode used to scale images.
 ///
 /// This does not apply retroactively to already created images.
 pub fn set_default_filter(ctx: &mut Context, mode: FilterMode) {
-    let gfx = &mut ctx.gfx_context;
+    let gfx = ctx.gfx_mut();
     let new_mode = mode.into();
     let sampler_info = texture::SamplerInfo::new(new_mode, texture::WrapMode::Clamp);
     // We create the sampler now so we don't end up creating it at some
@@ -630,7 +675,7 @@ pub fn set_default_filter(ctx: &mut Context, mode: FilterMode) {
 /// The `Rect`'s x and y will define the top-left corner of the screen,
 /// and that plus its w and h will define the bottom-right corner.
 pub fn set_screen_coordinates(context: &mut Context, rect: Rect) -> GameResult {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.try_gfx_mut()?;
     gfx.set_projection_rect(rect);
     gfx.calculate_transform_matrix();
     gfx.update_globals()
@@ -642,7 +687,7 @@ pub fn set_screen_coordinates(context: &mut Context, rect: Rect) -> GameResult {
 /// You must call `apply_transformations(ctx)` after calling this to apply
 /// these changes and recalculate the underlying MVP matrix.
 pub fn set_projection(context: &mut Context, proj: Matrix4) {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     gfx.set_projection(proj);
 }
 
@@ -651,14 +696,14 @@ pub fn set_projection(context: &mut Context, proj: Matrix4) {
 /// You must call `apply_transformations(ctx)` after calling this to apply
 /// these changes and recalculate the underlying MVP matrix.
 pub fn transform_projection(context: &mut Context, transform: Matrix4) {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     let curr = gfx.get_projection();
     gfx.set_projection(transform * curr);
 }
 
 /// Gets a copy of the context's raw projection matrix
 pub fn get_projection(context: &Context) -> Matrix4 {
-    let gfx = &context.gfx_context;
+    let gfx = context.gfx();
     gfx.get_projection()
 }
 
@@ -672,7 +717,7 @@ pub fn get_projection(context: &Context) -> Matrix4 {
 /// A `DrawParam` can be converted into an appropriate transform
 /// matrix by calling `param.into_matrix()`.
 pub fn push_transform(context: &mut Context, transform: Option<Matrix4>) {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     if let Some(t) = transform {
         gfx.push_transform(t);
     } else {
@@ -689,7 +734,7 @@ pub fn push_transform(context: &mut Context, transform: Option<Matrix4>) {
 /// You must call `apply_transformations(ctx)` after calling this to apply
 /// these changes and recalculate the underlying MVP matrix.
 pub fn pop_transform(context: &mut Context) {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     gfx.pop_transform();
 }
 
@@ -702,13 +747,13 @@ pub fn pop_transform(context: &mut Context) {
 /// A `DrawParam` can be converted into an appropriate transform
 /// matrix by calling `param.into_matrix()`.
 pub fn set_transform(context: &mut Context, transform: Matrix4) {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     gfx.set_transform(transform);
 }
 
 /// Gets a copy of the context's current transform matrix
 pub fn get_transform(context: &Context) -> Matrix4 {
-    let gfx = &context.gfx_context;
+    let gfx = context.gfx();
     gfx.get_transform()
 }
 
@@ -720,7 +765,7 @@ pub fn get_transform(context: &Context) -> Matrix4 {
 /// A `DrawParam` can be converted into an appropriate transform
 /// matrix by calling `param.into_matrix()`.
 pub fn transform(context: &mut Context, transform: Matrix4) {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     let curr = gfx.get_transform();
     gfx.set_transform(transform * curr);
 }
@@ -730,7 +775,7 @@ pub fn transform(context: &mut Context, transform: Matrix4) {
 /// You must call `apply_transformations(ctx)` after calling this to apply
 /// these changes and recalculate the underlying MVP matrix.
 pub fn origin(context: &mut Context) {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     gfx.set_transform(Matrix4::identity());
 }
 
@@ -738,14 +783,14 @@ pub fn origin(context: &mut Context) {
 /// based on the matrices at the top of the transform and view matrix stacks
 /// and sends it to the graphics card.
 pub fn apply_transformations(context: &mut Context) -> GameResult {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.try_gfx_mut()?;
     gfx.calculate_transform_matrix();
     gfx.update_globals()
 }
 
 /// Sets the blend mode of the currently active shader program
 pub fn set_blend_mode(ctx: &mut Context, mode: BlendMode) -> GameResult {
-    ctx.gfx_context.set_blend_mode(mode)
+    ctx.try_gfx_mut()?.set_blend_mode(mode)
 }
 
 /// Sets the window mode, such as the size and other properties.
@@ -755,8 +800,7 @@ pub fn set_blend_mode(ctx: &mut Context, mode: BlendMode) -> GameResult {
 /// It is recommended to call `set_screen_coordinates()` after changing the window
 /// size to make sure everything is what you want it to be.
 pub fn set_mode(context: &mut Context, mode: WindowMode) -> GameResult {
-    let gfx = &mut context.gfx_context;
-    gfx.set_window_mode(mode)?;
+    context.try_gfx_mut()?.set_window_mode(mode)?;
     // Save updated mode.
     context.conf.window_mode = mode;
     Ok(())
@@ -792,13 +836,13 @@ pub fn set_window_icon<P: AsRef<Path>>(context: &Context, path: Option<P>) -> Ga
         Some(path) => Some(Icon::from_path(path)?),
         None => None,
     };
-    context.gfx_context.window.set_window_icon(icon);
+    context.try_gfx()?.window.set_window_icon(icon);
     Ok(())
 }
 
 /// Sets the window title.
 pub fn set_window_title(context: &Context, title: &str) {
-    context.gfx_context.window.set_title(title);
+    context.gfx().window.set_title(title);
 }
 
 /// Returns a reference to the SDL window.
@@ -806,7 +850,7 @@ pub fn set_window_title(context: &Context, title: &str) {
 /// would provide all the functions you need without having
 /// to dip into SDL itself.  But life isn't always ideal.
 pub fn get_window(context: &Context) -> &glutin::Window {
-    let gfx = &context.gfx_context;
+    let gfx = context.gfx();
     &gfx.window
 }
 
@@ -816,7 +860,7 @@ pub fn get_window(context: &Context) -> &glutin::Window {
 /// TODO: Rename, since get_drawable_size is usually what we
 /// actually want
 pub fn get_size(context: &Context) -> (f64, f64) {
-    let gfx = &context.gfx_context;
+    let gfx = context.gfx();
     gfx.window
         .get_outer_size()
         .map(|logical_size| (logical_size.width, logical_size.height))
@@ -828,19 +872,19 @@ pub fn get_size(context: &Context) -> (f64, f64) {
 /// is true this is equal to `get_os_hidpi_factor()`,
 /// otherwise it is `1.0`.
 pub fn get_hidpi_factor(context: &Context) -> f32 {
-    context.gfx_context.hidpi_factor
+    context.gfx().hidpi_factor
 }
 
 /// Returns the hidpi pixel scaling factor that the operating
 /// system says that ggez should be using.
 pub fn get_os_hidpi_factor(context: &Context) -> f32 {
-    context.gfx_context.os_hidpi_factor
+    context.gfx().os_hidpi_factor
 }
 
 /// Returns the size of the window's underlying drawable in pixels as (width, height).
 /// Returns zeros if window doesn't exist.
 pub fn get_drawable_size(context: &Context) -> (f64, f64) {
-    let gfx = &context.gfx_context;
+    let gfx = context.gfx();
     gfx.window
         .get_inner_size()
         .map(|logical_size| (logical_size.width, logical_size.height))
@@ -849,13 +893,13 @@ pub fn get_drawable_size(context: &Context) -> (f64, f64) {
 
 /// Returns the gfx-rs `Factory` object for ggez's rendering context.
 pub fn get_factory(context: &mut Context) -> &mut gfx_device_gl::Factory {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     &mut gfx.factory
 }
 
 /// Returns the gfx-rs `Device` object for ggez's rendering context.
 pub fn get_device(context: &mut Context) -> &mut gfx_device_gl::Device {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     gfx.device.as_mut()
 }
 
@@ -863,7 +907,7 @@ pub fn get_device(context: &mut Context) -> &mut gfx_device_gl::Device {
 pub fn get_encoder(
     context: &mut Context,
 ) -> &mut gfx::Encoder<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer> {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     &mut gfx.encoder
 }
 
@@ -871,7 +915,7 @@ pub fn get_encoder(
 pub fn get_depth_view(
     context: &mut Context,
 ) -> gfx::handle::RawDepthStencilView<gfx_device_gl::Resources> {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     gfx.depth_view.clone()
 }
 
@@ -879,7 +923,7 @@ pub fn get_depth_view(
 pub fn get_screen_render_target(
     context: &Context,
 ) -> gfx::handle::RawRenderTargetView<gfx_device_gl::Resources> {
-    let gfx = &context.gfx_context;
+    let gfx = context.gfx();
     gfx.data.out.clone()
 }
 
@@ -901,7 +945,7 @@ pub fn get_gfx_objects(
     gfx::handle::RawDepthStencilView<<GlBackendSpec as BackendSpec>::Resources>,
     gfx::handle::RawRenderTargetView<<GlBackendSpec as BackendSpec>::Resources>,
 ) {
-    let gfx = &mut context.gfx_context;
+    let gfx = context.gfx_mut();
     let f = &mut gfx.factory;
     let d = gfx.device.as_mut();
     let e = &mut gfx.encoder;
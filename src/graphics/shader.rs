@@ -309,21 +309,22 @@ where
         blend_modes: Option<&[BlendMode]>,
     ) -> GameResult<Shader<C>> {
         let debug_id = DebugId::get(ctx);
-        let color_format = ctx.gfx_context.color_format();
+        let gfx = ctx.try_gfx_mut()?;
+        let color_format = gfx.color_format();
         let (mut shader, draw) = create_shader(
             vertex_source,
             pixel_source,
             consts,
             name,
-            &mut ctx.gfx_context.encoder,
-            &mut *ctx.gfx_context.factory,
-            ctx.gfx_context.multisample_samples,
+            &mut gfx.encoder,
+            &mut *gfx.factory,
+            gfx.multisample_samples,
             blend_modes,
             color_format,
             debug_id,
         )?;
-        shader.id = ctx.gfx_context.shaders.len();
-        ctx.gfx_context.shaders.push(draw);
+        shader.id = gfx.shaders.len();
+        gfx.shaders.push(draw);
 
         Ok(shader)
     }
@@ -341,7 +342,7 @@ where
 {
     /// Send data to the GPU for use with the `Shader`
     pub fn send(&self, ctx: &mut Context, consts: C) -> GameResult {
-        ctx.gfx_context
+        ctx.try_gfx_mut()?
             .encoder
             .update_buffer(&self.buffer, &[consts], 0)?;
         Ok(())
@@ -443,7 +444,7 @@ where
     C: Structure<ConstFormat>,
 {
     ps.debug_id.assert(ctx);
-    let cell = Rc::clone(&ctx.gfx_context.current_shader);
+    let cell = Rc::clone(&ctx.gfx().current_shader);
     let previous_shader = *cell.borrow();
     set_shader(ctx, ps);
     ShaderLock {
@@ -458,7 +459,7 @@ where
     C: Structure<ConstFormat>,
 {
     ps.debug_id.assert(ctx);
-    *ctx.gfx_context.current_shader.borrow_mut() = Some(ps.id);
+    *ctx.gfx().current_shader.borrow_mut() = Some(ps.id);
 }
 
 /// Clears the the current shader for the Context, restoring the default shader.
@@ -466,7 +467,7 @@ where
 /// However, calling this and then dropping a `ShaderLock` will still set the
 /// shader to whatever was set when the `ShaderLock` was created.
 pub fn clear_shader(ctx: &mut Context) {
-    *ctx.gfx_context.current_shader.borrow_mut() = None;
+    *ctx.gfx().current_shader.borrow_mut() = None;
 }
 
 #[derive(Debug)]
@@ -81,6 +81,7 @@ impl SpriteBatch {
         // We have a Context, and *everything* must pass through this
         // function to be drawn, so.
         // Though we do awkwardly have to allocate a new vector.
+        let srgb = ctx.try_gfx()?.is_srgb();
         let new_sprites = self.sprites
             .iter()
             .map(|param| {
@@ -98,11 +99,11 @@ impl SpriteBatch {
                 // with graphics::set_color(); this just inherits from that.
                 new_param.color = new_param.color;
                 let primitive_param = graphics::DrawTransform::from(new_param);
-                primitive_param.to_instance_properties(ctx.gfx_context.is_srgb())
+                primitive_param.to_instance_properties(srgb)
             })
             .collect::<Vec<_>>();
 
-        let gfx = &mut ctx.gfx_context;
+        let gfx = ctx.try_gfx_mut()?;
         if gfx.data.rect_instance_properties.len() < self.sprites.len() {
             gfx.data.rect_instance_properties = gfx.factory.create_buffer(
                 self.sprites.len(),
@@ -152,7 +153,7 @@ impl graphics::Drawable for SpriteBatch {
         // Awkwardly we must update values on all sprites and such.
         // Also awkwardly we have this chain of colors with differing priorities.
         self.flush(ctx, &self.image)?;
-        let gfx = &mut ctx.gfx_context;
+        let gfx = ctx.try_gfx_mut()?;
         let sampler = gfx.samplers
             .get_or_insert(self.image.sampler_info, gfx.factory.as_mut());
         gfx.data.vbuf = gfx.quad_vertex_buffer.clone();
@@ -0,0 +1,241 @@
+//! Counting what the renderer does each frame, and timing how long
+//! the GPU takes to do it.
+
+use std::collections::VecDeque;
+use std::time;
+
+use gfx_device_gl;
+
+use super::{BlendMode, ShaderId};
+
+/// How much rendering work was done during a frame.  Get it with
+/// `graphics::get_render_stats()` while drawing a frame, or
+/// `graphics::get_last_render_stats()` for the frame last finished
+/// by `graphics::present()`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// How many draw calls were made.
+    pub draw_calls: u32,
+    /// How many vertices were drawn, counting each instance of an
+    /// instanced draw such as a `SpriteBatch` separately.  Text isn't
+    /// counted, since `gfx_glyph` builds its vertices itself.
+    pub vertices: u64,
+    /// How many draws used a different texture from the draw before
+    /// them.
+    pub texture_binds: u32,
+    /// How many draws used a different shader or blend mode, and so
+    /// a different pipeline state object, from the draw before them.
+    pub pipeline_switches: u32,
+    /// How many times queued text was drawn.  Each of these is also
+    /// a draw call.  Any glyphs uploaded to `gfx_glyph`'s cache
+    /// texture along the way aren't counted, since it doesn't tell us
+    /// about them.
+    pub text_draws: u32,
+}
+
+/// Keeps the `RenderStats` up to date as things are drawn.  `T` is
+/// the texture handle type, compared to tell when a draw binds a new
+/// texture.
+#[derive(Debug)]
+pub(crate) struct RenderCounter<T> {
+    current: RenderStats,
+    last: RenderStats,
+    last_texture: Option<T>,
+    last_pipeline: Option<(ShaderId, BlendMode)>,
+}
+
+impl<T> RenderCounter<T>
+where
+    T: PartialEq + Clone,
+{
+    pub(crate) fn new() -> Self {
+        RenderCounter {
+            current: RenderStats::default(),
+            last: RenderStats::default(),
+            last_texture: None,
+            last_pipeline: None,
+        }
+    }
+
+    /// Counts a draw call of `vertices` vertices, `instances` times,
+    /// with the given texture, shader and blend mode.
+    pub(crate) fn record_draw(
+        &mut self,
+        vertices: u32,
+        instances: u32,
+        texture: &T,
+        shader: ShaderId,
+        mode: BlendMode,
+    ) {
+        let stats = &mut self.current;
+        stats.draw_calls += 1;
+        stats.vertices += u64::from(vertices) * u64::from(instances);
+        if self.last_texture.as_ref() != Some(texture) {
+            stats.texture_binds += 1;
+            self.last_texture = Some(texture.clone());
+        }
+        if self.last_pipeline != Some((shader, mode)) {
+            stats.pipeline_switches += 1;
+            self.last_pipeline = Some((shader, mode));
+        }
+    }
+
+    /// Counts a draw of queued text.  `gfx_glyph` uses its own
+    /// pipeline and texture, so whatever is drawn next has to switch
+    /// back.
+    pub(crate) fn record_text_draw(&mut self) {
+        self.current.draw_calls += 1;
+        self.current.text_draws += 1;
+        self.last_texture = None;
+        self.last_pipeline = None;
+    }
+
+    /// Returns the stats for the frame being drawn.
+    pub(crate) fn current(&self) -> RenderStats {
+        self.current
+    }
+
+    /// Returns the stats for the last finished frame.
+    pub(crate) fn last(&self) -> RenderStats {
+        self.last
+    }
+
+    /// Finishes the frame, starting the counts over from zero.
+    pub(crate) fn finish_frame(&mut self) {
+        self.last = self.current;
+        self.current = RenderStats::default();
+        self.last_texture = None;
+        self.last_pipeline = None;
+    }
+}
+
+// These aren't in every version of the `gl` bindings gfx uses, so
+// they're spelled out here.
+const GL_TIME_ELAPSED: u32 = 0x88BF;
+const GL_QUERY_RESULT: u32 = 0x8866;
+const GL_QUERY_RESULT_AVAILABLE: u32 = 0x8867;
+
+/// How many frames can be waiting on their timing at once.  Results
+/// come back a frame or two late; if the GPU falls further behind
+/// than this, frames go untimed rather than stalling to wait.
+const GPU_QUERY_COUNT: usize = 4;
+
+/// Times the GPU work for each frame with OpenGL timer queries.
+/// Everything ggez draws in a frame is sent to the GPU in one go when
+/// `present()` flushes the encoder, so timing that flush times the
+/// whole frame.
+#[derive(Debug)]
+pub(crate) struct GpuTimer {
+    supported: bool,
+    free: Vec<u32>,
+    in_flight: VecDeque<u32>,
+    current: Option<u32>,
+    last_time: Option<time::Duration>,
+}
+
+impl GpuTimer {
+    /// Creates the timer's queries, if the driver supports them.
+    /// They're core in OpenGL 3.3, and otherwise need the
+    /// `GL_ARB_timer_query` extension.
+    ///
+    /// The queries are never deleted; there are only a few of them
+    /// and they live as long as the graphics context does anyway.
+    pub(crate) fn new(device: &mut gfx_device_gl::Device) -> Self {
+        let supported = {
+            let info = device.get_info();
+            !info.version.is_embedded
+                && info.is_version_or_extension_supported(3, 3, "GL_ARB_timer_query")
+        };
+        let mut free = vec![0; GPU_QUERY_COUNT];
+        if supported {
+            unsafe {
+                device.with_gl(|gl| gl.GenQueries(GPU_QUERY_COUNT as i32, free.as_mut_ptr()));
+            }
+        } else {
+            free.clear();
+        }
+        GpuTimer {
+            supported,
+            free,
+            in_flight: VecDeque::with_capacity(GPU_QUERY_COUNT),
+            current: None,
+            last_time: None,
+        }
+    }
+
+    /// Collects the results of any finished queries, then starts
+    /// timing if there's a query free.
+    pub(crate) fn begin(&mut self, device: &mut gfx_device_gl::Device) {
+        if !self.supported {
+            return;
+        }
+        let in_flight = &mut self.in_flight;
+        let free = &mut self.free;
+        let last_time = &mut self.last_time;
+        let current = &mut self.current;
+        unsafe {
+            device.with_gl(|gl| {
+                while let Some(&query) = in_flight.front() {
+                    let mut available = 0;
+                    gl.GetQueryObjectiv(query, GL_QUERY_RESULT_AVAILABLE, &mut available);
+                    if available == 0 {
+                        break;
+                    }
+                    let mut nanos = 0;
+                    gl.GetQueryObjectui64v(query, GL_QUERY_RESULT, &mut nanos);
+                    *last_time = Some(time::Duration::from_nanos(nanos));
+                    let _ = in_flight.pop_front();
+                    free.push(query);
+                }
+                *current = free.pop();
+                if let Some(query) = *current {
+                    gl.BeginQuery(GL_TIME_ELAPSED, query);
+                }
+            });
+        }
+    }
+
+    /// Stops timing, if `begin()` started.
+    pub(crate) fn end(&mut self, device: &mut gfx_device_gl::Device) {
+        if let Some(query) = self.current.take() {
+            unsafe {
+                device.with_gl(|gl| gl.EndQuery(GL_TIME_ELAPSED));
+            }
+            self.in_flight.push_back(query);
+        }
+    }
+
+    /// Returns how long the GPU took to draw the most recent frame
+    /// whose timing has come back.
+    pub(crate) fn last_time(&self) -> Option<time::Duration> {
+        self.last_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_render_counter_counts_state_changes() {
+        let mut counter = RenderCounter::new();
+        counter.record_draw(6, 1, &1, 0, BlendMode::Alpha);
+        counter.record_draw(6, 1, &1, 0, BlendMode::Alpha);
+        counter.record_draw(6, 10, &2, 0, BlendMode::Alpha);
+        counter.record_draw(6, 1, &2, 0, BlendMode::Add);
+        counter.record_text_draw();
+        counter.record_draw(6, 1, &2, 0, BlendMode::Add);
+        let stats = counter.current();
+        assert_eq!(stats.draw_calls, 6);
+        assert_eq!(stats.vertices, 6 * 14);
+        assert_eq!(stats.texture_binds, 3);
+        assert_eq!(stats.pipeline_switches, 3);
+        assert_eq!(stats.text_draws, 1);
+
+        counter.finish_frame();
+        assert_eq!(counter.last(), stats);
+        assert_eq!(counter.current(), RenderStats::default());
+        counter.record_draw(6, 1, &2, 0, BlendMode::Add);
+        assert_eq!(counter.current().texture_binds, 1);
+    }
+}
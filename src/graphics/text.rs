@@ -326,7 +326,7 @@ impl Text {
             let varied_section = self.generate_varied_section(Point2::new(0.0, 0.0), None);
             let glyphed_section_texts = self
                 .layout
-                .calculate_glyphs(context.gfx_context.glyph_brush.fonts(), &varied_section);
+                .calculate_glyphs(context.gfx().glyph_brush.fonts(), &varied_section);
             for glyphed_section_text in &glyphed_section_texts {
                 let (ref positioned_glyph, ..) = glyphed_section_text;
                 if let Some(rect) = positioned_glyph.pixel_bounding_box() {
@@ -385,6 +385,8 @@ impl Drawable for Text {
         //     param.offset.y * self.height(ctx) as f32,
         // );
         // let param = param.offset(offset);
+        // `queue_text()` can't report a headless context, so check first.
+        let _ = ctx.try_gfx()?;
         queue_text(ctx, self, Point2::new(0.0, 0.0), Some(param.color));
         draw_queued_text(ctx, param)
     }
@@ -418,7 +420,7 @@ impl Font {
         // Take a Cow here to avoid this clone where unnecessary?
         // Nah, let's not complicate things more than necessary.
         let v = bytes.to_vec();
-        let font_id = context.gfx_context.glyph_brush.add_font_bytes(v);
+        let font_id = context.try_gfx_mut()?.glyph_brush.add_font_bytes(v);
 
         Ok(Font { font_id: font_id })
     }
@@ -449,7 +451,7 @@ where
 {
     let p = Point2::from(relative_dest.into());
     let varied_section = batch.generate_varied_section(p, color);
-    context.gfx_context.glyph_brush.queue(varied_section);
+    context.gfx_mut().glyph_brush.queue(varied_section);
 }
 
 /// Exposes `gfx_glyph`'s `GlyphBrush::queue()` and `GlyphBrush::queue_custom_layout()`,
@@ -459,7 +461,7 @@ where
     S: Into<Cow<'a, VariedSection<'a>>>,
     G: GlyphPositioner,
 {
-    let brush = &mut context.gfx_context.glyph_brush;
+    let brush = &mut context.gfx_mut().glyph_brush;
     match custom_layout {
         Some(layout) => brush.queue_custom_layout(section, layout),
         None => brush.queue(section),
@@ -551,17 +553,17 @@ where
 
     // TODO: Does this not handle color?
 
-    let color_format = context.gfx_context.color_format();
-    let depth_format = context.gfx_context.depth_format();
+    let gfx = context.try_gfx_mut()?;
+    gfx.render_counter.record_text_draw();
+    let color_format = gfx.color_format();
+    let depth_format = gfx.depth_format();
     let (encoder, render_tgt, depth_view) = (
-        &mut context.gfx_context.encoder,
-        &context.gfx_context.screen_render_target,
-        &context.gfx_context.depth_view,
+        &mut gfx.encoder,
+        &gfx.screen_render_target,
+        &gfx.depth_view,
     );
 
-    context
-        .gfx_context
-        .glyph_brush
+    gfx.glyph_brush
         .draw_queued_with_transform(
             final_matrix.into(),
             encoder,
@@ -0,0 +1,661 @@
+//! Named input actions, so game code can ask "is the player
+//! jumping?" instead of "is the space bar down?".
+//!
+//! An `InputMap` binds action names to any mix of keys, mouse
+//! buttons and gamepad buttons or sticks, optionally with modifier
+//! keys held, and binds axis names to pairs of those or to gamepad
+//! axes.  The `Context` keeps one `InputMap` and brings the state of
+//! every action up to date once per frame, just before `update()` is
+//! called.  Bindings can be changed at any time, and an `InputMap`
+//! can be saved to and loaded from TOML the same way `conf::Conf` is,
+//! which makes a settings screen for rebinding keys pretty simple.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! use ggez::input::actions::{self, AxisBinding, Binding, InputMap};
+//!
+//! let map = InputMap::new()
+//!     .action("jump", Binding::key(KeyCode::Space))
+//!     .action("jump", Binding::gamepad_button(Button::South))
+//!     .action("save", Binding::key(KeyCode::S).with_mods(KeyMods::CTRL))
+//!     .axis("move_x", AxisBinding::keys(KeyCode::A, KeyCode::D))
+//!     .axis("move_x", AxisBinding::gamepad(Axis::LeftStickX));
+//! actions::set_input_map(ctx, map);
+//!
+//! // Then, in `update()`:
+//! if actions::is_action_just_pressed(ctx, "jump") {
+//!     self.player.jump();
+//! }
+//! self.player.x += actions::get_axis(ctx, "move_x") * SPEED;
+//! ```
+//!
+//! And the same map as TOML:
+//!
+//! ```toml
+//! [actions]
+//! jump = ["Key:Space", "Button:South"]
+//! save = ["Ctrl+Key:S"]
+//!
+//! [[axes.move_x]]
+//! type = "Buttons"
+//! negative = "Key:A"
+//! positive = "Key:D"
+//!
+//! [[axes.move_x]]
+//! type = "Gamepad"
+//! axis = "LeftStickX"
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use toml;
+
+use context::Context;
+use event::{Axis, Button, KeyCode, KeyMods, MouseButton, ScanCode};
+use input::gamepad::{self, GamepadId, ALL_AXES, ALL_BUTTONS};
+use input::keyboard::{self, ALL_KEYCODES};
+use input::mouse;
+use GameError;
+use GameResult;
+
+/// How far a gamepad axis has to be pushed before it counts as
+/// pressed, for `Input::GamepadAxisPositive` and
+/// `Input::GamepadAxisNegative`.
+pub const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+/// A single physical input.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Input {
+    /// A keyboard key.
+    Key(KeyCode),
+    /// A physical keyboard key, whatever the keyboard layout says it
+    /// is; see `keyboard::us_layout_scancode()`.
+    Scancode(ScanCode),
+    /// A mouse button.
+    Mouse(MouseButton),
+    /// A button on any gamepad.
+    GamepadButton(Button),
+    /// An axis on any gamepad, pushed past `AXIS_PRESS_THRESHOLD`
+    /// in the positive direction.
+    GamepadAxisPositive(Axis),
+    /// An axis on any gamepad, pushed past `AXIS_PRESS_THRESHOLD`
+    /// in the negative direction.
+    GamepadAxisNegative(Axis),
+}
+
+/// An input plus the modifier keys that have to be held along with
+/// it.  Extra modifiers being held doesn't stop a binding from
+/// matching, so a binding for `S` still fires while Shift is down.
+///
+/// In TOML a binding is written as a string like `"Key:W"`,
+/// `"Ctrl+Shift+Key:S"`, `"Scancode:17"`, `"Mouse:Left"`, `"Mouse:4"`,
+/// `"Button:South"` or `"Axis:LeftStickY-"`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Binding {
+    /// The input itself.
+    pub input: Input,
+    /// Modifiers that must be held for the binding to match.
+    pub mods: KeyMods,
+}
+
+impl Binding {
+    /// A binding for the given input with no modifiers.
+    pub fn new(input: Input) -> Self {
+        Binding {
+            input,
+            mods: KeyMods::NONE,
+        }
+    }
+
+    /// A binding for a keyboard key.
+    pub fn key(keycode: KeyCode) -> Self {
+        Self::new(Input::Key(keycode))
+    }
+
+    /// A binding for a physical keyboard key.
+    pub fn scancode(scancode: ScanCode) -> Self {
+        Self::new(Input::Scancode(scancode))
+    }
+
+    /// A binding for the physical key that's labelled `keycode` on a
+    /// US keyboard, wherever the current layout puts that label; this
+    /// keeps WASD-style bindings in the same place on AZERTY and
+    /// Dvorak keyboards.  Falls back to `keycode` itself for keys
+    /// `keyboard::us_layout_scancode()` doesn't know.
+    pub fn us_layout_key(keycode: KeyCode) -> Self {
+        match keyboard::us_layout_scancode(keycode) {
+            Some(scancode) => Self::scancode(scancode),
+            None => Self::key(keycode),
+        }
+    }
+
+    /// A binding for a mouse button.
+    pub fn mouse(button: MouseButton) -> Self {
+        Self::new(Input::Mouse(button))
+    }
+
+    /// A binding for a gamepad button.
+    pub fn gamepad_button(button: Button) -> Self {
+        Self::new(Input::GamepadButton(button))
+    }
+
+    /// Requires the given modifiers to be held too.
+    pub fn with_mods(mut self, mods: KeyMods) -> Self {
+        self.mods = mods;
+        self
+    }
+}
+
+const MODIFIER_NAMES: [(KeyMods, &str); 4] = [
+    (KeyMods::SHIFT, "Shift"),
+    (KeyMods::CTRL, "Ctrl"),
+    (KeyMods::ALT, "Alt"),
+    (KeyMods::LOGO, "Logo"),
+];
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(modifier, name) in &MODIFIER_NAMES {
+            if self.mods.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.input {
+            Input::Key(keycode) => write!(f, "Key:{:?}", keycode),
+            Input::Scancode(scancode) => write!(f, "Scancode:{}", scancode),
+            Input::Mouse(MouseButton::Other(n)) => write!(f, "Mouse:{}", n),
+            Input::Mouse(button) => write!(f, "Mouse:{:?}", button),
+            Input::GamepadButton(button) => write!(f, "Button:{:?}", button),
+            Input::GamepadAxisPositive(axis) => write!(f, "Axis:{:?}+", axis),
+            Input::GamepadAxisNegative(axis) => write!(f, "Axis:{:?}-", axis),
+        }
+    }
+}
+
+fn parse_error(s: &str) -> GameError {
+    GameError::ConfigError(format!("Invalid input binding: {:?}", s))
+}
+
+fn parse_keycode(name: &str) -> Option<KeyCode> {
+    ALL_KEYCODES
+        .iter()
+        .find(|keycode| format!("{:?}", keycode) == name)
+        .cloned()
+}
+
+fn parse_mouse_button(name: &str) -> Option<MouseButton> {
+    match name {
+        "Left" => Some(MouseButton::Left),
+        "Right" => Some(MouseButton::Right),
+        "Middle" => Some(MouseButton::Middle),
+        _ => name.parse().ok().map(MouseButton::Other),
+    }
+}
+
+fn parse_button(name: &str) -> Option<Button> {
+    ALL_BUTTONS
+        .iter()
+        .find(|button| format!("{:?}", button) == name)
+        .cloned()
+}
+
+fn parse_axis(name: &str) -> Option<Axis> {
+    ALL_AXES
+        .iter()
+        .find(|axis| format!("{:?}", axis) == name)
+        .cloned()
+}
+
+impl FromStr for Binding {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> GameResult<Binding> {
+        let colon = s.find(':').ok_or_else(|| parse_error(s))?;
+        let (prefix, name) = (&s[..colon], &s[colon + 1..]);
+        let mut parts: Vec<&str> = prefix.split('+').collect();
+        let kind = parts.pop().ok_or_else(|| parse_error(s))?;
+
+        let mut mods = KeyMods::NONE;
+        for part in parts {
+            let &(modifier, _) = MODIFIER_NAMES
+                .iter()
+                .find(|&&(_, modifier_name)| modifier_name == part)
+                .ok_or_else(|| parse_error(s))?;
+            mods |= modifier;
+        }
+
+        let input = match kind {
+            "Key" => parse_keycode(name).map(Input::Key),
+            "Scancode" => name.parse().ok().map(Input::Scancode),
+            "Mouse" => parse_mouse_button(name).map(Input::Mouse),
+            "Button" => parse_button(name).map(Input::GamepadButton),
+            "Axis" if name.ends_with('+') => {
+                parse_axis(&name[..name.len() - 1]).map(Input::GamepadAxisPositive)
+            }
+            "Axis" if name.ends_with('-') => {
+                parse_axis(&name[..name.len() - 1]).map(Input::GamepadAxisNegative)
+            }
+            _ => None,
+        };
+        input
+            .map(|input| Binding { input, mods })
+            .ok_or_else(|| parse_error(s))
+    }
+}
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Binding, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Serializes a gamepad `Axis` by name.
+mod axis_name {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(axis: &Axis, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{:?}", axis))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Axis, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_axis(&s).ok_or_else(|| de::Error::custom(parse_error(&s)))
+    }
+}
+
+/// Something that drives a named axis, which reads from -1 to 1.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AxisBinding {
+    /// A pair of digital inputs; holding `negative` reads as -1 and
+    /// holding `positive` reads as 1.
+    Buttons {
+        /// The input that pushes the axis towards -1.
+        negative: Binding,
+        /// The input that pushes the axis towards 1.
+        positive: Binding,
+    },
+    /// An analog axis on any gamepad, with the gamepad deadzone
+    /// applied.
+    Gamepad {
+        /// Which axis.
+        #[serde(with = "axis_name")]
+        axis: Axis,
+        /// Flips the axis around, for instance for inverted look
+        /// controls.
+        #[serde(default)]
+        inverted: bool,
+    },
+}
+
+impl AxisBinding {
+    /// An axis driven by a pair of keyboard keys.
+    pub fn keys(negative: KeyCode, positive: KeyCode) -> Self {
+        AxisBinding::Buttons {
+            negative: Binding::key(negative),
+            positive: Binding::key(positive),
+        }
+    }
+
+    /// An axis driven by a gamepad axis.
+    pub fn gamepad(axis: Axis) -> Self {
+        AxisBinding::Gamepad {
+            axis,
+            inverted: false,
+        }
+    }
+}
+
+/// A set of named actions and axes and the inputs bound to them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct InputMap {
+    /// Digital actions, by name.  An action is pressed while any of
+    /// its bindings are.
+    #[serde(default)]
+    pub actions: BTreeMap<String, Vec<Binding>>,
+    /// Analog axes, by name.  An axis reads as whichever of its
+    /// bindings is pushed furthest.
+    #[serde(default)]
+    pub axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl InputMap {
+    /// Creates an empty `InputMap`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a binding to the named action.
+    pub fn action(mut self, name: &str, binding: Binding) -> Self {
+        self.actions
+            .entry(name.to_owned())
+            .or_insert_with(Vec::new)
+            .push(binding);
+        self
+    }
+
+    /// Adds a binding to the named axis.
+    pub fn axis(mut self, name: &str, binding: AxisBinding) -> Self {
+        self.axes
+            .entry(name.to_owned())
+            .or_insert_with(Vec::new)
+            .push(binding);
+        self
+    }
+
+    /// Load a TOML file from the given `Read` and attempts to parse
+    /// an `InputMap` from it.
+    pub fn from_toml_file<R: io::Read>(file: &mut R) -> GameResult<InputMap> {
+        let mut s = String::new();
+        let _ = file.read_to_string(&mut s)?;
+        let decoded = toml::from_str(&s)?;
+        Ok(decoded)
+    }
+
+    /// Saves the `InputMap` to the given `Write` object,
+    /// formatted as TOML.
+    pub fn to_toml_file<W: io::Write>(&self, file: &mut W) -> GameResult {
+        let s = toml::to_vec(self)?;
+        file.write_all(&s)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct ActionState {
+    pressed: bool,
+    just_pressed: bool,
+    just_released: bool,
+}
+
+/// Holds the current `InputMap` and the state of each action.
+#[derive(Debug, Default)]
+pub struct ActionContext {
+    map: InputMap,
+    states: HashMap<String, ActionState>,
+}
+
+impl ActionContext {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self, name: &str) -> ActionState {
+        self.states.get(name).cloned().unwrap_or_default()
+    }
+}
+
+fn any_gamepad<F>(ctx: &Context, f: F) -> bool
+where
+    F: Fn(GamepadId) -> bool,
+{
+    gamepad::list_gamepads(ctx).into_iter().any(f)
+}
+
+fn is_input_active(ctx: &Context, input: Input) -> bool {
+    match input {
+        Input::Key(keycode) => keyboard::is_key_pressed(ctx, keycode),
+        Input::Scancode(scancode) => keyboard::is_scancode_pressed(ctx, scancode),
+        Input::Mouse(button) => mouse::get_button_pressed(ctx, button),
+        Input::GamepadButton(button) => {
+            any_gamepad(ctx, |id| gamepad::get_button_pressed(ctx, id, button))
+        }
+        Input::GamepadAxisPositive(axis) => any_gamepad(ctx, |id| {
+            gamepad::get_axis(ctx, id, axis) >= AXIS_PRESS_THRESHOLD
+        }),
+        Input::GamepadAxisNegative(axis) => any_gamepad(ctx, |id| {
+            gamepad::get_axis(ctx, id, axis) <= -AXIS_PRESS_THRESHOLD
+        }),
+    }
+}
+
+/// Whether the input went down at some point this frame, even if
+/// it's been released again since.
+fn is_input_tapped(ctx: &Context, input: Input) -> bool {
+    match input {
+        Input::Key(keycode) => keyboard::is_key_just_pressed(ctx, keycode),
+        Input::Scancode(scancode) => keyboard::is_scancode_just_pressed(ctx, scancode),
+        Input::Mouse(button) => mouse::is_button_just_pressed(ctx, button),
+        Input::GamepadButton(button) => {
+            any_gamepad(ctx, |id| gamepad::is_button_just_pressed(ctx, id, button))
+        }
+        Input::GamepadAxisPositive(_) | Input::GamepadAxisNegative(_) => false,
+    }
+}
+
+fn is_binding_active(ctx: &Context, binding: &Binding) -> bool {
+    keyboard::get_active_mods(ctx).contains(binding.mods) && is_input_active(ctx, binding.input)
+}
+
+fn is_binding_tapped(ctx: &Context, binding: &Binding) -> bool {
+    keyboard::get_active_mods(ctx).contains(binding.mods) && is_input_tapped(ctx, binding.input)
+}
+
+fn axis_binding_value(ctx: &Context, binding: &AxisBinding) -> f32 {
+    match *binding {
+        AxisBinding::Buttons {
+            ref negative,
+            ref positive,
+        } => {
+            let mut value = 0.0;
+            if is_binding_active(ctx, negative) {
+                value -= 1.0;
+            }
+            if is_binding_active(ctx, positive) {
+                value += 1.0;
+            }
+            value
+        }
+        AxisBinding::Gamepad { axis, inverted } => {
+            let value = gamepad::list_gamepads(ctx)
+                .into_iter()
+                .map(|id| gamepad::get_axis(ctx, id, axis))
+                .fold(0.0, furthest);
+            if inverted {
+                -value
+            } else {
+                value
+            }
+        }
+    }
+}
+
+/// Whichever of the two values is further from 0.
+fn furthest(a: f32, b: f32) -> f32 {
+    if b.abs() > a.abs() {
+        b
+    } else {
+        a
+    }
+}
+
+/// Brings the state of every action up to date with the current
+/// input.  Called by `event::run()` and friends once per frame.
+///
+/// An input that was pressed and released within the same frame
+/// makes its action both just pressed and just released, so quick
+/// taps aren't lost.
+pub(crate) fn update(ctx: &mut Context) {
+    let inputs: Vec<(bool, bool)> = ctx
+        .action_context
+        .map
+        .actions
+        .values()
+        .map(|bindings| {
+            let active = bindings.iter().any(|b| is_binding_active(ctx, b));
+            let tapped = !active && bindings.iter().any(|b| is_binding_tapped(ctx, b));
+            (active, tapped)
+        })
+        .collect();
+    let actions = &mut ctx.action_context;
+    for (name, (active, tapped)) in actions.map.actions.keys().zip(inputs) {
+        if !actions.states.contains_key(name) {
+            let _ = actions.states.insert(name.clone(), ActionState::default());
+        }
+        if let Some(state) = actions.states.get_mut(name) {
+            state.just_pressed = !state.pressed && (active || tapped);
+            state.just_released = (state.pressed && !active) || tapped;
+            state.pressed = active;
+        }
+    }
+}
+
+/// Replaces the current `InputMap`.  All actions start out released.
+pub fn set_input_map(ctx: &mut Context, map: InputMap) {
+    ctx.action_context.map = map;
+    ctx.action_context.states.clear();
+}
+
+/// Returns the current `InputMap`.
+pub fn get_input_map(ctx: &Context) -> &InputMap {
+    &ctx.action_context.map
+}
+
+/// Adds a binding to the named action, creating the action if it
+/// doesn't exist yet.
+pub fn bind_action(ctx: &mut Context, name: &str, binding: Binding) {
+    ctx.action_context
+        .map
+        .actions
+        .entry(name.to_owned())
+        .or_insert_with(Vec::new)
+        .push(binding);
+}
+
+/// Removes all of the named action's bindings, returning them.  This
+/// is the first step of letting the player rebind it.
+pub fn unbind_action(ctx: &mut Context, name: &str) -> Vec<Binding> {
+    ctx.action_context
+        .map
+        .actions
+        .get_mut(name)
+        .map(|bindings| bindings.drain(..).collect())
+        .unwrap_or_default()
+}
+
+/// Adds a binding to the named axis, creating the axis if it
+/// doesn't exist yet.
+pub fn bind_axis(ctx: &mut Context, name: &str, binding: AxisBinding) {
+    ctx.action_context
+        .map
+        .axes
+        .entry(name.to_owned())
+        .or_insert_with(Vec::new)
+        .push(binding);
+}
+
+/// Removes all of the named axis' bindings, returning them.
+pub fn unbind_axis(ctx: &mut Context, name: &str) -> Vec<AxisBinding> {
+    ctx.action_context
+        .map
+        .axes
+        .get_mut(name)
+        .map(|bindings| bindings.drain(..).collect())
+        .unwrap_or_default()
+}
+
+/// Returns whether the named action is held down.  Unknown actions
+/// are never pressed.
+pub fn is_action_pressed(ctx: &Context, name: &str) -> bool {
+    ctx.action_context.state(name).pressed
+}
+
+/// Returns whether the named action was pressed this frame.
+pub fn is_action_just_pressed(ctx: &Context, name: &str) -> bool {
+    ctx.action_context.state(name).just_pressed
+}
+
+/// Returns whether the named action was released this frame.
+pub fn is_action_just_released(ctx: &Context, name: &str) -> bool {
+    ctx.action_context.state(name).just_released
+}
+
+/// Returns the value of the named axis, from -1 to 1.  Unknown axes
+/// read as 0.
+pub fn get_axis(ctx: &Context, name: &str) -> f32 {
+    ctx.action_context
+        .map
+        .axes
+        .get(name)
+        .map(|bindings| {
+            bindings
+                .iter()
+                .map(|binding| axis_binding_value(ctx, binding))
+                .fold(0.0, furthest)
+        })
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_binding_strings_round_trip() {
+        let bindings = [
+            Binding::key(KeyCode::W),
+            Binding::key(KeyCode::S).with_mods(KeyMods::CTRL | KeyMods::SHIFT),
+            Binding::scancode(17),
+            Binding::mouse(MouseButton::Left),
+            Binding::mouse(MouseButton::Other(4)),
+            Binding::gamepad_button(Button::South),
+            Binding::new(Input::GamepadAxisNegative(Axis::LeftStickY)),
+        ];
+        for binding in &bindings {
+            let s = binding.to_string();
+            assert_eq!(s.parse::<Binding>().unwrap(), *binding);
+        }
+        assert_eq!(bindings[1].to_string(), "Shift+Ctrl+Key:S");
+        assert!("Key:NotAKey".parse::<Binding>().is_err());
+        assert!("Hyper+Key:A".parse::<Binding>().is_err());
+        assert!("Space".parse::<Binding>().is_err());
+    }
+
+    #[test]
+    fn headless_input_map_toml_round_trip() {
+        let map = InputMap::new()
+            .action("jump", Binding::key(KeyCode::Space))
+            .action("jump", Binding::gamepad_button(Button::South))
+            .action("save", Binding::key(KeyCode::S).with_mods(KeyMods::CTRL))
+            .axis("move_x", AxisBinding::keys(KeyCode::A, KeyCode::D))
+            .axis(
+                "look_y",
+                AxisBinding::Gamepad {
+                    axis: Axis::RightStickY,
+                    inverted: true,
+                },
+            );
+        let mut writer = Vec::new();
+        map.to_toml_file(&mut writer).unwrap();
+        let mut reader = writer.as_slice();
+        let map2 = InputMap::from_toml_file(&mut reader).unwrap();
+        assert_eq!(map, map2);
+
+        let handwritten = r#"
+            [actions]
+            fire = ["Mouse:Left", "Ctrl+Key:F"]
+
+            [[axes.move_y]]
+            type = "Gamepad"
+            axis = "LeftStickY"
+        "#;
+        let map3 = InputMap::from_toml_file(&mut handwritten.as_bytes()).unwrap();
+        assert_eq!(map3.actions["fire"].len(), 2);
+        assert_eq!(
+            map3.axes["move_y"],
+            vec![AxisBinding::gamepad(Axis::LeftStickY)]
+        );
+    }
+}
@@ -4,16 +4,122 @@
 //! gets fleshed out.  The `gilrs` crate needs help to add better
 //! cross-platform support.  Why not give it a hand?
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::time;
 
-use gilrs::{Gamepad, Gilrs};
+use gilrs::ev::filter::{Filter, Repeat};
+use gilrs::{self, Axis, Button, Gamepad, Gilrs};
 
 use context::Context;
+use GameError;
 use GameResult;
 
+/// Force feedback effects, straight from `gilrs`.  Build them with
+/// `create_effect()`, or use `rumble()` for the common case.
+pub use gilrs::ff;
+
+/// Every `Button` there is, for looking them up by number.
+pub(crate) const ALL_BUTTONS: [Button; 20] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+    Button::Unknown,
+];
+
+/// Every `Axis` there is, for looking them up by number.
+pub(crate) const ALL_AXES: [Axis; 9] = [
+    Axis::LeftStickX,
+    Axis::LeftStickY,
+    Axis::LeftZ,
+    Axis::RightStickX,
+    Axis::RightStickY,
+    Axis::RightZ,
+    Axis::DPadX,
+    Axis::DPadY,
+    Axis::Unknown,
+];
+
+/// Identifies a gamepad.
+///
+/// Ids are handed out by `gilrs` as gamepads are first seen, and are
+/// never handed to a different gamepad while the game is running, so
+/// it's safe to hang on to one for the whole session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub(crate) usize);
+
+impl GamepadId {
+    /// The raw `gilrs` index behind this id.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl From<usize> for GamepadId {
+    fn from(index: usize) -> Self {
+        GamepadId(index)
+    }
+}
+
+impl fmt::Display for GamepadId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The default deadzone applied to analog axes.
+pub const DEFAULT_DEADZONE: f32 = 0.1;
+
+/// What we know about one gamepad, built up from its events.
+#[derive(Debug, Clone)]
+struct GamepadState {
+    connected: bool,
+    buttons: [bool; 20],
+    axes: [f32; 9],
+}
+
+impl Default for GamepadState {
+    fn default() -> Self {
+        GamepadState {
+            connected: true,
+            buttons: [false; 20],
+            axes: [0.0; 9],
+        }
+    }
+}
+
 /// A structure that contains gamepad state.
+///
+/// Button, axis and connection state comes from the `GameEvent`s the
+/// game sees, not from `gilrs` directly, so injected and replayed
+/// events look just like real gamepads.  `gilrs` is only asked for
+/// names and force feedback.
 pub struct GamepadContext {
-    pub(crate) gilrs: Gilrs,
+    /// `None` for a headless context, which only knows about
+    /// gamepads from injected events.
+    pub(crate) gilrs: Option<Gilrs>,
+    deadzone: f32,
+    repeat: Repeat,
+    rumbles: HashMap<GamepadId, ff::Effect>,
+    gamepads: BTreeMap<GamepadId, GamepadState>,
+    just_pressed: HashSet<(GamepadId, Button)>,
+    just_released: HashSet<(GamepadId, Button)>,
 }
 
 impl fmt::Debug for GamepadContext {
@@ -25,33 +131,360 @@ impl fmt::Debug for GamepadContext {
 impl GamepadContext {
     pub(crate) fn new() -> GameResult<GamepadContext> {
         let gilrs = Gilrs::new()?;
-        Ok(GamepadContext { gilrs })
+        // Gamepads that were plugged in before we started don't send
+        // a `Connected` event, so note them now.
+        let gamepads = gilrs
+            .gamepads()
+            .filter(|&(_, gamepad)| gamepad.is_connected())
+            .map(|(id, _)| (GamepadId(id), GamepadState::default()))
+            .collect();
+        Ok(GamepadContext {
+            gilrs: Some(gilrs),
+            deadzone: DEFAULT_DEADZONE,
+            repeat: Repeat::new(),
+            rumbles: HashMap::new(),
+            gamepads,
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+        })
+    }
+
+    pub(crate) fn new_headless() -> GamepadContext {
+        GamepadContext {
+            gilrs: None,
+            deadzone: DEFAULT_DEADZONE,
+            repeat: Repeat::new(),
+            rumbles: HashMap::new(),
+            gamepads: BTreeMap::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+        }
+    }
+
+    /// Returns the next pending gamepad event, if any, including
+    /// `ButtonRepeated` events for buttons that are held down.
+    pub(crate) fn next_event(&mut self) -> Option<gilrs::Event> {
+        let repeat = &self.repeat;
+        match self.gilrs {
+            Some(ref mut gilrs) => gilrs.next_event().filter_ev(repeat, gilrs),
+            None => None,
+        }
+    }
+
+    /// Notes a button press or release from a gamepad event.  A
+    /// gamepad we haven't heard of yet counts as connected.
+    pub(crate) fn set_button(&mut self, id: GamepadId, button: Button, pressed: bool) {
+        {
+            let state = self.state_mut(id);
+            if button != Button::Unknown {
+                if let Some(i) = ALL_BUTTONS.iter().position(|b| *b == button) {
+                    state.buttons[i] = pressed;
+                }
+            }
+        }
+        if pressed {
+            let _ = self.just_pressed.insert((id, button));
+        } else {
+            let _ = self.just_released.insert((id, button));
+        }
+    }
+
+    /// Notes an axis moving from a gamepad event.  The value should
+    /// already have the deadzone applied.
+    pub(crate) fn set_axis(&mut self, id: GamepadId, axis: Axis, value: f32) {
+        let state = self.state_mut(id);
+        if axis != Axis::Unknown {
+            if let Some(i) = ALL_AXES.iter().position(|a| *a == axis) {
+                state.axes[i] = value;
+            }
+        }
+    }
+
+    /// Notes a gamepad being connected or disconnected.  A gamepad
+    /// starts out with nothing pressed either way.
+    pub(crate) fn set_connected(&mut self, id: GamepadId, connected: bool) {
+        let _ = self.gamepads.insert(
+            id,
+            GamepadState {
+                connected,
+                ..GamepadState::default()
+            },
+        );
+    }
+
+    fn state_mut(&mut self, id: GamepadId) -> &mut GamepadState {
+        let state = self
+            .gamepads
+            .entry(id)
+            .or_insert_with(GamepadState::default);
+        state.connected = true;
+        state
+    }
+
+    /// Returns the state of the given gamepad, if it's connected.
+    fn state(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.gamepads.get(&id).filter(|state| state.connected)
+    }
+
+    /// Forgets which buttons were just pressed or released, ready for
+    /// the next frame.
+    pub(crate) fn begin_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+
+    /// Applies the deadzone to a raw axis value.  Anything inside the
+    /// deadzone becomes 0, and the rest is rescaled so the output
+    /// still covers the whole -1 to 1 range.
+    pub(crate) fn apply_deadzone(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.deadzone {
+            0.0
+        } else {
+            let scaled = (magnitude - self.deadzone) / (1.0 - self.deadzone);
+            scaled.min(1.0) * value.signum()
+        }
+    }
+
+    fn get(&self, id: GamepadId) -> Option<&Gamepad> {
+        self.gilrs.as_ref().and_then(|gilrs| gilrs.get(id.0))
     }
 }
 
-/// returns the `Gamepad` associated with an id.
-pub fn get_gamepad(ctx: &Context, id: usize) -> Option<&Gamepad> {
-    ctx.gamepad_context.gilrs.get(id)
+/// A copy of everything we know about a gamepad at one moment.
+///
+/// Axis values have the deadzone already applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadSnapshot {
+    /// Which gamepad this is.
+    pub id: GamepadId,
+    /// The name the gamepad reports for itself.
+    pub name: String,
+    buttons: [bool; 20],
+    axes: [f32; 9],
 }
 
-// Properties gamepads might want:
-// Number of buttons
-// Number of axes
-// Name/ID
-// Is it connected?  (For consoles?)
-// Whether or not they support vibration
+impl GamepadSnapshot {
+    /// Returns whether the given button was held down.
+    pub fn is_pressed(&self, button: Button) -> bool {
+        ALL_BUTTONS
+            .iter()
+            .position(|b| *b == button)
+            .map(|i| self.buttons[i])
+            .unwrap_or(false)
+    }
+
+    /// Returns the value of the given axis, from -1 to 1.
+    pub fn axis(&self, axis: Axis) -> f32 {
+        ALL_AXES
+            .iter()
+            .position(|a| *a == axis)
+            .map(|i| self.axes[i])
+            .unwrap_or(0.0)
+    }
+
+    /// Returns all the buttons that were held down.
+    pub fn pressed_buttons(&self) -> Vec<Button> {
+        ALL_BUTTONS
+            .iter()
+            .zip(self.buttons.iter())
+            .filter(|&(_, pressed)| *pressed)
+            .map(|(button, _)| *button)
+            .collect()
+    }
+}
+
+/// returns the `Gamepad` associated with an id, if it's connected.
+pub fn get_gamepad(ctx: &Context, id: GamepadId) -> Option<&Gamepad> {
+    ctx.gamepad_context.get(id)
+}
+
+/// Lists the ids of all currently connected gamepads, in order.
+pub fn list_gamepads(ctx: &Context) -> Vec<GamepadId> {
+    ctx.gamepad_context
+        .gamepads
+        .iter()
+        .filter(|&(_, state)| state.connected)
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+/// Returns whether the given gamepad is currently connected.
+pub fn is_connected(ctx: &Context, id: GamepadId) -> bool {
+    ctx.gamepad_context.state(id).is_some()
+}
+
+/// Returns the name of the given gamepad, or `None` if it isn't
+/// connected or `gilrs` doesn't know it, as with gamepads that only
+/// exist in injected or replayed events.
+pub fn get_name(ctx: &Context, id: GamepadId) -> Option<String> {
+    if !is_connected(ctx, id) {
+        return None;
+    }
+    get_gamepad(ctx, id).map(|gamepad| gamepad.name().to_owned())
+}
+
+/// Returns the state of the given axis on a gamepad, from -1 to 1,
+/// with the deadzone applied.  Gamepads that aren't connected read
+/// as 0.
+pub fn get_axis(ctx: &Context, id: GamepadId, axis: Axis) -> f32 {
+    if axis == Axis::Unknown {
+        return 0.0;
+    }
+    match ctx.gamepad_context.state(id) {
+        Some(state) => ALL_AXES
+            .iter()
+            .position(|a| *a == axis)
+            .map(|i| state.axes[i])
+            .unwrap_or(0.0),
+        None => 0.0,
+    }
+}
+
+/// Returns whether the given button on a gamepad is held down.
+/// Gamepads that aren't connected have nothing pressed.
+pub fn get_button_pressed(ctx: &Context, id: GamepadId, button: Button) -> bool {
+    if button == Button::Unknown {
+        return false;
+    }
+    match ctx.gamepad_context.state(id) {
+        Some(state) => ALL_BUTTONS
+            .iter()
+            .position(|b| *b == button)
+            .map(|i| state.buttons[i])
+            .unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Returns whether the given button on a gamepad went down this
+/// frame.  This is true even if it was released again before the
+/// frame ended, so short taps aren't missed.
+pub fn is_button_just_pressed(ctx: &Context, id: GamepadId, button: Button) -> bool {
+    ctx.gamepad_context.just_pressed.contains(&(id, button))
+}
+
+/// Returns whether the given button on a gamepad was released this
+/// frame.
+pub fn is_button_just_released(ctx: &Context, id: GamepadId, button: Button) -> bool {
+    ctx.gamepad_context.just_released.contains(&(id, button))
+}
+
+/// Takes a snapshot of the state of the given gamepad, or returns
+/// `None` if it isn't connected.  Gamepads `gilrs` doesn't know
+/// have an empty name.
+pub fn get_snapshot(ctx: &Context, id: GamepadId) -> Option<GamepadSnapshot> {
+    ctx.gamepad_context.state(id).map(|state| GamepadSnapshot {
+        id,
+        name: get_name(ctx, id).unwrap_or_default(),
+        buttons: state.buttons,
+        axes: state.axes,
+    })
+}
+
+/// Sets the deadzone for analog axes, from 0 to 1.  Axis values
+/// closer to 0 than this are reported as 0, both by `get_axis()` and
+/// in `controller_axis_event()`.  Defaults to `DEFAULT_DEADZONE`.
+pub fn set_deadzone(ctx: &mut Context, deadzone: f32) {
+    ctx.gamepad_context.deadzone = deadzone.max(0.0).min(0.99);
+}
+
+/// Returns the current deadzone for analog axes.
+pub fn get_deadzone(ctx: &Context) -> f32 {
+    ctx.gamepad_context.deadzone
+}
+
+/// Returns whether the given gamepad can do force feedback.
+pub fn is_ff_supported(ctx: &Context, id: GamepadId) -> bool {
+    get_gamepad(ctx, id)
+        .map(|gamepad| gamepad.is_ff_supported())
+        .unwrap_or(false)
+}
 
-/// Lists all gamepads.  With metainfo, maybe?
-pub fn list_gamepads() {
-    unimplemented!()
+/// Creates a force feedback effect made of the given base effects,
+/// to be played on the given gamepads.  Call `play()` on the result
+/// to start it; it stops when it's dropped.
+pub fn create_effect(
+    ctx: &mut Context,
+    effects: &[ff::BaseEffect],
+    gamepads: &[GamepadId],
+) -> GameResult<ff::Effect> {
+    let gilrs = ctx.gamepad_context.gilrs.as_mut().ok_or_else(|| {
+        GameError::GamepadError("No gamepads available in a headless context".to_owned())
+    })?;
+    let ids: Vec<usize> = gamepads.iter().map(|id| id.0).collect();
+    let mut builder = ff::EffectBuilder::new();
+    for effect in effects {
+        let _ = builder.add_effect(*effect);
+    }
+    let effect = builder.gamepads(&ids).finish(gilrs)?;
+    Ok(effect)
+}
+
+/// Rumbles a gamepad once for the given duration.  `strong` and
+/// `weak` are the strengths of the gamepad's low- and high-frequency
+/// motors, from 0 to 1.  This replaces any rumble already playing on
+/// that gamepad.
+pub fn rumble(
+    ctx: &mut Context,
+    id: GamepadId,
+    strong: f32,
+    weak: f32,
+    duration: time::Duration,
+) -> GameResult {
+    let millis = duration
+        .as_secs()
+        .saturating_mul(1000)
+        .saturating_add(u64::from(duration.subsec_nanos() / 1_000_000))
+        .min(u64::from(u32::max_value())) as u32;
+    // Base effects loop forever, so pad each one out with a delay
+    // long enough that it never comes round again.
+    let scheduling = ff::Replay {
+        after: ff::Ticks::from_ms(0),
+        play_for: ff::Ticks::from_ms(millis),
+        with_delay: ff::Ticks::from_ms(u32::max_value() / 2),
+    };
+    let magnitude =
+        |strength: f32| (strength.max(0.0).min(1.0) * f32::from(u16::max_value())) as u16;
+    let effects = [
+        ff::BaseEffect {
+            kind: ff::BaseEffectType::Strong {
+                magnitude: magnitude(strong),
+            },
+            scheduling,
+            ..Default::default()
+        },
+        ff::BaseEffect {
+            kind: ff::BaseEffectType::Weak {
+                magnitude: magnitude(weak),
+            },
+            scheduling,
+            ..Default::default()
+        },
+    ];
+    stop_rumble(ctx, id);
+    let effect = create_effect(ctx, &effects, &[id])?;
+    effect.play()?;
+    let _ = ctx.gamepad_context.rumbles.insert(id, effect);
+    Ok(())
 }
 
-/// Returns the state of the given axis on a gamepad.
-pub fn get_axis() {
-    unimplemented!()
+/// Stops any rumble started with `rumble()` on the given gamepad.
+pub fn stop_rumble(ctx: &mut Context, id: GamepadId) {
+    let _ = ctx.gamepad_context.rumbles.remove(&id);
 }
 
-/// Returns the state of the given button on a gamepad.
-pub fn get_button_pressed() {
-    unimplemented!()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_deadzone_rescales_axis_values() {
+        let gamepads = GamepadContext::new_headless();
+        assert_eq!(gamepads.apply_deadzone(0.05), 0.0);
+        assert_eq!(gamepads.apply_deadzone(-0.1), 0.0);
+        assert_eq!(gamepads.apply_deadzone(1.0), 1.0);
+        assert_eq!(gamepads.apply_deadzone(-1.0), -1.0);
+        assert!((gamepads.apply_deadzone(0.55) - 0.5).abs() < 1e-6);
+    }
 }
@@ -64,12 +64,21 @@
 //! }
 //! ```
 
+use std::collections::{HashMap, HashSet};
+
 use context::Context;
 
 use winit::ModifiersState;
 /// A key code.
 pub use winit::VirtualKeyCode as KeyCode;
 
+/// A platform-specific number identifying a physical key, regardless
+/// of what the current keyboard layout says is printed on it.  The W
+/// key on a US keyboard has the same scancode as the Z key on a
+/// French one, which makes scancodes the right thing to bind
+/// movement to; use `us_layout_scancode()` to find them by name.
+pub type ScanCode = u32;
+
 bitflags! {
     /// Bitflags describing state of keyboard modifiers, such as Control or Shift.
     #[derive(Default)]
@@ -87,6 +96,163 @@ bitflags! {
     }
 }
 
+/// Every `KeyCode` there is, in the order `winit` declares them, for
+/// looking them up by number or by name.
+pub(crate) const ALL_KEYCODES: [KeyCode; 152] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::Snapshot,
+    KeyCode::Scroll,
+    KeyCode::Pause,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Back,
+    KeyCode::Return,
+    KeyCode::Space,
+    KeyCode::Compose,
+    KeyCode::Caret,
+    KeyCode::Numlock,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::AbntC1,
+    KeyCode::AbntC2,
+    KeyCode::Add,
+    KeyCode::Apostrophe,
+    KeyCode::Apps,
+    KeyCode::At,
+    KeyCode::Ax,
+    KeyCode::Backslash,
+    KeyCode::Calculator,
+    KeyCode::Capital,
+    KeyCode::Colon,
+    KeyCode::Comma,
+    KeyCode::Convert,
+    KeyCode::Decimal,
+    KeyCode::Divide,
+    KeyCode::Equals,
+    KeyCode::Grave,
+    KeyCode::Kana,
+    KeyCode::Kanji,
+    KeyCode::LAlt,
+    KeyCode::LBracket,
+    KeyCode::LControl,
+    KeyCode::LShift,
+    KeyCode::LWin,
+    KeyCode::Mail,
+    KeyCode::MediaSelect,
+    KeyCode::MediaStop,
+    KeyCode::Minus,
+    KeyCode::Multiply,
+    KeyCode::Mute,
+    KeyCode::MyComputer,
+    KeyCode::NavigateForward,
+    KeyCode::NavigateBackward,
+    KeyCode::NextTrack,
+    KeyCode::NoConvert,
+    KeyCode::NumpadComma,
+    KeyCode::NumpadEnter,
+    KeyCode::NumpadEquals,
+    KeyCode::OEM102,
+    KeyCode::Period,
+    KeyCode::PlayPause,
+    KeyCode::Power,
+    KeyCode::PrevTrack,
+    KeyCode::RAlt,
+    KeyCode::RBracket,
+    KeyCode::RControl,
+    KeyCode::RShift,
+    KeyCode::RWin,
+    KeyCode::Semicolon,
+    KeyCode::Slash,
+    KeyCode::Sleep,
+    KeyCode::Stop,
+    KeyCode::Subtract,
+    KeyCode::Sysrq,
+    KeyCode::Tab,
+    KeyCode::Underline,
+    KeyCode::Unlabeled,
+    KeyCode::VolumeDown,
+    KeyCode::VolumeUp,
+    KeyCode::Wake,
+    KeyCode::WebBack,
+    KeyCode::WebFavorites,
+    KeyCode::WebForward,
+    KeyCode::WebHome,
+    KeyCode::WebRefresh,
+    KeyCode::WebSearch,
+    KeyCode::WebStop,
+    KeyCode::Yen,
+    KeyCode::Copy,
+    KeyCode::Paste,
+    KeyCode::Cut,
+];
+
 impl From<ModifiersState> for KeyMods {
     fn from(state: ModifiersState) -> Self {
         let mut keymod = KeyMods::empty();
@@ -118,6 +284,18 @@ pub struct KeyboardContext {
     /// will check if that assumption gets violated.
     // Maybe we can just use a HashSet instead?  Eh.
     pressed_keys: Vec<bool>,
+    /// Keys that went down or up since the start of the frame,
+    /// indexed the same way as `pressed_keys`.
+    just_pressed_keys: Vec<bool>,
+    just_released_keys: Vec<bool>,
+
+    /// The same again, for physical keys.
+    pressed_scancodes: HashSet<ScanCode>,
+    just_pressed_scancodes: HashSet<ScanCode>,
+    just_released_scancodes: HashSet<ScanCode>,
+    /// What the current layout says each physical key is, as learned
+    /// from key events.
+    scancode_keys: HashMap<ScanCode, KeyCode>,
 
     // These two are necessary for tracking key-repeat.
     last_pressed: Option<KeyCode>,
@@ -138,7 +316,13 @@ impl KeyboardContext {
         key_vec.resize(MAX_KEY_IDX, false);
         Self {
             active_modifiers: KeyMods::empty(),
+            just_pressed_keys: key_vec.clone(),
+            just_released_keys: key_vec.clone(),
             pressed_keys: key_vec,
+            pressed_scancodes: HashSet::new(),
+            just_pressed_scancodes: HashSet::new(),
+            just_released_scancodes: HashSet::new(),
+            scancode_keys: HashMap::new(),
             last_pressed: None,
             current_pressed: None,
         }
@@ -154,7 +338,13 @@ impl KeyboardContext {
             key_idx < self.pressed_keys.len(),
             "Impossible KeyCode detected!"
         );
+        let was_pressed = self.pressed_keys[key_idx];
         self.pressed_keys[key_idx] = pressed;
+        if pressed && !was_pressed {
+            self.just_pressed_keys[key_idx] = true;
+        } else if !pressed && was_pressed {
+            self.just_released_keys[key_idx] = true;
+        }
         if pressed {
             self.last_pressed = self.current_pressed;
             self.current_pressed = Some(key);
@@ -187,6 +377,46 @@ impl KeyboardContext {
         self.pressed_keys[key_idx]
     }
 
+    pub(crate) fn is_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.just_pressed_keys[key as usize]
+    }
+
+    pub(crate) fn is_key_just_released(&self, key: KeyCode) -> bool {
+        self.just_released_keys[key as usize]
+    }
+
+    pub(crate) fn set_scancode(&mut self, scancode: ScanCode, pressed: bool) {
+        if pressed {
+            if self.pressed_scancodes.insert(scancode) {
+                let _ = self.just_pressed_scancodes.insert(scancode);
+            }
+        } else if self.pressed_scancodes.remove(&scancode) {
+            let _ = self.just_released_scancodes.insert(scancode);
+        }
+    }
+
+    /// Remembers that the current layout turns `scancode` into `key`.
+    pub(crate) fn learn_scancode(&mut self, scancode: ScanCode, key: KeyCode) {
+        let _ = self.scancode_keys.insert(scancode, key);
+    }
+
+    pub(crate) fn is_scancode_pressed(&self, scancode: ScanCode) -> bool {
+        self.pressed_scancodes.contains(&scancode)
+    }
+
+    /// Forgets which keys were just pressed or released, ready for
+    /// the next frame.
+    pub(crate) fn begin_frame(&mut self) {
+        for flag in &mut self.just_pressed_keys {
+            *flag = false;
+        }
+        for flag in &mut self.just_released_keys {
+            *flag = false;
+        }
+        self.just_pressed_scancodes.clear();
+        self.just_released_scancodes.clear();
+    }
+
     pub(crate) fn is_key_repeated(&self) -> bool {
         if let Some(_) = self.last_pressed {
             self.last_pressed == self.current_pressed
@@ -195,6 +425,12 @@ impl KeyboardContext {
         }
     }
 
+    /// Whether pressing `key` now would make `is_key_repeated()`
+    /// true, without actually pressing it.
+    pub(crate) fn is_repeat_press(&self, key: KeyCode) -> bool {
+        self.current_pressed == Some(key)
+    }
+
     pub(crate) fn get_pressed_keys(&self) -> Vec<KeyCode> {
         self.pressed_keys
             .iter()
@@ -234,6 +470,233 @@ pub fn is_key_pressed(ctx: &Context, key: KeyCode) -> bool {
     ctx.keyboard_context.is_key_pressed(key)
 }
 
+/// Checks if a key went down this frame.  This is true even if the
+/// key was released again before the frame ended, so short taps
+/// aren't missed.  Key repeats don't count.
+pub fn is_key_just_pressed(ctx: &Context, key: KeyCode) -> bool {
+    ctx.keyboard_context.is_key_just_pressed(key)
+}
+
+/// Checks if a key was released this frame.
+pub fn is_key_just_released(ctx: &Context, key: KeyCode) -> bool {
+    ctx.keyboard_context.is_key_just_released(key)
+}
+
+/// Checks if the key with the given scancode is currently pressed
+/// down.
+pub fn is_scancode_pressed(ctx: &Context, scancode: ScanCode) -> bool {
+    ctx.keyboard_context.is_scancode_pressed(scancode)
+}
+
+/// Checks if the key with the given scancode went down this frame.
+pub fn is_scancode_just_pressed(ctx: &Context, scancode: ScanCode) -> bool {
+    ctx.keyboard_context
+        .just_pressed_scancodes
+        .contains(&scancode)
+}
+
+/// Checks if the key with the given scancode was released this
+/// frame.
+pub fn is_scancode_just_released(ctx: &Context, scancode: ScanCode) -> bool {
+    ctx.keyboard_context
+        .just_released_scancodes
+        .contains(&scancode)
+}
+
+/// Returns the scancodes of all the keys currently pressed down.
+pub fn get_pressed_scancodes(ctx: &Context) -> Vec<ScanCode> {
+    let mut scancodes: Vec<_> = ctx
+        .keyboard_context
+        .pressed_scancodes
+        .iter()
+        .cloned()
+        .collect();
+    scancodes.sort();
+    scancodes
+}
+
+/// Returns the `KeyCode` the current keyboard layout produces for
+/// the given physical key, so you can show the player which key to
+/// press.  On an AZERTY keyboard, the scancode for W's position on
+/// a US keyboard gives `KeyCode::Z`.
+///
+/// **This only knows the layout for keys that have been pressed.**
+/// `winit` has no way to ask the system about its layout, so the
+/// layout is learned from key events as they happen.  Until a key
+/// has been pressed, this guesses from a US QWERTY layout instead, as
+/// in `us_layout_scancode()`, which will be wrong for keys that other
+/// layouts move about.  It returns `None` for keys outside the main
+/// block that haven't been pressed yet.  So a rebinding screen that
+/// shows default bindings before the player has touched the keyboard
+/// shows them as they are on a US keyboard.
+pub fn get_scancode_key(ctx: &Context, scancode: ScanCode) -> Option<KeyCode> {
+    ctx.keyboard_context
+        .scancode_keys
+        .get(&scancode)
+        .cloned()
+        .or_else(|| {
+            ALL_KEYCODES
+                .iter()
+                .find(|key| us_layout_scancode(**key) == Some(scancode))
+                .cloned()
+        })
+}
+
+/// Returns the name of the key the current keyboard layout produces
+/// for the given physical key, like `"Z"` or `"Semicolon"`, or
+/// `None` if it isn't known.  Like `get_scancode_key()`, this only
+/// knows the actual layout for keys that have been pressed, and
+/// guesses from a US layout for the rest.
+pub fn get_scancode_name(ctx: &Context, scancode: ScanCode) -> Option<String> {
+    get_scancode_key(ctx, scancode).map(|key| format!("{:?}", key))
+}
+
+/// Returns the scancode of the physical key that has `key` printed on
+/// it on a US QWERTY keyboard, for the main block of letter, number
+/// and punctuation keys.  Binding to `us_layout_scancode(KeyCode::W)`
+/// rather than `KeyCode::W` makes a WASD-style binding land on the
+/// same physical keys on AZERTY, Dvorak and other layouts.
+pub fn us_layout_scancode(key: KeyCode) -> Option<ScanCode> {
+    us_layout::scancode(key)
+}
+
+/// Scancodes on Windows and Linux; these use the same numbering for
+/// the main block of keys.
+#[cfg(not(target_os = "macos"))]
+mod us_layout {
+    use super::{KeyCode, ScanCode};
+
+    pub fn scancode(key: KeyCode) -> Option<ScanCode> {
+        let scancode = match key {
+            KeyCode::Escape => 1,
+            KeyCode::Key1 => 2,
+            KeyCode::Key2 => 3,
+            KeyCode::Key3 => 4,
+            KeyCode::Key4 => 5,
+            KeyCode::Key5 => 6,
+            KeyCode::Key6 => 7,
+            KeyCode::Key7 => 8,
+            KeyCode::Key8 => 9,
+            KeyCode::Key9 => 10,
+            KeyCode::Key0 => 11,
+            KeyCode::Minus => 12,
+            KeyCode::Equals => 13,
+            KeyCode::Back => 14,
+            KeyCode::Tab => 15,
+            KeyCode::Q => 16,
+            KeyCode::W => 17,
+            KeyCode::E => 18,
+            KeyCode::R => 19,
+            KeyCode::T => 20,
+            KeyCode::Y => 21,
+            KeyCode::U => 22,
+            KeyCode::I => 23,
+            KeyCode::O => 24,
+            KeyCode::P => 25,
+            KeyCode::LBracket => 26,
+            KeyCode::RBracket => 27,
+            KeyCode::Return => 28,
+            KeyCode::LControl => 29,
+            KeyCode::A => 30,
+            KeyCode::S => 31,
+            KeyCode::D => 32,
+            KeyCode::F => 33,
+            KeyCode::G => 34,
+            KeyCode::H => 35,
+            KeyCode::J => 36,
+            KeyCode::K => 37,
+            KeyCode::L => 38,
+            KeyCode::Semicolon => 39,
+            KeyCode::Apostrophe => 40,
+            KeyCode::Grave => 41,
+            KeyCode::LShift => 42,
+            KeyCode::Backslash => 43,
+            KeyCode::Z => 44,
+            KeyCode::X => 45,
+            KeyCode::C => 46,
+            KeyCode::V => 47,
+            KeyCode::B => 48,
+            KeyCode::N => 49,
+            KeyCode::M => 50,
+            KeyCode::Comma => 51,
+            KeyCode::Period => 52,
+            KeyCode::Slash => 53,
+            KeyCode::RShift => 54,
+            KeyCode::Space => 57,
+            _ => return None,
+        };
+        Some(scancode)
+    }
+}
+
+/// Scancodes on macOS, which are its virtual key codes.
+#[cfg(target_os = "macos")]
+mod us_layout {
+    use super::{KeyCode, ScanCode};
+
+    pub fn scancode(key: KeyCode) -> Option<ScanCode> {
+        let scancode = match key {
+            KeyCode::A => 0x00,
+            KeyCode::S => 0x01,
+            KeyCode::D => 0x02,
+            KeyCode::F => 0x03,
+            KeyCode::H => 0x04,
+            KeyCode::G => 0x05,
+            KeyCode::Z => 0x06,
+            KeyCode::X => 0x07,
+            KeyCode::C => 0x08,
+            KeyCode::V => 0x09,
+            KeyCode::B => 0x0B,
+            KeyCode::Q => 0x0C,
+            KeyCode::W => 0x0D,
+            KeyCode::E => 0x0E,
+            KeyCode::R => 0x0F,
+            KeyCode::Y => 0x10,
+            KeyCode::T => 0x11,
+            KeyCode::Key1 => 0x12,
+            KeyCode::Key2 => 0x13,
+            KeyCode::Key3 => 0x14,
+            KeyCode::Key4 => 0x15,
+            KeyCode::Key6 => 0x16,
+            KeyCode::Key5 => 0x17,
+            KeyCode::Equals => 0x18,
+            KeyCode::Key9 => 0x19,
+            KeyCode::Key7 => 0x1A,
+            KeyCode::Minus => 0x1B,
+            KeyCode::Key8 => 0x1C,
+            KeyCode::Key0 => 0x1D,
+            KeyCode::RBracket => 0x1E,
+            KeyCode::O => 0x1F,
+            KeyCode::U => 0x20,
+            KeyCode::LBracket => 0x21,
+            KeyCode::I => 0x22,
+            KeyCode::P => 0x23,
+            KeyCode::Return => 0x24,
+            KeyCode::L => 0x25,
+            KeyCode::J => 0x26,
+            KeyCode::Apostrophe => 0x27,
+            KeyCode::K => 0x28,
+            KeyCode::Semicolon => 0x29,
+            KeyCode::Backslash => 0x2A,
+            KeyCode::Comma => 0x2B,
+            KeyCode::Slash => 0x2C,
+            KeyCode::N => 0x2D,
+            KeyCode::M => 0x2E,
+            KeyCode::Period => 0x2F,
+            KeyCode::Tab => 0x30,
+            KeyCode::Space => 0x31,
+            KeyCode::Grave => 0x32,
+            KeyCode::Back => 0x33,
+            KeyCode::Escape => 0x35,
+            KeyCode::LShift => 0x38,
+            KeyCode::LControl => 0x3B,
+            KeyCode::RShift => 0x3C,
+            _ => return None,
+        };
+        Some(scancode)
+    }
+}
+
 /// Checks if the last keystroke sent by the system is repeated,
 /// like when a key is held down for a period of time.
 pub fn is_key_repeated(ctx: &Context) -> bool {
@@ -259,6 +722,14 @@ pub fn get_active_mods(ctx: &Context) -> KeyMods {
 mod tests {
     use super::*;
 
+    #[test]
+    fn headless_all_keycodes_are_in_declaration_order() {
+        // Recordings made before the table existed stored `as u32`.
+        for (i, keycode) in ALL_KEYCODES.iter().enumerate() {
+            assert_eq!(*keycode as usize, i);
+        }
+    }
+
     #[test]
     fn key_mod_conversions() {
         assert_eq!(
@@ -354,6 +825,43 @@ mod tests {
         assert_eq!(keyboard.get_pressed_keys(), &[]);
     }
 
+    #[test]
+    fn headless_just_pressed_keys_tracking() {
+        let mut keyboard = KeyboardContext::new();
+        keyboard.set_key(KeyCode::A, true);
+        assert!(keyboard.is_key_just_pressed(KeyCode::A));
+        assert!(!keyboard.is_key_just_released(KeyCode::A));
+        keyboard.begin_frame();
+        keyboard.set_key(KeyCode::A, true);
+        assert!(!keyboard.is_key_just_pressed(KeyCode::A));
+        keyboard.begin_frame();
+        keyboard.set_key(KeyCode::A, false);
+        assert!(keyboard.is_key_just_released(KeyCode::A));
+        keyboard.begin_frame();
+        assert!(!keyboard.is_key_just_released(KeyCode::A));
+        keyboard.set_key(KeyCode::B, true);
+        keyboard.set_key(KeyCode::B, false);
+        assert!(keyboard.is_key_just_pressed(KeyCode::B));
+        assert!(keyboard.is_key_just_released(KeyCode::B));
+        assert!(!keyboard.is_key_pressed(KeyCode::B));
+    }
+
+    #[test]
+    fn headless_scancode_tracking() {
+        let mut keyboard = KeyboardContext::new();
+        keyboard.set_scancode(17, true);
+        keyboard.learn_scancode(17, KeyCode::Z);
+        assert!(keyboard.is_scancode_pressed(17));
+        assert!(keyboard.just_pressed_scancodes.contains(&17));
+        keyboard.begin_frame();
+        keyboard.set_scancode(17, true);
+        assert!(!keyboard.just_pressed_scancodes.contains(&17));
+        keyboard.set_scancode(17, false);
+        assert!(!keyboard.is_scancode_pressed(17));
+        assert!(keyboard.just_released_scancodes.contains(&17));
+        assert_eq!(keyboard.scancode_keys.get(&17), Some(&KeyCode::Z));
+    }
+
     #[test]
     fn repeated_keys_tracking() {
         let mut keyboard = KeyboardContext::new();
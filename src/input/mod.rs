@@ -1,4 +1,8 @@
-//! Input handling modules for keyboar, mouse and gamepad.
+//! Input handling modules for keyboard, mouse, gamepad, touch and text entry.
+pub mod actions;
 pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+pub mod recording;
+pub mod text;
+pub mod touch;
@@ -3,7 +3,7 @@
 use context::Context;
 use graphics;
 use graphics::Point2;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use winit::dpi;
 pub use winit::{MouseButton, MouseCursor};
 use GameError;
@@ -16,9 +16,12 @@ pub struct MouseContext {
     last_position: Point2,
     last_delta: Point2,
     buttons_pressed: HashMap<MouseButton, bool>,
+    buttons_just_pressed: HashSet<MouseButton>,
+    buttons_just_released: HashSet<MouseButton>,
     cursor_type: MouseCursor,
     cursor_grabbed: bool,
     cursor_hidden: bool,
+    relative_mode: bool,
 }
 
 impl MouseContext {
@@ -28,8 +31,11 @@ impl MouseContext {
             last_delta: Point2::origin(),
             cursor_type: MouseCursor::Default,
             buttons_pressed: HashMap::new(),
+            buttons_just_pressed: HashSet::new(),
+            buttons_just_released: HashSet::new(),
             cursor_grabbed: false,
             cursor_hidden: false,
+            relative_mode: false,
         }
     }
 
@@ -41,8 +47,43 @@ impl MouseContext {
         self.last_delta = p;
     }
 
+    /// Adds raw device motion to this frame's delta.
+    pub(crate) fn add_delta(&mut self, dx: f32, dy: f32) {
+        self.last_delta.x += dx;
+        self.last_delta.y += dy;
+    }
+
+    /// Whether deltas come from raw device motion rather than
+    /// cursor positions.
+    pub(crate) fn is_relative(&self) -> bool {
+        self.relative_mode
+    }
+
     pub(crate) fn set_button(&mut self, button: MouseButton, pressed: bool) {
-        let _ = self.buttons_pressed.insert(button, pressed);
+        let was_pressed = self
+            .buttons_pressed
+            .insert(button, pressed)
+            .unwrap_or(false);
+        if pressed && !was_pressed {
+            let _ = self.buttons_just_pressed.insert(button);
+        } else if !pressed && was_pressed {
+            let _ = self.buttons_just_released.insert(button);
+        }
+    }
+
+    /// Forgets which buttons were just pressed or released, ready for
+    /// the next update.
+    pub(crate) fn begin_frame(&mut self) {
+        self.buttons_just_pressed.clear();
+        self.buttons_just_released.clear();
+    }
+
+    /// In relative mode, zeroes the delta at the start of a frame, so
+    /// it only sums up that frame's motion.
+    pub(crate) fn reset_delta(&mut self) {
+        if self.relative_mode {
+            self.last_delta = Point2::origin();
+        }
     }
 
     fn button_pressed(&self, button: MouseButton) -> bool {
@@ -75,6 +116,15 @@ pub fn get_cursor_grabbed(ctx: &Context) -> bool {
 /// Set whether or not the mouse is grabbed (confined to the window)
 pub fn set_cursor_grabbed(ctx: &mut Context, grabbed: bool) -> GameResult<()> {
     ctx.mouse_context.cursor_grabbed = grabbed;
+    if ctx.mouse_context.relative_mode {
+        // Relative mode keeps the cursor grabbed; this takes effect
+        // when it's turned off.
+        return Ok(());
+    }
+    grab_window_cursor(ctx, grabbed)
+}
+
+fn grab_window_cursor(ctx: &Context, grabbed: bool) -> GameResult<()> {
     graphics::get_window(ctx)
         .grab_cursor(grabbed)
         .map_err(|e| GameError::WindowError(e.to_string()))
@@ -88,7 +138,9 @@ pub fn get_cursor_hidden(ctx: &Context) -> bool {
 /// Set whether or not the mouse is hidden (invisible).
 pub fn set_cursor_hidden(ctx: &mut Context, hidden: bool) {
     ctx.mouse_context.cursor_hidden = hidden;
-    graphics::get_window(ctx).hide_cursor(hidden)
+    if !ctx.mouse_context.relative_mode {
+        graphics::get_window(ctx).hide_cursor(hidden)
+    }
 }
 
 /// Get the current position of the mouse cursor, in pixels.
@@ -111,6 +163,9 @@ pub fn set_position(ctx: &mut Context, point: Point2) -> GameResult<()> {
 }
 
 /// Get the distance the cursor was moved during last frame, in pixels.
+/// In relative mode this is instead all the raw device motion seen
+/// this frame, which isn't stopped by the edges of the window, and
+/// is 0 on frames where the mouse didn't move.
 pub fn get_delta(ctx: &Context) -> Point2 {
     ctx.mouse_context.last_delta
 }
@@ -120,14 +175,53 @@ pub fn get_button_pressed(ctx: &Context, button: MouseButton) -> bool {
     ctx.mouse_context.button_pressed(button)
 }
 
-/// TODO: Can we implement this?  Check with Winit peoples.
-/// Winit doesn't implement it itself, we can do it by locking
-/// the cursor to the window and resetting it to center each frame?
-pub fn get_relative_mode() -> bool {
-    unimplemented!()
+/// Returns whether the given mouse button went down this frame.
+/// This is true even if it was released again before the frame
+/// ended, so short clicks aren't missed.
+pub fn is_button_just_pressed(ctx: &Context, button: MouseButton) -> bool {
+    ctx.mouse_context.buttons_just_pressed.contains(&button)
+}
+
+/// Returns whether the given mouse button was released this frame.
+pub fn is_button_just_released(ctx: &Context, button: MouseButton) -> bool {
+    ctx.mouse_context.buttons_just_released.contains(&button)
+}
+
+/// Returns whether the mouse is in relative mode.
+pub fn get_relative_mode(ctx: &Context) -> bool {
+    ctx.mouse_context.relative_mode
 }
 
-/// TODO: Can we impement this?  Check with Winit peoples.
-pub fn set_relative_mode() {
-    unimplemented!()
+/// Turns relative mode on or off.  In relative mode the cursor is
+/// grabbed and hidden, `EventHandler::raw_mouse_motion_event()` is
+/// called with the raw motion of the mouse, and `get_delta()` returns
+/// that raw motion.  This is what you want for mouselook in a 3D
+/// game, since the mouse keeps turning the camera even when the
+/// cursor would have hit the edge of the window.
+///
+/// Turning it off puts the cursor back the way `set_cursor_grabbed()`
+/// and `set_cursor_hidden()` last left it.  A headless context has no
+/// cursor, but still sums up injected raw motion in relative mode.
+pub fn set_relative_mode(ctx: &mut Context, relative: bool) -> GameResult<()> {
+    if ctx.mouse_context.relative_mode == relative {
+        return Ok(());
+    }
+    if ctx.is_headless() {
+        ctx.mouse_context.relative_mode = relative;
+        ctx.mouse_context.last_delta = Point2::origin();
+        return Ok(());
+    }
+    let (grabbed, hidden) = if relative {
+        (true, true)
+    } else {
+        (
+            ctx.mouse_context.cursor_grabbed,
+            ctx.mouse_context.cursor_hidden,
+        )
+    };
+    grab_window_cursor(ctx, grabbed)?;
+    graphics::get_window(ctx).hide_cursor(hidden);
+    ctx.mouse_context.relative_mode = relative;
+    ctx.mouse_context.last_delta = Point2::origin();
+    Ok(())
 }
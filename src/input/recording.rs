@@ -0,0 +1,724 @@
+//! Recording and replaying input.
+//!
+//! While recording, every `GameEvent` the `Context` processes is
+//! saved, along with the length of each frame as given by
+//! `timer::get_delta()`.  The resulting `Recording` can be saved to a
+//! file and loaded again later, and replaying it feeds the same events
+//! to your game on the same frames and makes the timer report the same
+//! frame times, ignoring live input.  If your game logic is otherwise
+//! deterministic (for instance, it seeds its random number generators
+//! the same way each time), it should do exactly the same thing again.
+//! This is very handy for reproducing bug reports and for regression
+//! tests that play through a whole session.
+//!
+//! Example:
+//!
+//! ```rust,ignore
+//! // When the game starts:
+//! recording::start_recording(ctx);
+//!
+//! // When it's done:
+//! if let Some(recording) = recording::stop_recording(ctx) {
+//!     recording.save(ctx, "/last_session.rec")?;
+//! }
+//!
+//! // Then later, to watch it all happen again:
+//! let recording = recording::Recording::load(ctx, "/last_session.rec")?;
+//! recording::start_replay(ctx, recording);
+//! event::run(ctx, events_loop, state)?;
+//! ```
+//!
+//! Recording and replaying rely on the main loop telling them when
+//! each frame starts, so they only work with `event::run()` and
+//! friends, not with a hand-rolled event loop.
+//!
+//! While replaying, the only live event that still gets through is
+//! `GameEvent::Quit`, so you can still close the window.
+
+use std::io::{Read, Write};
+use std::path;
+use std::time;
+use std::vec;
+
+use context::Context;
+use event::{Axis, Button, GameEvent, KeyMods, MouseButton};
+use filesystem;
+use input::gamepad::{GamepadId, ALL_AXES, ALL_BUTTONS};
+use input::keyboard::{self, ALL_KEYCODES};
+use input::touch::TouchPhase;
+use timer;
+use GameError;
+use GameResult;
+
+/// Identifies a recording file, followed by a format version number.
+const MAGIC: &[u8] = b"GGEZREC";
+const VERSION: u8 = 2;
+
+/// The input for a single frame of a `Recording`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedFrame {
+    /// How long the frame took.
+    pub delta: time::Duration,
+    /// The events processed during it, in order.
+    pub events: Vec<GameEvent>,
+}
+
+/// A recorded stream of input, frame by frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    /// Creates a new, empty `Recording`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a `Recording` from the given frames.
+    pub fn from_frames(frames: Vec<RecordedFrame>) -> Self {
+        Recording { frames }
+    }
+
+    /// Returns the recorded frames.
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    /// Loads a `Recording` from the file at the given path.
+    /// See the `filesystem` module docs for where exactly it looks.
+    pub fn load<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let mut file = filesystem::open(ctx, path)?;
+        let mut bytes = Vec::new();
+        let _ = file.read_to_end(&mut bytes)?;
+        Recording::from_bytes(&bytes)
+    }
+
+    /// Saves the `Recording` to the file at the given path.
+    /// See the `filesystem` module docs for where exactly
+    /// the file will end up.
+    pub fn save<P: AsRef<path::Path>>(&self, ctx: &mut Context, path: P) -> GameResult {
+        let mut file = filesystem::create(ctx, path)?;
+        file.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Decodes a `Recording` from the format written by `to_bytes()`.
+    pub fn from_bytes(bytes: &[u8]) -> GameResult<Self> {
+        let mut reader = ByteReader { bytes, pos: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not an input recording"));
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(invalid(&format!("unknown version {}", version)));
+        }
+        let frame_count = reader.u32()?;
+        let mut frames = Vec::new();
+        for _ in 0..frame_count {
+            let delta = time::Duration::new(reader.u64()?, reader.u32()?);
+            let event_count = reader.u32()?;
+            let mut events = Vec::new();
+            for _ in 0..event_count {
+                events.push(reader.event()?);
+            }
+            frames.push(RecordedFrame { delta, events });
+        }
+        Ok(Recording { frames })
+    }
+
+    /// Encodes the `Recording` in a compact binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter { bytes: Vec::new() };
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.u8(VERSION);
+        writer.u32(self.frames.len() as u32);
+        for frame in &self.frames {
+            writer.u64(frame.delta.as_secs());
+            writer.u32(frame.delta.subsec_nanos());
+            writer.u32(frame.events.len() as u32);
+            for event in &frame.events {
+                writer.event(event);
+            }
+        }
+        writer.bytes
+    }
+}
+
+fn invalid(reason: &str) -> GameError {
+    GameError::ResourceLoadError(format!("Invalid input recording: {}", reason))
+}
+
+/// Stands in for the key code of a key press that didn't have one.
+const NO_KEYCODE: u32 = 0xFFFF_FFFF;
+
+// Tags identifying each kind of `GameEvent` in a recording.
+const TAG_MOUSE_BUTTON_DOWN: u8 = 0;
+const TAG_MOUSE_BUTTON_UP: u8 = 1;
+const TAG_MOUSE_MOTION: u8 = 2;
+const TAG_MOUSE_WHEEL: u8 = 3;
+const TAG_KEY_DOWN: u8 = 4;
+const TAG_KEY_UP: u8 = 5;
+const TAG_TEXT_INPUT: u8 = 6;
+const TAG_CONTROLLER_BUTTON_DOWN: u8 = 7;
+const TAG_CONTROLLER_BUTTON_UP: u8 = 8;
+const TAG_CONTROLLER_AXIS: u8 = 9;
+const TAG_FOCUS: u8 = 10;
+const TAG_RESIZE: u8 = 11;
+const TAG_QUIT: u8 = 12;
+const TAG_CONTROLLER_BUTTON_REPEAT: u8 = 13;
+const TAG_CONTROLLER_CONNECTED: u8 = 14;
+const TAG_CONTROLLER_DISCONNECTED: u8 = 15;
+const TAG_RAW_MOUSE_MOTION: u8 = 16;
+const TAG_TOUCH: u8 = 17;
+
+/// Finds where `item` is in `all`.  Anything gilrs has that isn't
+/// listed is recorded as `unknown`, which always is, so that it can't
+/// replay as some other real button or axis.
+fn index_or_unknown<T: PartialEq>(all: &[T], item: T, unknown: T) -> u8 {
+    let index = all
+        .iter()
+        .position(|x| *x == item)
+        .or_else(|| all.iter().position(|x| *x == unknown))
+        .expect("`Unknown` is missing from the list of gamepad inputs");
+    index as u8
+}
+
+/// Writes little-endian values into a byte buffer.
+struct ByteWriter {
+    bytes: Vec<u8>,
+}
+
+impl ByteWriter {
+    fn u8(&mut self, v: u8) {
+        self.bytes.push(v);
+    }
+
+    fn u32(&mut self, v: u32) {
+        for i in 0..4 {
+            self.bytes.push((v >> (i * 8)) as u8);
+        }
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.u32(v as u32);
+        self.u32((v >> 32) as u32);
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.u32(v.to_bits());
+    }
+
+    fn mouse_button(&mut self, button: MouseButton) {
+        match button {
+            MouseButton::Left => self.u8(0),
+            MouseButton::Right => self.u8(1),
+            MouseButton::Middle => self.u8(2),
+            MouseButton::Other(n) => {
+                self.u8(3);
+                self.u8(n);
+            }
+        }
+    }
+
+    fn keycode(&mut self, keycode: Option<keyboard::KeyCode>) {
+        let index = keycode.and_then(|k| ALL_KEYCODES.iter().position(|x| *x == k));
+        self.u32(index.map(|i| i as u32).unwrap_or(NO_KEYCODE));
+    }
+
+    fn gamepad_button(&mut self, button: Button) {
+        self.u8(index_or_unknown(&ALL_BUTTONS, button, Button::Unknown));
+    }
+
+    fn gamepad_axis(&mut self, axis: Axis) {
+        self.u8(index_or_unknown(&ALL_AXES, axis, Axis::Unknown));
+    }
+
+    fn touch_phase(&mut self, phase: TouchPhase) {
+        match phase {
+            TouchPhase::Started => self.u8(0),
+            TouchPhase::Moved => self.u8(1),
+            TouchPhase::Ended => self.u8(2),
+            TouchPhase::Cancelled => self.u8(3),
+        }
+    }
+
+    fn event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::MouseButtonDown { button, x, y } => {
+                self.u8(TAG_MOUSE_BUTTON_DOWN);
+                self.mouse_button(button);
+                self.f32(x);
+                self.f32(y);
+            }
+            GameEvent::MouseButtonUp { button, x, y } => {
+                self.u8(TAG_MOUSE_BUTTON_UP);
+                self.mouse_button(button);
+                self.f32(x);
+                self.f32(y);
+            }
+            GameEvent::MouseMotion { x, y, dx, dy } => {
+                self.u8(TAG_MOUSE_MOTION);
+                self.f32(x);
+                self.f32(y);
+                self.f32(dx);
+                self.f32(dy);
+            }
+            GameEvent::RawMouseMotion { dx, dy } => {
+                self.u8(TAG_RAW_MOUSE_MOTION);
+                self.f32(dx);
+                self.f32(dy);
+            }
+            GameEvent::Touch { id, phase, x, y } => {
+                self.u8(TAG_TOUCH);
+                self.u64(id);
+                self.touch_phase(phase);
+                self.f32(x);
+                self.f32(y);
+            }
+            GameEvent::MouseWheel { x, y } => {
+                self.u8(TAG_MOUSE_WHEEL);
+                self.f32(x);
+                self.f32(y);
+            }
+            GameEvent::KeyDown {
+                keycode,
+                scancode,
+                keymods,
+                repeat,
+            } => {
+                self.u8(TAG_KEY_DOWN);
+                self.keycode(keycode);
+                self.u32(scancode);
+                self.u8(keymods.bits());
+                self.u8(repeat as u8);
+            }
+            GameEvent::KeyUp {
+                keycode,
+                scancode,
+                keymods,
+            } => {
+                self.u8(TAG_KEY_UP);
+                self.keycode(keycode);
+                self.u32(scancode);
+                self.u8(keymods.bits());
+            }
+            GameEvent::TextInput(ch) => {
+                self.u8(TAG_TEXT_INPUT);
+                self.u32(ch as u32);
+            }
+            GameEvent::ControllerButtonDown { button, id } => {
+                self.u8(TAG_CONTROLLER_BUTTON_DOWN);
+                self.gamepad_button(button);
+                self.u32(id.index() as u32);
+            }
+            GameEvent::ControllerButtonRepeat { button, id } => {
+                self.u8(TAG_CONTROLLER_BUTTON_REPEAT);
+                self.gamepad_button(button);
+                self.u32(id.index() as u32);
+            }
+            GameEvent::ControllerButtonUp { button, id } => {
+                self.u8(TAG_CONTROLLER_BUTTON_UP);
+                self.gamepad_button(button);
+                self.u32(id.index() as u32);
+            }
+            GameEvent::ControllerAxis { axis, value, id } => {
+                self.u8(TAG_CONTROLLER_AXIS);
+                self.gamepad_axis(axis);
+                self.f32(value);
+                self.u32(id.index() as u32);
+            }
+            GameEvent::ControllerConnected { id } => {
+                self.u8(TAG_CONTROLLER_CONNECTED);
+                self.u32(id.index() as u32);
+            }
+            GameEvent::ControllerDisconnected { id } => {
+                self.u8(TAG_CONTROLLER_DISCONNECTED);
+                self.u32(id.index() as u32);
+            }
+            GameEvent::Focus(gained) => {
+                self.u8(TAG_FOCUS);
+                self.u8(gained as u8);
+            }
+            GameEvent::Resize { width, height } => {
+                self.u8(TAG_RESIZE);
+                self.f32(width);
+                self.f32(height);
+            }
+            GameEvent::Quit => self.u8(TAG_QUIT),
+        }
+    }
+}
+
+/// Reads little-endian values back out of a byte buffer,
+/// complaining if it runs out.
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> GameResult<&'a [u8]> {
+        if self.bytes.len() - self.pos < len {
+            return Err(invalid("unexpected end of file"));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> GameResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> GameResult<u32> {
+        let b = self.take(4)?;
+        Ok(b.iter()
+            .enumerate()
+            .fold(0, |acc, (i, byte)| acc | (u32::from(*byte) << (i * 8))))
+    }
+
+    fn u64(&mut self) -> GameResult<u64> {
+        let low = u64::from(self.u32()?);
+        let high = u64::from(self.u32()?);
+        Ok(low | (high << 32))
+    }
+
+    fn f32(&mut self) -> GameResult<f32> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    fn bool(&mut self) -> GameResult<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn mouse_button(&mut self) -> GameResult<MouseButton> {
+        match self.u8()? {
+            0 => Ok(MouseButton::Left),
+            1 => Ok(MouseButton::Right),
+            2 => Ok(MouseButton::Middle),
+            3 => Ok(MouseButton::Other(self.u8()?)),
+            n => Err(invalid(&format!("unknown mouse button {}", n))),
+        }
+    }
+
+    fn keycode(&mut self) -> GameResult<Option<keyboard::KeyCode>> {
+        let code = self.u32()?;
+        if code == NO_KEYCODE {
+            return Ok(None);
+        }
+        ALL_KEYCODES
+            .get(code as usize)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| invalid(&format!("unknown key code {}", code)))
+    }
+
+    fn keymods(&mut self) -> GameResult<KeyMods> {
+        let bits = self.u8()?;
+        KeyMods::from_bits(bits).ok_or_else(|| invalid(&format!("unknown key mods {}", bits)))
+    }
+
+    fn gamepad_button(&mut self) -> GameResult<Button> {
+        let idx = self.u8()?;
+        ALL_BUTTONS
+            .get(idx as usize)
+            .cloned()
+            .ok_or_else(|| invalid(&format!("unknown gamepad button {}", idx)))
+    }
+
+    fn gamepad_axis(&mut self) -> GameResult<Axis> {
+        let idx = self.u8()?;
+        ALL_AXES
+            .get(idx as usize)
+            .cloned()
+            .ok_or_else(|| invalid(&format!("unknown gamepad axis {}", idx)))
+    }
+
+    fn touch_phase(&mut self) -> GameResult<TouchPhase> {
+        match self.u8()? {
+            0 => Ok(TouchPhase::Started),
+            1 => Ok(TouchPhase::Moved),
+            2 => Ok(TouchPhase::Ended),
+            3 => Ok(TouchPhase::Cancelled),
+            n => Err(invalid(&format!("unknown touch phase {}", n))),
+        }
+    }
+
+    fn event(&mut self) -> GameResult<GameEvent> {
+        let event = match self.u8()? {
+            TAG_MOUSE_BUTTON_DOWN => GameEvent::MouseButtonDown {
+                button: self.mouse_button()?,
+                x: self.f32()?,
+                y: self.f32()?,
+            },
+            TAG_MOUSE_BUTTON_UP => GameEvent::MouseButtonUp {
+                button: self.mouse_button()?,
+                x: self.f32()?,
+                y: self.f32()?,
+            },
+            TAG_MOUSE_MOTION => GameEvent::MouseMotion {
+                x: self.f32()?,
+                y: self.f32()?,
+                dx: self.f32()?,
+                dy: self.f32()?,
+            },
+            TAG_MOUSE_WHEEL => GameEvent::MouseWheel {
+                x: self.f32()?,
+                y: self.f32()?,
+            },
+            TAG_KEY_DOWN => GameEvent::KeyDown {
+                keycode: self.keycode()?,
+                scancode: self.u32()?,
+                keymods: self.keymods()?,
+                repeat: self.bool()?,
+            },
+            TAG_KEY_UP => GameEvent::KeyUp {
+                keycode: self.keycode()?,
+                scancode: self.u32()?,
+                keymods: self.keymods()?,
+            },
+            TAG_TEXT_INPUT => {
+                let code = self.u32()?;
+                let ch = ::std::char::from_u32(code)
+                    .ok_or_else(|| invalid(&format!("invalid character {}", code)))?;
+                GameEvent::TextInput(ch)
+            }
+            TAG_CONTROLLER_BUTTON_DOWN => GameEvent::ControllerButtonDown {
+                button: self.gamepad_button()?,
+                id: GamepadId(self.u32()? as usize),
+            },
+            TAG_CONTROLLER_BUTTON_UP => GameEvent::ControllerButtonUp {
+                button: self.gamepad_button()?,
+                id: GamepadId(self.u32()? as usize),
+            },
+            TAG_CONTROLLER_AXIS => GameEvent::ControllerAxis {
+                axis: self.gamepad_axis()?,
+                value: self.f32()?,
+                id: GamepadId(self.u32()? as usize),
+            },
+            TAG_FOCUS => GameEvent::Focus(self.bool()?),
+            TAG_RESIZE => GameEvent::Resize {
+                width: self.f32()?,
+                height: self.f32()?,
+            },
+            TAG_QUIT => GameEvent::Quit,
+            TAG_CONTROLLER_BUTTON_REPEAT => GameEvent::ControllerButtonRepeat {
+                button: self.gamepad_button()?,
+                id: GamepadId(self.u32()? as usize),
+            },
+            TAG_CONTROLLER_CONNECTED => GameEvent::ControllerConnected {
+                id: GamepadId(self.u32()? as usize),
+            },
+            TAG_CONTROLLER_DISCONNECTED => GameEvent::ControllerDisconnected {
+                id: GamepadId(self.u32()? as usize),
+            },
+            TAG_RAW_MOUSE_MOTION => GameEvent::RawMouseMotion {
+                dx: self.f32()?,
+                dy: self.f32()?,
+            },
+            TAG_TOUCH => GameEvent::Touch {
+                id: self.u64()?,
+                phase: self.touch_phase()?,
+                x: self.f32()?,
+                y: self.f32()?,
+            },
+            tag => return Err(invalid(&format!("unknown event type {}", tag))),
+        };
+        Ok(event)
+    }
+}
+
+/// Holds the state of any recording or replay in progress.
+#[derive(Debug, Default)]
+pub struct RecordingContext {
+    recording: Option<Recording>,
+    replay: Option<vec::IntoIter<RecordedFrame>>,
+}
+
+impl RecordingContext {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an event the `Context` just processed to the
+    /// recording, if we're recording and the first recorded
+    /// frame has started.
+    pub(crate) fn record_event(&mut self, event: &GameEvent) {
+        if let Some(ref mut recording) = self.recording {
+            if let Some(frame) = recording.frames.last_mut() {
+                frame.events.push(*event);
+            }
+        }
+    }
+
+    /// Whether an event coming from real input should be processed;
+    /// during a replay only the recorded events should be.
+    pub(crate) fn accepts_live_event(&self, event: &GameEvent) -> bool {
+        self.replay.is_none() || *event == GameEvent::Quit
+    }
+}
+
+/// Called by the main loop at the start of each frame, right after
+/// `TimeContext::tick()`.  If we're replaying, this overrides the
+/// frame's delta with the recorded one and returns the recorded events
+/// to feed to the game; if we're recording, it starts a new frame of
+/// the recording.
+pub(crate) fn begin_frame(ctx: &mut Context) -> Option<Vec<GameEvent>> {
+    let replayed = match ctx.recording_context.replay {
+        Some(ref mut frames) => frames.next(),
+        None => None,
+    };
+    let events = match replayed {
+        Some(frame) => {
+            ctx.timer_context.override_delta(frame.delta);
+            Some(frame.events)
+        }
+        None => {
+            if ctx.recording_context.replay.take().is_some() {
+                info!("Input replay finished");
+            }
+            None
+        }
+    };
+    let delta = timer::get_delta(ctx);
+    if let Some(ref mut recording) = ctx.recording_context.recording {
+        recording.frames.push(RecordedFrame {
+            delta,
+            events: Vec::new(),
+        });
+    }
+    events
+}
+
+/// Starts recording input from the next frame on, throwing away
+/// any recording already in progress.
+pub fn start_recording(ctx: &mut Context) {
+    ctx.recording_context.recording = Some(Recording::new());
+}
+
+/// Stops recording input and returns what was recorded,
+/// or `None` if we weren't recording.
+pub fn stop_recording(ctx: &mut Context) -> Option<Recording> {
+    ctx.recording_context.recording.take()
+}
+
+/// Returns whether input is being recorded.
+pub fn is_recording(ctx: &Context) -> bool {
+    ctx.recording_context.recording.is_some()
+}
+
+/// Starts replaying the given recording, starting with the next
+/// frame.  Live input (apart from quit events) is ignored until it
+/// runs out or `stop_replay()` is called.
+pub fn start_replay(ctx: &mut Context, recording: Recording) {
+    ctx.recording_context.replay = Some(recording.frames.into_iter());
+}
+
+/// Stops replaying input and goes back to live input.
+pub fn stop_replay(ctx: &mut Context) {
+    ctx.recording_context.replay = None;
+}
+
+/// Returns whether a recording is being replayed.
+pub fn is_replaying(ctx: &Context) -> bool {
+    ctx.recording_context.replay.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use event::KeyCode;
+
+    #[test]
+    fn headless_recording_round_trip() {
+        let recording = Recording::from_frames(vec![
+            RecordedFrame {
+                delta: time::Duration::new(0, 16_666_667),
+                events: vec![
+                    GameEvent::KeyDown {
+                        keycode: Some(KeyCode::Space),
+                        scancode: 57,
+                        keymods: KeyMods::SHIFT | KeyMods::CTRL,
+                        repeat: false,
+                    },
+                    GameEvent::MouseMotion {
+                        x: 10.5,
+                        y: -3.0,
+                        dx: 1.0,
+                        dy: 2.0,
+                    },
+                    GameEvent::MouseButtonDown {
+                        button: MouseButton::Other(7),
+                        x: 10.5,
+                        y: -3.0,
+                    },
+                    GameEvent::TextInput('ü'),
+                    GameEvent::Touch {
+                        id: 1 << 40,
+                        phase: TouchPhase::Cancelled,
+                        x: 0.25,
+                        y: 600.0,
+                    },
+                ],
+            },
+            RecordedFrame {
+                delta: time::Duration::new(1, 5),
+                events: vec![],
+            },
+            RecordedFrame {
+                delta: time::Duration::new(0, 16_666_667),
+                events: vec![
+                    GameEvent::ControllerAxis {
+                        axis: Axis::RightStickY,
+                        value: -0.25,
+                        id: GamepadId(2),
+                    },
+                    GameEvent::ControllerButtonUp {
+                        button: Button::DPadLeft,
+                        id: GamepadId(0),
+                    },
+                    GameEvent::ControllerConnected { id: GamepadId(3) },
+                    GameEvent::KeyUp {
+                        keycode: Some(KeyCode::Cut),
+                        scancode: 0,
+                        keymods: KeyMods::empty(),
+                    },
+                    GameEvent::KeyDown {
+                        keycode: None,
+                        scancode: 0xE05B,
+                        keymods: KeyMods::LOGO,
+                        repeat: true,
+                    },
+                    GameEvent::Focus(true),
+                    GameEvent::Resize {
+                        width: 640.0,
+                        height: 480.0,
+                    },
+                    GameEvent::Quit,
+                ],
+            },
+        ]);
+        let bytes = recording.to_bytes();
+        let decoded = Recording::from_bytes(&bytes).unwrap();
+        assert_eq!(recording, decoded);
+    }
+
+    #[test]
+    fn headless_unlisted_inputs_record_as_unknown() {
+        assert_eq!(
+            index_or_unknown(&ALL_BUTTONS, Button::East, Button::Unknown),
+            1
+        );
+        assert_eq!(index_or_unknown(&["a", "b", "?"], "z", "?"), 2);
+    }
+
+    #[test]
+    fn headless_recording_rejects_garbage() {
+        assert!(Recording::from_bytes(b"").is_err());
+        assert!(Recording::from_bytes(b"NOTAREC\x01").is_err());
+        let bytes = Recording::new().to_bytes();
+        assert!(Recording::from_bytes(&bytes).is_ok());
+        assert!(Recording::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
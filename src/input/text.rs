@@ -0,0 +1,449 @@
+//! Text entry, for things like chat boxes and name entry.
+//!
+//! Calling `start_text_input()` starts a text-entry session: from
+//! then on, typed characters go into a text buffer kept by the
+//! `Context`, and the usual editing keys work on it.  Those are
+//! Backspace and Delete, Left and Right (add Ctrl to move a word at a
+//! time), Home and End, Shift with any of the movement keys to
+//! select, and Ctrl+A, Ctrl+C, Ctrl+X and Ctrl+V (or Cmd on a Mac) to
+//! select all, copy, cut and paste.  Read the result with
+//! `get_text()` and end the session with `stop_text_input()`.  Your
+//! `EventHandler` still gets every `text_input_event()` and
+//! `key_down_event()` as usual, so it can watch for Return or Escape
+//! itself.
+//!
+//! Input methods for languages such as Chinese, Japanese and Korean
+//! work too: the text they produce arrives as ordinary characters
+//! once the player has finished composing it.  The input method draws
+//! the text while it's still being composed in its own little window;
+//! call `set_ime_position()` to put that window next to your text box.
+//!
+//! There are no composition (preedit) events, so you can't draw the
+//! text being composed inside your own text box.  The version of
+//! `winit` we use doesn't report it on any platform, so there's
+//! nothing for such events to carry; they'll be added once `winit`
+//! does.
+
+use std::fmt;
+use std::ops::Range;
+
+use clipboard::{ClipboardContext, ClipboardProvider};
+use winit::dpi;
+
+use context::Context;
+use event::{GameEvent, KeyCode, KeyMods};
+use graphics;
+use graphics::Point2;
+use GameError;
+use GameResult;
+
+/// The text being edited, plus the cursor and selection.  Positions
+/// are byte offsets into the text, always on `char` boundaries.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TextBuffer {
+    text: String,
+    cursor: usize,
+    /// The other end of the selection from the cursor, if any.
+    anchor: Option<usize>,
+}
+
+impl TextBuffer {
+    fn selection(&self) -> Option<Range<usize>> {
+        match self.anchor {
+            Some(anchor) if anchor < self.cursor => Some(anchor..self.cursor),
+            Some(anchor) if anchor > self.cursor => Some(self.cursor..anchor),
+            _ => None,
+        }
+    }
+
+    fn selected_text(&self) -> &str {
+        self.selection()
+            .map(|range| &self.text[range])
+            .unwrap_or("")
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.text = text.to_owned();
+        self.cursor = self.text.len();
+        self.anchor = None;
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some(range) => {
+                self.cursor = range.start;
+                self.anchor = None;
+                let _ = self.text.drain(range);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, s: &str) {
+        let _ = self.delete_selection();
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    fn prev_boundary(&self, from: usize) -> usize {
+        self.text[..from]
+            .char_indices()
+            .next_back()
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
+    fn next_boundary(&self, from: usize) -> usize {
+        self.text[from..]
+            .chars()
+            .next()
+            .map(|c| from + c.len_utf8())
+            .unwrap_or(from)
+    }
+
+    /// The start of the word before `from`, skipping any whitespace
+    /// right before it.
+    fn prev_word(&self, from: usize) -> usize {
+        let mut pos = from;
+        let mut seen_word = false;
+        for (i, c) in self.text[..from].char_indices().rev() {
+            if c.is_whitespace() {
+                if seen_word {
+                    break;
+                }
+            } else {
+                seen_word = true;
+            }
+            pos = i;
+        }
+        pos
+    }
+
+    /// The end of the word after `from`, skipping any whitespace
+    /// right after it.
+    fn next_word(&self, from: usize) -> usize {
+        let mut seen_word = false;
+        for (i, c) in self.text[from..].char_indices() {
+            if c.is_whitespace() {
+                if seen_word {
+                    return from + i;
+                }
+            } else {
+                seen_word = true;
+            }
+        }
+        self.text.len()
+    }
+
+    /// Moves the cursor to `to`, extending the selection if `select`
+    /// is set and dropping it otherwise.
+    fn move_to(&mut self, to: usize, select: bool) {
+        if select {
+            if self.anchor.is_none() {
+                self.anchor = Some(self.cursor);
+            }
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = to;
+    }
+
+    fn backspace(&mut self, by_word: bool) {
+        if !self.delete_selection() {
+            let start = if by_word {
+                self.prev_word(self.cursor)
+            } else {
+                self.prev_boundary(self.cursor)
+            };
+            let _ = self.text.drain(start..self.cursor);
+            self.cursor = start;
+        }
+    }
+
+    fn delete(&mut self, by_word: bool) {
+        if !self.delete_selection() {
+            let end = if by_word {
+                self.next_word(self.cursor)
+            } else {
+                self.next_boundary(self.cursor)
+            };
+            let _ = self.text.drain(self.cursor..end);
+        }
+    }
+}
+
+/// Holds the state of text entry and the clipboard.
+pub struct TextContext {
+    active: bool,
+    buffer: TextBuffer,
+    /// `None` if there's no system clipboard, such as in a headless
+    /// context; `local_clipboard` stands in for it then.
+    clipboard: Option<ClipboardContext>,
+    local_clipboard: String,
+}
+
+impl fmt::Debug for TextContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<TextContext: {:p}>", self)
+    }
+}
+
+impl TextContext {
+    pub(crate) fn new() -> Self {
+        let clipboard = match ClipboardProvider::new() {
+            Ok(clipboard) => Some(clipboard),
+            Err(e) => {
+                warn!("Could not open the system clipboard: {}", e);
+                None
+            }
+        };
+        TextContext {
+            active: false,
+            buffer: TextBuffer::default(),
+            clipboard,
+            local_clipboard: String::new(),
+        }
+    }
+
+    pub(crate) fn new_headless() -> Self {
+        TextContext {
+            active: false,
+            buffer: TextBuffer::default(),
+            clipboard: None,
+            local_clipboard: String::new(),
+        }
+    }
+
+    fn get_clipboard(&mut self) -> GameResult<String> {
+        match self.clipboard {
+            Some(ref mut clipboard) => clipboard
+                .get_contents()
+                .map_err(|e| GameError::WindowError(format!("Could not read clipboard: {}", e))),
+            None => Ok(self.local_clipboard.clone()),
+        }
+    }
+
+    fn set_clipboard(&mut self, contents: &str) -> GameResult {
+        match self.clipboard {
+            Some(ref mut clipboard) => clipboard
+                .set_contents(contents.to_owned())
+                .map_err(|e| GameError::WindowError(format!("Could not write clipboard: {}", e))),
+            None => {
+                self.local_clipboard = contents.to_owned();
+                Ok(())
+            }
+        }
+    }
+
+    fn copy_selection(&mut self) {
+        let selected = self.buffer.selected_text().to_owned();
+        if !selected.is_empty() {
+            if let Err(e) = self.set_clipboard(&selected) {
+                warn!("{}", e);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, keycode: KeyCode, keymods: KeyMods) {
+        let select = keymods.contains(KeyMods::SHIFT);
+        let by_word = keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::ALT);
+        let command = keymods.contains(KeyMods::CTRL) || keymods.contains(KeyMods::LOGO);
+        let cursor = self.buffer.cursor;
+        match keycode {
+            KeyCode::Back => self.buffer.backspace(by_word),
+            KeyCode::Delete => self.buffer.delete(by_word),
+            KeyCode::Left => {
+                let to = match self.buffer.selection() {
+                    Some(range) if !select => range.start,
+                    _ if by_word => self.buffer.prev_word(cursor),
+                    _ => self.buffer.prev_boundary(cursor),
+                };
+                self.buffer.move_to(to, select);
+            }
+            KeyCode::Right => {
+                let to = match self.buffer.selection() {
+                    Some(range) if !select => range.end,
+                    _ if by_word => self.buffer.next_word(cursor),
+                    _ => self.buffer.next_boundary(cursor),
+                };
+                self.buffer.move_to(to, select);
+            }
+            KeyCode::Home | KeyCode::Up => self.buffer.move_to(0, select),
+            KeyCode::End | KeyCode::Down => {
+                let end = self.buffer.text.len();
+                self.buffer.move_to(end, select);
+            }
+            KeyCode::A if command => {
+                self.buffer.anchor = Some(0);
+                self.buffer.cursor = self.buffer.text.len();
+            }
+            KeyCode::C if command => self.copy_selection(),
+            KeyCode::X if command => {
+                self.copy_selection();
+                let _ = self.buffer.delete_selection();
+            }
+            KeyCode::V if command => match self.get_clipboard() {
+                Ok(contents) => {
+                    // Only the first line, since this is single-line
+                    // text entry.
+                    let line = contents.lines().next().unwrap_or("");
+                    self.buffer.insert(line);
+                }
+                Err(e) => warn!("{}", e),
+            },
+            _ => (),
+        }
+    }
+
+    /// Updates the text being entered from an input event.
+    pub(crate) fn handle_event(&mut self, event: &GameEvent) {
+        if !self.active {
+            return;
+        }
+        match *event {
+            GameEvent::TextInput(ch) if !ch.is_control() => {
+                let mut utf8 = [0; 4];
+                self.buffer.insert(ch.encode_utf8(&mut utf8));
+            }
+            GameEvent::KeyDown {
+                keycode: Some(keycode),
+                keymods,
+                ..
+            } => self.handle_key(keycode, keymods),
+            _ => (),
+        }
+    }
+}
+
+/// Starts a text-entry session, keeping any text already entered.
+pub fn start_text_input(ctx: &mut Context) {
+    ctx.text_context.active = true;
+}
+
+/// Ends the text-entry session.  The text stays put until it's
+/// changed with `set_text()` or another session.
+pub fn stop_text_input(ctx: &mut Context) {
+    ctx.text_context.active = false;
+}
+
+/// Returns whether a text-entry session is going on.
+pub fn is_text_input_active(ctx: &Context) -> bool {
+    ctx.text_context.active
+}
+
+/// Returns the text that's been entered.
+pub fn get_text(ctx: &Context) -> &str {
+    &ctx.text_context.buffer.text
+}
+
+/// Replaces the text being edited, putting the cursor at the end.
+/// Use `set_text(ctx, "")` to clear it.
+pub fn set_text(ctx: &mut Context, text: &str) {
+    ctx.text_context.buffer.set_text(text);
+}
+
+/// Returns the position of the cursor, as a byte offset into the
+/// text.
+pub fn get_cursor(ctx: &Context) -> usize {
+    ctx.text_context.buffer.cursor
+}
+
+/// Returns the selected part of the text as a range of byte offsets,
+/// or `None` if nothing is selected.
+pub fn get_selection(ctx: &Context) -> Option<Range<usize>> {
+    ctx.text_context.buffer.selection()
+}
+
+/// Returns the selected part of the text.
+pub fn get_selected_text(ctx: &Context) -> &str {
+    ctx.text_context.buffer.selected_text()
+}
+
+/// Tells the system's input method where the text box is, so it can
+/// show the text being composed and its list of suggestions next to
+/// it.  Coordinates are in pixels from the top left of the window.
+/// Only some platforms support this, and it does nothing in a
+/// headless context.
+pub fn set_ime_position(ctx: &Context, position: Point2) {
+    if ctx.is_headless() {
+        return;
+    }
+    graphics::get_window(ctx).set_ime_spot(dpi::LogicalPosition {
+        x: position.x as f64,
+        y: position.y as f64,
+    });
+}
+
+/// Returns the contents of the clipboard.
+///
+/// A headless context has a clipboard of its own, which starts out
+/// empty.
+pub fn get_clipboard(ctx: &mut Context) -> GameResult<String> {
+    ctx.text_context.get_clipboard()
+}
+
+/// Puts the given text on the clipboard.
+pub fn set_clipboard(ctx: &mut Context, contents: &str) -> GameResult {
+    ctx.text_context.set_clipboard(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(text: &mut TextContext, s: &str) {
+        for ch in s.chars() {
+            text.handle_event(&GameEvent::TextInput(ch));
+        }
+    }
+
+    fn press(text: &mut TextContext, keycode: KeyCode, keymods: KeyMods) {
+        text.handle_event(&GameEvent::key_down(keycode, keymods));
+    }
+
+    #[test]
+    fn headless_editing_keys() {
+        let mut text = TextContext::new_headless();
+        type_text(&mut text, "ignored");
+        assert_eq!(text.buffer.text, "");
+
+        text.active = true;
+        type_text(&mut text, "héllo wörld\u{8}");
+        assert_eq!(text.buffer.text, "héllo wörld");
+        press(&mut text, KeyCode::Back, KeyMods::NONE);
+        assert_eq!(text.buffer.text, "héllo wörl");
+        press(&mut text, KeyCode::Back, KeyMods::CTRL);
+        assert_eq!(text.buffer.text, "héllo ");
+        press(&mut text, KeyCode::Home, KeyMods::NONE);
+        press(&mut text, KeyCode::Right, KeyMods::NONE);
+        press(&mut text, KeyCode::Delete, KeyMods::NONE);
+        assert_eq!(text.buffer.text, "hllo ");
+        type_text(&mut text, "ë");
+        assert_eq!(text.buffer.text, "hëllo ");
+        assert_eq!(text.buffer.cursor, "hë".len());
+    }
+
+    #[test]
+    fn headless_selection_and_clipboard() {
+        let mut text = TextContext::new_headless();
+        text.active = true;
+        type_text(&mut text, "one two three");
+        press(&mut text, KeyCode::Left, KeyMods::CTRL | KeyMods::SHIFT);
+        assert_eq!(text.buffer.selected_text(), "three");
+        press(&mut text, KeyCode::X, KeyMods::CTRL);
+        assert_eq!(text.buffer.text, "one two ");
+        assert_eq!(text.local_clipboard, "three");
+        press(&mut text, KeyCode::Home, KeyMods::NONE);
+        press(&mut text, KeyCode::V, KeyMods::CTRL);
+        assert_eq!(text.buffer.text, "threeone two ");
+        press(&mut text, KeyCode::A, KeyMods::CTRL);
+        assert_eq!(text.buffer.selection(), Some(0.."threeone two ".len()));
+        type_text(&mut text, "x");
+        assert_eq!(text.buffer.text, "x");
+        press(&mut text, KeyCode::Left, KeyMods::SHIFT);
+        press(&mut text, KeyCode::Right, KeyMods::NONE);
+        assert_eq!(text.buffer.selection(), None);
+        assert_eq!(text.buffer.cursor, 1);
+    }
+}
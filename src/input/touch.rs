@@ -0,0 +1,171 @@
+//! Touchscreen input.
+//!
+//! Each finger touching the screen gets a numeric id that stays the
+//! same from when it touches down until it lifts off.  Positions are
+//! in the same coordinates as drawing, as set with
+//! `graphics::set_screen_coordinates()`, not window pixels.
+//!
+//! Games written for the mouse can turn on mouse emulation with
+//! `set_mouse_emulation()`: the first finger down then acts as the
+//! left mouse button, so tapping and dragging work without any
+//! touch-specific code.  Some platforms do this on their own already,
+//! so it's off by default.
+
+use std::collections::BTreeMap;
+
+use context::Context;
+use event::{GameEvent, MouseButton};
+use graphics;
+use graphics::Point2;
+pub use winit::TouchPhase;
+
+/// Keeps track of which fingers are touching the screen and where.
+#[derive(Clone, Debug, Default)]
+pub struct TouchContext {
+    touches: BTreeMap<u64, Point2>,
+    emulate_mouse: bool,
+    /// The touch that's acting as the mouse, if any.
+    mouse_touch: Option<u64>,
+    /// The mouse event the last touch event turned into, waiting to
+    /// be handed to the `EventHandler`.
+    emulated_event: Option<GameEvent>,
+}
+
+impl TouchContext {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the touch state, working out which mouse event it
+    /// should be emulated as if mouse emulation is on.  `window_pos`
+    /// is where the touch is in window coordinates, since that's
+    /// what mouse events use, and `mouse_pos` is where the mouse was
+    /// last.
+    pub(crate) fn set_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        pos: Point2,
+        window_pos: Point2,
+        mouse_pos: Point2,
+    ) -> Option<GameEvent> {
+        let (x, y) = (window_pos.x, window_pos.y);
+        self.emulated_event = match phase {
+            TouchPhase::Started => {
+                let _ = self.touches.insert(id, pos);
+                if self.emulate_mouse && self.mouse_touch.is_none() {
+                    self.mouse_touch = Some(id);
+                    Some(GameEvent::MouseButtonDown {
+                        button: MouseButton::Left,
+                        x,
+                        y,
+                    })
+                } else {
+                    None
+                }
+            }
+            TouchPhase::Moved => {
+                let _ = self.touches.insert(id, pos);
+                if self.mouse_touch == Some(id) {
+                    Some(GameEvent::MouseMotion {
+                        x,
+                        y,
+                        dx: x - mouse_pos.x,
+                        dy: y - mouse_pos.y,
+                    })
+                } else {
+                    None
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                let _ = self.touches.remove(&id);
+                if self.mouse_touch == Some(id) {
+                    self.mouse_touch = None;
+                    Some(GameEvent::MouseButtonUp {
+                        button: MouseButton::Left,
+                        x,
+                        y,
+                    })
+                } else {
+                    None
+                }
+            }
+        };
+        self.emulated_event
+    }
+
+    /// Takes the emulated mouse event from the last touch event, if
+    /// there is one.
+    pub(crate) fn take_emulated_event(&mut self) -> Option<GameEvent> {
+        self.emulated_event.take()
+    }
+}
+
+/// Converts a position in window pixels to screen coordinates.
+/// Headless contexts have no screen, so there they're the same.
+pub(crate) fn window_to_screen(ctx: &Context, x: f32, y: f32) -> Point2 {
+    if ctx.is_headless() {
+        return Point2::new(x, y);
+    }
+    let screen = graphics::get_screen_coordinates(ctx);
+    let (width, height) = graphics::get_drawable_size(ctx);
+    if width <= 0.0 || height <= 0.0 {
+        return Point2::new(x, y);
+    }
+    Point2::new(
+        screen.x + x / width as f32 * screen.w,
+        screen.y + y / height as f32 * screen.h,
+    )
+}
+
+/// Converts a position in screen coordinates to window pixels; the
+/// reverse of `window_to_screen()`.
+pub(crate) fn screen_to_window(ctx: &Context, p: Point2) -> Point2 {
+    if ctx.is_headless() {
+        return p;
+    }
+    let screen = graphics::get_screen_coordinates(ctx);
+    let (width, height) = graphics::get_drawable_size(ctx);
+    if screen.w == 0.0 || screen.h == 0.0 {
+        return p;
+    }
+    Point2::new(
+        (p.x - screen.x) / screen.w * width as f32,
+        (p.y - screen.y) / screen.h * height as f32,
+    )
+}
+
+/// Returns the ids and positions of every finger touching the
+/// screen, ordered by id.
+pub fn get_touches(ctx: &Context) -> Vec<(u64, Point2)> {
+    ctx.touch_context
+        .touches
+        .iter()
+        .map(|(&id, &pos)| (id, pos))
+        .collect()
+}
+
+/// Returns where the given finger is, or `None` if it isn't touching
+/// the screen.
+pub fn get_touch_position(ctx: &Context, id: u64) -> Option<Point2> {
+    ctx.touch_context.touches.get(&id).cloned()
+}
+
+/// Returns how many fingers are touching the screen.
+pub fn get_touch_count(ctx: &Context) -> usize {
+    ctx.touch_context.touches.len()
+}
+
+/// Turns mouse emulation on or off.  While it's on, the first finger
+/// to touch the screen also sends left mouse button and mouse motion
+/// events, and updates the state in `input::mouse`.  Turning it off
+/// doesn't take effect until that finger lifts, so the mouse button
+/// doesn't get stuck down.
+pub fn set_mouse_emulation(ctx: &mut Context, enabled: bool) {
+    ctx.touch_context.emulate_mouse = enabled;
+}
+
+/// Returns whether mouse emulation is on.
+pub fn get_mouse_emulation(ctx: &Context) -> bool {
+    ctx.touch_context.emulate_mouse
+}
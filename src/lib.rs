@@ -34,6 +34,7 @@
 #[macro_use]
 extern crate bitflags;
 extern crate app_dirs2;
+extern crate clipboard;
 #[macro_use]
 extern crate gfx;
 extern crate gfx_device_gl;
@@ -46,7 +47,9 @@ extern crate log;
 extern crate lyon;
 extern crate mint;
 pub extern crate nalgebra;
+extern crate rand;
 extern crate rodio;
+extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 #[macro_use]
@@ -64,7 +67,10 @@ pub mod event;
 pub mod filesystem;
 pub mod graphics;
 pub mod input;
+pub mod scene;
+pub mod schedule;
 pub mod timer;
+pub mod tween;
 mod vfs;
 
 pub use context::{Context, ContextBuilder};
@@ -0,0 +1,532 @@
+//! A stack of scenes, for games with more than one screen.
+//!
+//! Title screens, menus, levels and game-over screens can each be a
+//! separate `Scene`, rather than all being crammed into one
+//! `EventHandler`.  A `SceneStack` keeps track of them and is itself
+//! an `EventHandler`, so you hand it to `event::run()` in place of
+//! your game state.  Only the scene on top of the stack gets updated
+//! and gets input; it can push a new scene on top of itself, pop
+//! itself off, or replace itself by returning a `SceneSwitch` from
+//! `update()`.  When the last scene is popped the game quits.
+//!
+//! Each scene is normally drawn on its own, but a scene that returns
+//! `true` from `draw_previous()` is drawn on top of the scene below
+//! it, which is how you make pause menus and other overlays.  The
+//! bottom scene being drawn should start by calling
+//! `graphics::clear()`; the `SceneStack` calls `graphics::present()`
+//! once they're all done.
+//!
+//! Anything the scenes need to share, such as loaded assets or the
+//! player's score, goes in the stack's `world`, which is handed to
+//! every scene callback.
+
+use std::fmt;
+use std::path;
+
+use context::Context;
+use event::{
+    Axis, Button, EventHandler, GamepadId, KeyCode, KeyMods, MouseButton, ScanCode, TouchPhase,
+};
+use graphics;
+use timer;
+use GameResult;
+
+/// A transition between scenes, returned from `Scene::update()`.
+pub enum SceneSwitch<C, E = ()> {
+    /// Stay in the current scene.
+    None,
+    /// Put a new scene on top of the current one, which is paused
+    /// until the new scene is popped.
+    Push(Box<dyn Scene<C, E>>),
+    /// Remove the current scene, going back to the one below it.
+    Pop,
+    /// Swap the current scene for a new one.
+    Replace(Box<dyn Scene<C, E>>),
+}
+
+impl<C, E> SceneSwitch<C, E> {
+    /// Shortcut for `SceneSwitch::Push(Box::new(scene))`.
+    pub fn push<S>(scene: S) -> Self
+    where
+        S: Scene<C, E> + 'static,
+    {
+        SceneSwitch::Push(Box::new(scene))
+    }
+
+    /// Shortcut for `SceneSwitch::Replace(Box::new(scene))`.
+    pub fn replace<S>(scene: S) -> Self
+    where
+        S: Scene<C, E> + 'static,
+    {
+        SceneSwitch::Replace(Box::new(scene))
+    }
+}
+
+impl<C, E> fmt::Debug for SceneSwitch<C, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SceneSwitch::None => write!(f, "SceneSwitch::None"),
+            SceneSwitch::Push(ref scene) => write!(f, "SceneSwitch::Push({})", scene.name()),
+            SceneSwitch::Pop => write!(f, "SceneSwitch::Pop"),
+            SceneSwitch::Replace(ref scene) => {
+                write!(f, "SceneSwitch::Replace({})", scene.name())
+            }
+        }
+    }
+}
+
+/// One scene of a game, such as a menu or a level.  This is like
+/// `EventHandler`, but each callback also gets the `SceneStack`'s
+/// shared world, and `update()` can switch to another scene.
+///
+/// Unlike `EventHandler::key_down_event()`, the default
+/// `key_down_event()` here does nothing, so Escape doesn't quit the
+/// game unless you make it.  `E` is the type of user events, as for
+/// `EventHandler`.
+pub trait Scene<C, E = ()> {
+    /// Called upon each logic update while this is the top scene.
+    fn update(&mut self, world: &mut C, ctx: &mut Context) -> GameResult<SceneSwitch<C, E>>;
+
+    /// Draws the scene.  Don't call `graphics::present()` here; the
+    /// `SceneStack` does that after drawing all the visible scenes.
+    fn draw(&mut self, world: &mut C, ctx: &mut Context) -> GameResult;
+
+    /// A name for the scene, for debugging.
+    fn name(&self) -> &str {
+        "<unnamed scene>"
+    }
+
+    /// Whether the scene below this one should be drawn first, with
+    /// this one on top of it.
+    fn draw_previous(&self) -> bool {
+        false
+    }
+
+    /// A mouse button was pressed.
+    fn mouse_button_down_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+    }
+
+    /// A mouse button was released.
+    fn mouse_button_up_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+    }
+
+    /// The mouse was moved.
+    fn mouse_motion_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _x: f32,
+        _y: f32,
+        _dx: f32,
+        _dy: f32,
+    ) {
+    }
+
+    /// The mouse was moved while in relative mode.
+    fn raw_mouse_motion_event(&mut self, _world: &mut C, _ctx: &mut Context, _dx: f32, _dy: f32) {}
+
+    /// The mousewheel was scrolled.
+    fn mouse_wheel_event(&mut self, _world: &mut C, _ctx: &mut Context, _x: f32, _y: f32) {}
+
+    /// A keyboard button was pressed.
+    fn key_down_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _keycode: KeyCode,
+        _keymods: KeyMods,
+        _repeat: bool,
+    ) {
+    }
+
+    /// A keyboard button was released.
+    fn key_up_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _keycode: KeyCode,
+        _keymods: KeyMods,
+    ) {
+    }
+
+    /// A keyboard button was pressed, identified by its scancode.
+    fn scancode_down_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _scancode: ScanCode,
+        _keymods: KeyMods,
+        _repeat: bool,
+    ) {
+    }
+
+    /// A keyboard button was released, identified by its scancode.
+    fn scancode_up_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _scancode: ScanCode,
+        _keymods: KeyMods,
+    ) {
+    }
+
+    /// A unicode character was received.
+    fn text_input_event(&mut self, _world: &mut C, _ctx: &mut Context, _character: char) {}
+
+    /// A controller button was pressed.
+    fn controller_button_down_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _btn: Button,
+        _id: GamepadId,
+    ) {
+    }
+
+    /// A controller button is being held down and has repeated.
+    fn controller_button_repeat_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _btn: Button,
+        _id: GamepadId,
+    ) {
+    }
+
+    /// A controller button was released.
+    fn controller_button_up_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _btn: Button,
+        _id: GamepadId,
+    ) {
+    }
+
+    /// A controller axis moved.
+    fn controller_axis_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _axis: Axis,
+        _value: f32,
+        _id: GamepadId,
+    ) {
+    }
+
+    /// A controller was plugged in.
+    fn controller_connected_event(&mut self, _world: &mut C, _ctx: &mut Context, _id: GamepadId) {}
+
+    /// A controller was unplugged.
+    fn controller_disconnected_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _id: GamepadId,
+    ) {
+    }
+
+    /// A finger touched, moved on or lifted off the touchscreen.
+    fn touch_event(
+        &mut self,
+        _world: &mut C,
+        _ctx: &mut Context,
+        _phase: TouchPhase,
+        _id: u64,
+        _x: f32,
+        _y: f32,
+    ) {
+    }
+
+    /// The window gained or lost focus.  Every scene on the stack gets
+    /// this.
+    fn focus_event(&mut self, _world: &mut C, _ctx: &mut Context, _gained: bool) {}
+
+    /// The window was resized.  Every scene on the stack gets this,
+    /// not just the top one, so they can all lay themselves out again.
+    fn resize_event(&mut self, _world: &mut C, _ctx: &mut Context, _width: f32, _height: f32) {}
+
+    /// The window was moved.  Every scene on the stack gets this.
+    fn window_moved_event(&mut self, _world: &mut C, _ctx: &mut Context, _x: f32, _y: f32) {}
+
+    /// The window's contents need to be drawn again.  Every scene on
+    /// the stack gets this.
+    fn refresh_event(&mut self, _world: &mut C, _ctx: &mut Context) {}
+
+    /// The window's hidpi factor changed.  Every scene on the stack
+    /// gets this.
+    fn hidpi_factor_changed_event(&mut self, _world: &mut C, _ctx: &mut Context, _factor: f32) {}
+
+    /// The mouse cursor entered or left the window.
+    fn mouse_enter_or_leave_event(&mut self, _world: &mut C, _ctx: &mut Context, _entered: bool) {}
+
+    /// A file was dropped onto the window.
+    fn file_dropped_event(&mut self, _world: &mut C, _ctx: &mut Context, _path: path::PathBuf) {}
+
+    /// A file is being dragged over the window.
+    fn file_hovered_event(&mut self, _world: &mut C, _ctx: &mut Context, _path: path::PathBuf) {}
+
+    /// The file being dragged over the window was dragged away again.
+    fn file_hover_cancelled_event(&mut self, _world: &mut C, _ctx: &mut Context) {}
+
+    /// The application was suspended or resumed.  Every scene on the
+    /// stack gets this.
+    fn suspend_event(&mut self, _world: &mut C, _ctx: &mut Context, _suspended: bool) {}
+
+    /// An event sent with an `event::UserEventSender` has arrived.
+    fn user_event(&mut self, _world: &mut C, _ctx: &mut Context, _event: E) {}
+}
+
+/// A stack of `Scene`s sharing a world of type `C`, and taking user
+/// events of type `E`.  See the module docs for how it works.
+pub struct SceneStack<C, E = ()> {
+    /// The state shared by all the scenes.
+    pub world: C,
+    scenes: Vec<Box<dyn Scene<C, E>>>,
+}
+
+impl<C, E> fmt::Debug for SceneStack<C, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = self.scenes.iter().map(|s| s.name()).collect();
+        write!(f, "<SceneStack: {:?}>", names)
+    }
+}
+
+impl<C, E> SceneStack<C, E> {
+    /// Creates a new stack with the given world and first scene.
+    pub fn new<S>(world: C, scene: S) -> Self
+    where
+        S: Scene<C, E> + 'static,
+    {
+        SceneStack {
+            world,
+            scenes: vec![Box::new(scene)],
+        }
+    }
+
+    /// Puts a scene on top of the stack.
+    pub fn push(&mut self, scene: Box<dyn Scene<C, E>>) {
+        self.scenes.push(scene);
+    }
+
+    /// Removes the top scene from the stack and returns it.
+    pub fn pop(&mut self) -> Option<Box<dyn Scene<C, E>>> {
+        self.scenes.pop()
+    }
+
+    /// Returns the top scene, if there is one.
+    pub fn current(&self) -> Option<&dyn Scene<C, E>> {
+        self.scenes.last().map(|s| &**s)
+    }
+
+    /// Returns how many scenes are on the stack.
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Returns whether the stack has no scenes on it.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Makes the given transition, quitting the game if it leaves the
+    /// stack empty.
+    pub fn switch(&mut self, ctx: &mut Context, switch: SceneSwitch<C, E>) {
+        match switch {
+            SceneSwitch::None => (),
+            SceneSwitch::Push(scene) => self.push(scene),
+            SceneSwitch::Pop => {
+                let _ = self.pop();
+            }
+            SceneSwitch::Replace(scene) => {
+                let _ = self.pop();
+                self.push(scene);
+            }
+        }
+        if self.scenes.is_empty() {
+            debug!("Scene stack is empty, quitting...");
+            ctx.quit();
+        }
+    }
+}
+
+/// Calls a callback on the top scene, if there is one.
+macro_rules! forward {
+    ($stack:expr, $method:ident($($arg:expr),*)) => {
+        if let Some(scene) = $stack.scenes.last_mut() {
+            scene.$method(&mut $stack.world, $($arg),*);
+        }
+    };
+}
+
+/// Calls a callback on every scene, from the bottom of the stack up.
+macro_rules! forward_all {
+    ($stack:expr, $method:ident($($arg:expr),*)) => {
+        for scene in &mut $stack.scenes {
+            scene.$method(&mut $stack.world, $($arg),*);
+        }
+    };
+}
+
+impl<C, E> EventHandler<E> for SceneStack<C, E> {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let switch = match self.scenes.last_mut() {
+            Some(scene) => scene.update(&mut self.world, ctx)?,
+            None => return Ok(()),
+        };
+        self.switch(ctx, switch);
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let first_visible = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.draw_previous())
+            .unwrap_or(0);
+        for scene in &mut self.scenes[first_visible..] {
+            scene.draw(&mut self.world, ctx)?;
+        }
+        graphics::present(ctx)?;
+        timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        forward!(self, mouse_button_down_event(ctx, button, x, y));
+    }
+
+    fn mouse_button_up_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        forward!(self, mouse_button_up_event(ctx, button, x, y));
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, dx: f32, dy: f32) {
+        forward!(self, mouse_motion_event(ctx, x, y, dx, dy));
+    }
+
+    fn raw_mouse_motion_event(&mut self, ctx: &mut Context, dx: f32, dy: f32) {
+        forward!(self, raw_mouse_motion_event(ctx, dx, dy));
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, x: f32, y: f32) {
+        forward!(self, mouse_wheel_event(ctx, x, y));
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        keymods: KeyMods,
+        repeat: bool,
+    ) {
+        forward!(self, key_down_event(ctx, keycode, keymods, repeat));
+    }
+
+    fn key_up_event(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods) {
+        forward!(self, key_up_event(ctx, keycode, keymods));
+    }
+
+    fn scancode_down_event(
+        &mut self,
+        ctx: &mut Context,
+        scancode: ScanCode,
+        keymods: KeyMods,
+        repeat: bool,
+    ) {
+        forward!(self, scancode_down_event(ctx, scancode, keymods, repeat));
+    }
+
+    fn scancode_up_event(&mut self, ctx: &mut Context, scancode: ScanCode, keymods: KeyMods) {
+        forward!(self, scancode_up_event(ctx, scancode, keymods));
+    }
+
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) {
+        forward!(self, text_input_event(ctx, character));
+    }
+
+    fn controller_button_down_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) {
+        forward!(self, controller_button_down_event(ctx, btn, id));
+    }
+
+    fn controller_button_repeat_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) {
+        forward!(self, controller_button_repeat_event(ctx, btn, id));
+    }
+
+    fn controller_button_up_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) {
+        forward!(self, controller_button_up_event(ctx, btn, id));
+    }
+
+    fn controller_axis_event(&mut self, ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) {
+        forward!(self, controller_axis_event(ctx, axis, value, id));
+    }
+
+    fn controller_connected_event(&mut self, ctx: &mut Context, id: GamepadId) {
+        forward!(self, controller_connected_event(ctx, id));
+    }
+
+    fn controller_disconnected_event(&mut self, ctx: &mut Context, id: GamepadId) {
+        forward!(self, controller_disconnected_event(ctx, id));
+    }
+
+    fn touch_event(&mut self, ctx: &mut Context, phase: TouchPhase, id: u64, x: f32, y: f32) {
+        forward!(self, touch_event(ctx, phase, id, x, y));
+    }
+
+    fn focus_event(&mut self, ctx: &mut Context, gained: bool) {
+        forward_all!(self, focus_event(ctx, gained));
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        forward_all!(self, resize_event(ctx, width, height));
+    }
+
+    fn window_moved_event(&mut self, ctx: &mut Context, x: f32, y: f32) {
+        forward_all!(self, window_moved_event(ctx, x, y));
+    }
+
+    fn refresh_event(&mut self, ctx: &mut Context) {
+        forward_all!(self, refresh_event(ctx));
+    }
+
+    fn hidpi_factor_changed_event(&mut self, ctx: &mut Context, factor: f32) {
+        forward_all!(self, hidpi_factor_changed_event(ctx, factor));
+    }
+
+    fn mouse_enter_or_leave_event(&mut self, ctx: &mut Context, entered: bool) {
+        forward!(self, mouse_enter_or_leave_event(ctx, entered));
+    }
+
+    fn file_dropped_event(&mut self, ctx: &mut Context, path: path::PathBuf) {
+        forward!(self, file_dropped_event(ctx, path));
+    }
+
+    fn file_hovered_event(&mut self, ctx: &mut Context, path: path::PathBuf) {
+        forward!(self, file_hovered_event(ctx, path));
+    }
+
+    fn file_hover_cancelled_event(&mut self, ctx: &mut Context) {
+        forward!(self, file_hover_cancelled_event(ctx));
+    }
+
+    fn suspend_event(&mut self, ctx: &mut Context, suspended: bool) {
+        forward_all!(self, suspend_event(ctx, suspended));
+    }
+
+    fn user_event(&mut self, ctx: &mut Context, event: E) {
+        forward!(self, user_event(ctx, event));
+    }
+}
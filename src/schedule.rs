@@ -0,0 +1,329 @@
+//! Running code later: after a delay, at regular intervals, or a
+//! little bit every frame to animate a `tween::Tween`.
+//!
+//! A `Scheduler<S>` holds tasks that act on your game state of type
+//! `S`.  Keep it alongside that state rather than inside it, and call
+//! `update()` once a frame to run whatever is due:
+//!
+//! ```rust,ignore
+//! struct MainState {
+//!     world: World,
+//!     scheduler: Scheduler<World>,
+//! }
+//!
+//! // When the player picks up a power-up:
+//! self.world.player.speed = 2.0;
+//! let _ = self.scheduler.after(Duration::from_secs(10), |world, _ctx| {
+//!     world.player.speed = 1.0;
+//! });
+//!
+//! // In update():
+//! self.scheduler.update(ctx, &mut self.world);
+//! ```
+//!
+//! Every task belongs to a group, `DEFAULT_GROUP` unless it's moved
+//! with `set_task_group()`.  A group can follow one of the named
+//! clocks in `timer` with `set_group_clock()`, so for instance
+//! gameplay timers slow down and stop along with a `"game"` clock
+//! while the pause menu's animations keep going by the real one.
+//! Groups can also be paused and scaled on their own.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time;
+
+use context::Context;
+use timer;
+use tween::{Tween, Tweenable};
+
+/// The group tasks are put in when they're created.
+pub const DEFAULT_GROUP: &str = "default";
+
+/// Identifies a task in a `Scheduler`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(u64);
+
+#[derive(Debug, Clone, PartialEq)]
+struct GroupSettings {
+    paused: bool,
+    time_scale: f64,
+    /// The named clock the group's time comes from, if not the real
+    /// clock.
+    clock: Option<String>,
+}
+
+impl Default for GroupSettings {
+    fn default() -> Self {
+        GroupSettings {
+            paused: false,
+            time_scale: 1.0,
+            clock: None,
+        }
+    }
+}
+
+type Action<S> = Box<dyn FnMut(&mut S, &mut Context)>;
+
+enum Job<S> {
+    /// Runs once, when the wait is over.
+    Once(Action<S>),
+    /// Runs every time the interval passes.
+    Repeat(Action<S>, f64),
+    /// Runs every update with how much time has passed, until it
+    /// returns `true` to say it's finished.
+    Animate(Box<dyn FnMut(&mut S, f64) -> bool>),
+}
+
+struct Task<S> {
+    id: TaskId,
+    group: String,
+    /// Seconds until the task next runs.
+    wait: f64,
+    job: Job<S>,
+}
+
+impl<S> Task<S> {
+    /// Moves the task along by `dt` seconds, running it if it's due.
+    /// Returns whether it should be kept around.
+    fn advance(&mut self, state: &mut S, ctx: &mut Context, dt: f64) -> bool {
+        match self.job {
+            Job::Once(ref mut action) => {
+                self.wait -= dt;
+                if self.wait <= 0.0 {
+                    action(state, ctx);
+                    false
+                } else {
+                    true
+                }
+            }
+            Job::Repeat(ref mut action, interval) => {
+                self.wait -= dt;
+                while self.wait <= 0.0 {
+                    action(state, ctx);
+                    if interval <= 0.0 {
+                        self.wait = 0.0;
+                        break;
+                    }
+                    self.wait += interval;
+                }
+                true
+            }
+            Job::Animate(ref mut animate) => !animate(state, dt),
+        }
+    }
+}
+
+/// Runs tasks against a game state of type `S`.  See the module
+/// docs for an example.
+pub struct Scheduler<S> {
+    tasks: Vec<Task<S>>,
+    groups: HashMap<String, GroupSettings>,
+    next_id: u64,
+}
+
+impl<S> fmt::Debug for Scheduler<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Scheduler: {} tasks>", self.tasks.len())
+    }
+}
+
+impl<S> Default for Scheduler<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Scheduler<S> {
+    /// Creates a new, empty scheduler.
+    pub fn new() -> Self {
+        Scheduler {
+            tasks: Vec::new(),
+            groups: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    fn add(&mut self, wait: time::Duration, job: Job<S>) -> TaskId {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        self.tasks.push(Task {
+            id,
+            group: DEFAULT_GROUP.to_owned(),
+            wait: timer::duration_to_f64(wait),
+            job,
+        });
+        id
+    }
+
+    /// Runs `action` once, after `delay` has passed.
+    pub fn after<F>(&mut self, delay: time::Duration, action: F) -> TaskId
+    where
+        F: FnOnce(&mut S, &mut Context) + 'static,
+    {
+        let mut action = Some(action);
+        let job = Job::Once(Box::new(move |state, ctx| {
+            if let Some(action) = action.take() {
+                action(state, ctx);
+            }
+        }));
+        self.add(delay, job)
+    }
+
+    /// Runs `action` every time `interval` passes, until the task is
+    /// cancelled.  If a frame takes longer than the interval, the
+    /// action is run as many times as it should have been.
+    pub fn every<F>(&mut self, interval: time::Duration, action: F) -> TaskId
+    where
+        F: FnMut(&mut S, &mut Context) + 'static,
+    {
+        let job = Job::Repeat(Box::new(action), timer::duration_to_f64(interval));
+        self.add(interval, job)
+    }
+
+    /// Plays `tween`, calling `apply` with its value every update
+    /// until it's finished, so you can store the value in your game
+    /// state.
+    pub fn tween<T, F>(&mut self, mut tween: Tween<T>, mut apply: F) -> TaskId
+    where
+        T: Tweenable + 'static,
+        F: FnMut(&mut S, T) + 'static,
+    {
+        let job = Job::Animate(Box::new(move |state, dt| {
+            tween.advance_secs(dt);
+            apply(state, tween.value());
+            tween.is_finished()
+        }));
+        self.add(time::Duration::from_secs(0), job)
+    }
+
+    /// Cancels a task, returning whether it was still scheduled.
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        let before = self.tasks.len();
+        self.tasks.retain(|task| task.id != id);
+        self.tasks.len() != before
+    }
+
+    /// Cancels every task.
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    /// Returns whether the given task is still scheduled.
+    pub fn is_scheduled(&self, id: TaskId) -> bool {
+        self.tasks.iter().any(|task| task.id == id)
+    }
+
+    /// Returns how many tasks are scheduled.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Returns whether there are no tasks scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Moves a task to the given group.  Groups don't need to be
+    /// created first.
+    pub fn set_task_group(&mut self, id: TaskId, group: &str) {
+        if let Some(task) = self.tasks.iter_mut().find(|task| task.id == id) {
+            task.group = group.to_owned();
+        }
+    }
+
+    fn group_mut(&mut self, group: &str) -> &mut GroupSettings {
+        self.groups
+            .entry(group.to_owned())
+            .or_insert_with(GroupSettings::default)
+    }
+
+    /// Pauses or unpauses every task in the given group.
+    pub fn set_paused(&mut self, group: &str, paused: bool) {
+        self.group_mut(group).paused = paused;
+    }
+
+    /// Returns whether the given group is paused.
+    pub fn is_paused(&self, group: &str) -> bool {
+        self.groups.get(group).map(|g| g.paused).unwrap_or(false)
+    }
+
+    /// Sets how fast time passes for the given group: 2.0 makes its
+    /// tasks run twice as soon, 0.5 half as soon.
+    pub fn set_time_scale(&mut self, group: &str, time_scale: f64) {
+        self.group_mut(group).time_scale = time_scale.max(0.0);
+    }
+
+    /// Returns the time scale of the given group.
+    pub fn get_time_scale(&self, group: &str) -> f64 {
+        self.groups.get(group).map(|g| g.time_scale).unwrap_or(1.0)
+    }
+
+    /// Makes the given group's time come from the named clock, as in
+    /// `timer::get_clock_delta()`, so its tasks slow down, speed up
+    /// and pause along with that clock.  `None` goes back to the real
+    /// clock.  The group's own pausing and time scale still apply on
+    /// top of the clock's.
+    pub fn set_group_clock(&mut self, group: &str, clock: Option<&str>) {
+        self.group_mut(group).clock = clock.map(|clock| clock.to_owned());
+    }
+
+    /// Returns the clock the given group follows, or `None` for the
+    /// real clock.
+    pub fn get_group_clock(&self, group: &str) -> Option<&str> {
+        self.groups
+            .get(group)
+            .and_then(|g| g.clock.as_ref())
+            .map(|clock| clock.as_str())
+    }
+
+    /// Runs whatever tasks are due, moving each group's time along by
+    /// how long the last frame took on its clock, as given by
+    /// `timer::get_delta()` or `timer::get_clock_delta()`.  Call this
+    /// once per frame.
+    pub fn update(&mut self, ctx: &mut Context, state: &mut S) {
+        let real_dt = timer::get_delta(ctx);
+        self.run_tasks(ctx, state, |ctx, clock| match clock {
+            Some(clock) => timer::get_clock_delta(ctx, clock),
+            None => real_dt,
+        });
+    }
+
+    /// Runs whatever tasks are due, moving time along by `dt`.  Use
+    /// this instead of `update()` to drive the scheduler from a fixed
+    /// timestep.  Groups that follow a clock get `dt` scaled by that
+    /// clock's time scale, and nothing while it's paused.
+    pub fn advance(&mut self, ctx: &mut Context, state: &mut S, dt: time::Duration) {
+        self.run_tasks(ctx, state, |ctx, clock| match clock {
+            Some(clock) if timer::is_clock_paused(ctx, clock) => time::Duration::from_secs(0),
+            Some(clock) => timer::f64_to_duration(
+                timer::duration_to_f64(dt) * timer::get_clock_time_scale(ctx, clock),
+            ),
+            None => dt,
+        });
+    }
+
+    /// Moves every task along by however much time `group_dt` says
+    /// passed on its group's clock.
+    fn run_tasks<F>(&mut self, ctx: &mut Context, state: &mut S, group_dt: F)
+    where
+        F: Fn(&Context, Option<&str>) -> time::Duration,
+    {
+        let mut i = 0;
+        while i < self.tasks.len() {
+            let settings = self
+                .groups
+                .get(&self.tasks[i].group)
+                .cloned()
+                .unwrap_or_default();
+            let clock = settings.clock.as_ref().map(|clock| clock.as_str());
+            let dt = timer::duration_to_f64(group_dt(ctx, clock));
+            let keep =
+                settings.paused || self.tasks[i].advance(state, ctx, dt * settings.time_scale);
+            if keep {
+                i += 1;
+            } else {
+                let _ = self.tasks.remove(i);
+            }
+        }
+    }
+}
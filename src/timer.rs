@@ -7,15 +7,43 @@
 //! so it has a chance to breathe before continuing with your game.
 //! This should prevent it from using 100% CPU unless it really needs
 //! to.  Enabling vsync by setting `vsync` in your `Conf` object is
-//! generally the best way to cap your displayed framerate.
+//! generally the best way to cap your displayed framerate.  If you
+//! can't rely on vsync, setting `conf::RunSetup::fps_limit` makes
+//! `event::run()` sleep away the rest of each frame instead, and
+//! `get_frame_budget()` and `get_frame_sleep_time()` tell you how
+//! well that is going.
+//!
+//! If you want `update()` to run at a fixed rate, you can either call
+//! `check_update_time()` in a loop yourself, or set
+//! `conf::RunSetup::update_mode` to `UpdateMode::Fixed` and let
+//! `event::run()` do it for you.
+//!
+//! Besides the real clock, you can make as many named clocks as you
+//! like, each with its own time scale and the ability to be paused.
+//! For example, gameplay can go by a `"game"` clock that is slowed
+//! down for slow motion and paused by the pause menu, while the menu's
+//! animations go by the real clock.  See `get_clock_delta()` and
+//! friends.
+//!
+//! To find out where the time goes, `get_frame_stats()` and
+//! `get_frame_time_histogram()` summarize recent frame times,
+//! `profile_scope()` times sections of your code, and
+//! `draw_profiler_overlay()` puts all of that on the screen.
 //!
 //! For a more detailed tutorial in how to handle frame timings in games,
 //! see <http://gafferongames.com/game-physics/fix-your-timestep/>
 
+use conf::UpdateMode;
 use context::Context;
+use graphics;
+use mint;
+use GameResult;
 
+use std::cell::RefCell;
 use std::cmp;
+use std::collections::HashMap;
 use std::f64;
+use std::mem;
 use std::thread;
 use std::time;
 
@@ -46,7 +74,7 @@ where
         v.resize(size, init_val);
         LogBuffer {
             head: 0,
-            size,
+            size: 0,
             contents: v,
         }
     }
@@ -59,21 +87,79 @@ where
         self.size = cmp::min(self.size + 1, self.contents.len());
     }
 
-    /// Returns a slice pointing at the contents of the buffer.
-    /// They are in *no particular order*, and if not all the
-    /// slots are filled, the empty slots will be present but
-    /// contain the initial value given to `new()`
-    ///
-    /// We're only using this to log FPS for a short time,
-    /// so we don't care for the second or so when it's inaccurate.
-    fn contents(&self) -> &[T] {
-        &self.contents
+    /// Returns a slice of the items that have been pushed into the
+    /// buffer and not yet overwritten, in *no particular order*.
+    fn samples(&self) -> &[T] {
+        if self.size == self.contents.len() {
+            &self.contents
+        } else {
+            // Pushing starts at index 1, see `push()`.
+            &self.contents[1..=self.size]
+        }
+    }
+
+    /// Returns the items in the buffer from oldest to newest.
+    fn ordered(&self) -> Vec<T> {
+        let len = self.contents.len();
+        (0..self.size)
+            .map(|i| self.contents[(self.head + len - self.size + 1 + i) % len])
+            .collect()
     }
 
     /// Returns the most recent value in the buffer.
     fn latest(&self) -> T {
         self.contents[self.head]
     }
+
+    /// Overwrites the most recent value in the buffer.
+    fn replace_latest(&mut self, item: T) {
+        self.contents[self.head] = item;
+    }
+}
+
+/// A named clock, which runs at its own speed and can be paused.
+#[derive(Debug, Clone)]
+struct Clock {
+    time_scale: f64,
+    paused: bool,
+    /// How much time passed on this clock during the last frame.
+    delta: time::Duration,
+    /// How much time has passed on this clock in total.
+    elapsed: time::Duration,
+    residual_update_dt: time::Duration,
+}
+
+impl Clock {
+    fn new(elapsed: time::Duration, delta: time::Duration) -> Self {
+        Clock {
+            time_scale: 1.0,
+            paused: false,
+            delta,
+            elapsed,
+            residual_update_dt: time::Duration::from_secs(0),
+        }
+    }
+
+    /// Moves the clock along by a frame that really took `frame_dt`.
+    fn advance(&mut self, frame_dt: time::Duration) {
+        self.delta = if self.paused {
+            time::Duration::from_secs(0)
+        } else {
+            f64_to_duration(duration_to_f64(frame_dt) * self.time_scale)
+        };
+        self.elapsed += self.delta;
+        self.residual_update_dt += self.delta;
+    }
+
+    /// Undoes the last `advance()`.
+    fn rewind(&mut self) {
+        let zero = time::Duration::from_secs(0);
+        self.elapsed = self.elapsed.checked_sub(self.delta).unwrap_or(zero);
+        self.residual_update_dt = self
+            .residual_update_dt
+            .checked_sub(self.delta)
+            .unwrap_or(zero);
+    }
 }
 
 /// A structure that contains our time-tracking state.
@@ -84,11 +170,25 @@ pub struct TimeContext {
     frame_durations: LogBuffer<time::Duration>,
     residual_update_dt: time::Duration,
     frame_count: usize,
+    frame_budget: time::Duration,
+    frame_sleep_time: time::Duration,
+    /// The total of all the frame durations so far.
+    elapsed: time::Duration,
+    clocks: HashMap<String, Clock>,
+    /// The clock `UpdateMode::Fixed` goes by, if not the real one.
+    update_clock: Option<String>,
+    /// The `profile_scope()` timings of the last frame.
+    last_profile: Vec<ScopeTiming>,
 }
 
 // How many frames we log update times for.
 const TIME_LOG_FRAMES: usize = 200;
 
+// How many milliseconds before the end of a frame's budget we stop
+// trusting `thread::sleep()` and spin instead.  Sleeps on most OS's
+// can overshoot by a millisecond or so; this is a bit more than that.
+const FRAME_SPIN_MARGIN_MS: u64 = 2;
+
 impl TimeContext {
     /// Creates a new `TimeContext` and initializes the start to this instant.
     pub fn new() -> TimeContext {
@@ -98,6 +198,12 @@ impl TimeContext {
             frame_durations: LogBuffer::new(TIME_LOG_FRAMES, time::Duration::new(0, 0)),
             residual_update_dt: time::Duration::from_secs(0),
             frame_count: 0,
+            frame_budget: time::Duration::from_secs(0),
+            frame_sleep_time: time::Duration::from_secs(0),
+            elapsed: time::Duration::from_secs(0),
+            clocks: HashMap::new(),
+            update_clock: None,
+            last_profile: Vec::new(),
         }
     }
 
@@ -115,6 +221,101 @@ impl TimeContext {
         self.frame_count += 1;
 
         self.residual_update_dt += time_since_last;
+        self.elapsed += time_since_last;
+        for clock in self.clocks.values_mut() {
+            clock.advance(time_since_last);
+        }
+        self.last_profile =
+            PROFILE.with(|profile| mem::replace(&mut *profile.borrow_mut(), Vec::new()));
+    }
+
+    /// Pretends the frame just started by `tick()` took `delta`
+    /// instead of however long it really took.  Used to replay
+    /// recorded input with the timing it was recorded with.
+    pub(crate) fn override_delta(&mut self, delta: time::Duration) {
+        let measured = self.frame_durations.latest();
+        self.residual_update_dt = self.residual_update_dt
+            .checked_sub(measured)
+            .unwrap_or_else(|| time::Duration::from_secs(0)) + delta;
+        self.elapsed = self.elapsed
+            .checked_sub(measured)
+            .unwrap_or_else(|| time::Duration::from_secs(0)) + delta;
+        self.frame_durations.replace_latest(delta);
+        for clock in self.clocks.values_mut() {
+            clock.rewind();
+            clock.advance(delta);
+        }
+    }
+
+    /// Returns the named clock, creating it if it doesn't exist yet.
+    /// New clocks start out as if they'd been running at normal speed
+    /// since the game started.
+    fn clock_mut(&mut self, name: &str) -> &mut Clock {
+        let elapsed = self.elapsed;
+        let delta = self.frame_durations.latest();
+        self.clocks
+            .entry(name.to_owned())
+            .or_insert_with(|| Clock::new(elapsed, delta))
+    }
+
+    /// The residual update time `UpdateMode::Fixed` goes by: the
+    /// update clock's if one is set, or the real clock's.
+    fn update_residual(&self) -> time::Duration {
+        self.update_clock
+            .as_ref()
+            .and_then(|name| self.clocks.get(name))
+            .map(|clock| clock.residual_update_dt)
+            .unwrap_or(self.residual_update_dt)
+    }
+
+    fn update_residual_mut(&mut self) -> &mut time::Duration {
+        match self.update_clock.clone() {
+            Some(name) => &mut self.clock_mut(&name).residual_update_dt,
+            None => &mut self.residual_update_dt,
+        }
+    }
+
+    /// Throws away whole update steps of `target_fps` left over in
+    /// the residual update time, keeping only the fractional part.
+    /// Used by `event::run()` to keep from falling further and further
+    /// behind when the game can't keep up with its fixed update rate.
+    pub(crate) fn discard_residual_update_steps(&mut self, target_fps: u32) {
+        let target_dt = duration_to_f64(fps_as_duration(target_fps));
+        let residual = self.update_residual_mut();
+        *residual = f64_to_duration(duration_to_f64(*residual) % target_dt);
+    }
+
+    /// Waits until the current frame has taken up the whole time
+    /// budget of a frame at `fps_limit` frames per second, measured
+    /// from the last `tick()`.  Sleeps for most of the wait and
+    /// yields in a loop for the last little bit, since sleeping
+    /// tends to overshoot.  An `fps_limit` of 0 means no limit
+    /// and returns immediately.
+    ///
+    /// It's usually not necessary to call this function yourself,
+    /// `event::run()` will do it for you if `conf::RunSetup::fps_limit`
+    /// is set.
+    pub fn sleep_until_frame_end(&mut self, fps_limit: u32) {
+        if fps_limit == 0 {
+            self.frame_budget = time::Duration::from_secs(0);
+            self.frame_sleep_time = time::Duration::from_secs(0);
+            return;
+        }
+        let budget = fps_as_duration(fps_limit);
+        let spin_margin = time::Duration::from_millis(FRAME_SPIN_MARGIN_MS);
+        let deadline = self.last_instant + budget;
+        let sleep_start = time::Instant::now();
+        if sleep_start < deadline {
+            let remaining = deadline - sleep_start;
+            if remaining > spin_margin {
+                thread::sleep(remaining - spin_margin);
+            }
+            while time::Instant::now() < deadline {
+                thread::yield_now();
+            }
+        }
+        self.frame_budget = budget;
+        self.frame_sleep_time = time::Instant::now() - sleep_start;
     }
 }
 
@@ -137,10 +338,246 @@ pub fn get_average_delta(ctx: &Context) -> time::Duration {
     let tc = &ctx.timer_context;
     let init = time::Duration::new(0, 0);
     let sum = tc.frame_durations
-        .contents()
+        .samples()
         .iter()
         .fold(init, |d1, d2| d1 + *d2);
-    sum / (tc.frame_durations.size as u32)
+    sum / cmp::max(tc.frame_durations.size as u32, 1)
+}
+
+/// A summary of recent frame times, from `get_frame_stats()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct FrameStats {
+    /// The shortest frame.
+    pub min: time::Duration,
+    /// The longest frame.
+    pub max: time::Duration,
+    /// The average frame time, same as `get_average_delta()`.
+    pub mean: time::Duration,
+    /// The median frame time: half of frames were quicker.
+    pub median: time::Duration,
+    /// 95% of frames were at least this quick.
+    pub p95: time::Duration,
+    /// 99% of frames were at least this quick.
+    pub p99: time::Duration,
+}
+
+/// Returns statistics about the last 200 frame times.  The
+/// percentiles show hitches that the average hides: a game that
+/// usually runs at 60 FPS but stutters every second has a fine
+/// `mean` and a terrible `p99`.
+pub fn get_frame_stats(ctx: &Context) -> FrameStats {
+    let mut samples = ctx.timer_context.frame_durations.samples().to_vec();
+    if samples.is_empty() {
+        return FrameStats::default();
+    }
+    samples.sort();
+    // Nearest-rank percentiles.
+    let percentile = |p: usize| samples[(samples.len() * p + 99) / 100 - 1];
+    FrameStats {
+        min: samples[0],
+        max: samples[samples.len() - 1],
+        mean: get_average_delta(ctx),
+        median: percentile(50),
+        p95: percentile(95),
+        p99: percentile(99),
+    }
+}
+
+/// Sorts the last 200 frame times into `bucket_count` buckets each
+/// `bucket_width` wide, returning how many frames fell into each.
+/// Frames too long for the last bucket are counted in it anyway.
+pub fn get_frame_time_histogram(
+    ctx: &Context,
+    bucket_width: time::Duration,
+    bucket_count: usize,
+) -> Vec<usize> {
+    let mut buckets = vec![0; bucket_count];
+    let width = duration_to_f64(bucket_width);
+    if bucket_count == 0 || width <= 0.0 {
+        return buckets;
+    }
+    for sample in ctx.timer_context.frame_durations.samples() {
+        let bucket = (duration_to_f64(*sample) / width) as usize;
+        buckets[cmp::min(bucket, bucket_count - 1)] += 1;
+    }
+    buckets
+}
+
+/// How long a `profile_scope()` took in total during a frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScopeTiming {
+    /// The name given to `profile_scope()`.
+    pub name: &'static str,
+    /// The total time spent in scopes with this name.
+    pub total: time::Duration,
+    /// How many times a scope with this name was entered.
+    pub calls: u32,
+}
+
+thread_local! {
+    /// The scope timings of the current frame so far.
+    static PROFILE: RefCell<Vec<ScopeTiming>> = RefCell::new(Vec::new());
+}
+
+/// Times a section of code, from when this is called until the
+/// returned `ProfileScope` is dropped.  Scopes with the same name are
+/// added together over each frame, and `get_profile()` returns the
+/// totals for the last frame:
+///
+/// ```rust,ignore
+/// fn update(&mut self, ctx: &mut Context) -> GameResult {
+///     {
+///         let _scope = timer::profile_scope("physics");
+///         self.world.step();
+///     }
+///     let _scope = timer::profile_scope("ai");
+///     self.think();
+///     Ok(())
+/// }
+/// ```
+///
+/// Only scopes on the main thread count, since that's the one that
+/// runs the game loop.
+pub fn profile_scope(name: &'static str) -> ProfileScope {
+    ProfileScope {
+        name,
+        start: time::Instant::now(),
+    }
+}
+
+/// Times a section of code until it's dropped; see `profile_scope()`.
+#[must_use = "the scope is only timed until this is dropped"]
+#[derive(Debug)]
+pub struct ProfileScope {
+    name: &'static str,
+    start: time::Instant,
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        let name = self.name;
+        PROFILE.with(|profile| {
+            let mut profile = profile.borrow_mut();
+            if let Some(timing) = profile.iter_mut().find(|t| t.name == name) {
+                timing.total += elapsed;
+                timing.calls += 1;
+                return;
+            }
+            profile.push(ScopeTiming {
+                name,
+                total: elapsed,
+                calls: 1,
+            });
+        });
+    }
+}
+
+/// Returns how long each `profile_scope()` took during the last
+/// frame, in the order they were first entered.
+pub fn get_profile(ctx: &Context) -> &[ScopeTiming] {
+    &ctx.timer_context.last_profile
+}
+
+/// Height of the frame time graph in `draw_profiler_overlay()`.
+const OVERLAY_GRAPH_HEIGHT: f32 = 60.0;
+/// The frame time at the top of the overlay graph, in seconds.
+const OVERLAY_GRAPH_MAX: f64 = 1.0 / 20.0;
+
+/// Draws a graph of recent frame times, with the 60 FPS mark in red,
+/// and the frame statistics, the last frame's `RenderStats` and its
+/// `profile_scope()` timings written below it, with the top left
+/// corner at `dest`.  Call it at the end of your `draw()` to see
+/// where hitches happen without reaching for an external profiler.
+pub fn draw_profiler_overlay<P>(ctx: &mut Context, dest: P) -> GameResult
+where
+    P: Into<mint::Point2<f32>>,
+{
+    use graphics::{DrawMode, DrawParam, MeshBuilder, Rect, Text};
+
+    let dest: mint::Point2<f32> = dest.into();
+    let frames = ctx.timer_context.frame_durations.ordered();
+    let width = TIME_LOG_FRAMES as f32;
+    let to_height = |d: time::Duration| {
+        let fraction = (duration_to_f64(d) / OVERLAY_GRAPH_MAX).min(1.0) as f32;
+        (fraction * OVERLAY_GRAPH_HEIGHT).max(1.0)
+    };
+
+    let mut text = Text::new(format!("FPS: {:.1}\n", get_fps(ctx)));
+    let stats = get_frame_stats(ctx);
+    let ms = |d: time::Duration| duration_to_f64(d) * 1000.0;
+    let _ = text.add(format!(
+        "frame: {:.2} ms, p95 {:.2} ms, max {:.2} ms\n",
+        ms(stats.mean),
+        ms(stats.p95),
+        ms(stats.max)
+    ));
+    let render = graphics::get_last_render_stats(ctx);
+    let _ = text.add(format!(
+        "draws: {}, binds: {}, switches: {}\n",
+        render.draw_calls, render.texture_binds, render.pipeline_switches
+    ));
+    if let Some(gpu) = graphics::get_gpu_frame_time(ctx) {
+        let _ = text.add(format!("gpu: {:.2} ms\n", ms(gpu)));
+    }
+    for timing in get_profile(ctx) {
+        let _ = text.add(format!(
+            "{}: {:.2} ms ({}x)\n",
+            timing.name,
+            ms(timing.total),
+            timing.calls
+        ));
+    }
+
+    let background = MeshBuilder::new()
+        .rectangle(
+            DrawMode::Fill,
+            Rect::new(0.0, 0.0, width, OVERLAY_GRAPH_HEIGHT),
+        )
+        .build(ctx)?;
+    graphics::draw(
+        ctx,
+        &background,
+        DrawParam::new()
+            .dest(dest)
+            .color(graphics::Color::new(0.0, 0.0, 0.0, 0.6)),
+    )?;
+
+    if !frames.is_empty() {
+        let mut bars = MeshBuilder::new();
+        for (i, frame) in frames.iter().enumerate() {
+            let height = to_height(*frame);
+            let _ = bars.rectangle(
+                DrawMode::Fill,
+                Rect::new(i as f32, OVERLAY_GRAPH_HEIGHT - height, 1.0, height),
+            );
+        }
+        graphics::draw(
+            ctx,
+            &bars.build(ctx)?,
+            DrawParam::new()
+                .dest(dest)
+                .color(graphics::Color::new(0.2, 0.9, 0.2, 1.0)),
+        )?;
+    }
+
+    let budget_y = OVERLAY_GRAPH_HEIGHT - to_height(fps_as_duration(60));
+    let budget = MeshBuilder::new()
+        .line(&[[0.0, budget_y], [width, budget_y]], 1.0)
+        .build(ctx)?;
+    graphics::draw(
+        ctx,
+        &budget,
+        DrawParam::new()
+            .dest(dest)
+            .color(graphics::Color::new(0.9, 0.2, 0.2, 1.0)),
+    )?;
+
+    let text_dest = mint::Point2 {
+        x: dest.x,
+        y: dest.y + OVERLAY_GRAPH_HEIGHT + 4.0,
+    };
+    graphics::draw(ctx, &text, DrawParam::new().dest(text_dest))
 }
 
 /// A convenience function to convert a Rust `Duration` type
@@ -209,12 +646,27 @@ pub fn get_time_since_start(ctx: &Context) -> time::Duration {
 ///     Ok(())
 /// }
 /// ```
+///
+/// Don't use this if `event::run()` is already doing fixed updates
+/// for you with `UpdateMode::Fixed` by the real clock; the two share
+/// the same residual time and will fight over it.
 pub fn check_update_time(ctx: &mut Context, target_fps: u32) -> bool {
-    let timedata = &mut ctx.timer_context;
+    take_update_step(&mut ctx.timer_context.residual_update_dt, target_fps)
+}
 
+/// Like `check_update_time()`, but going by the clock set with
+/// `set_update_clock()`.  Used by `event::run()` for
+/// `UpdateMode::Fixed`.
+pub(crate) fn check_fixed_update_time(ctx: &mut Context, target_fps: u32) -> bool {
+    take_update_step(ctx.timer_context.update_residual_mut(), target_fps)
+}
+
+/// Takes one update step of `target_fps` out of `residual`, if
+/// there's a whole one there.
+fn take_update_step(residual: &mut time::Duration, target_fps: u32) -> bool {
     let target_dt = fps_as_duration(target_fps);
-    if timedata.residual_update_dt > target_dt {
-        timedata.residual_update_dt -= target_dt;
+    if *residual > target_dt {
+        *residual -= target_dt;
         true
     } else {
         false
@@ -236,6 +688,130 @@ pub fn get_remaining_update_time(ctx: &mut Context) -> time::Duration {
     ctx.timer_context.residual_update_dt
 }
 
+/// Returns how far the game is between the last fixed update and
+/// the next one, as a fraction from 0 to 1.  Meant to be called in
+/// your `draw()` callback to interpolate between the previous and
+/// current physics states when `event::run()` is using
+/// `UpdateMode::Fixed`.
+///
+/// With `UpdateMode::Variable` every frame gets exactly one update,
+/// so this always returns 1.0.
+pub fn get_update_alpha(ctx: &Context) -> f64 {
+    match ctx.conf.run_setup.update_mode {
+        UpdateMode::Variable => 1.0,
+        UpdateMode::Fixed {
+            updates_per_second, ..
+        } => {
+            let target_dt = duration_to_f64(fps_as_duration(updates_per_second));
+            let residual = duration_to_f64(ctx.timer_context.update_residual());
+            (residual / target_dt).min(1.0)
+        }
+    }
+}
+
+/// Returns how long each frame is allowed to take under the
+/// current `conf::RunSetup::fps_limit`, or zero if there is
+/// no limit.
+pub fn get_frame_budget(ctx: &Context) -> time::Duration {
+    ctx.timer_context.frame_budget
+}
+
+/// Returns how much of the last frame's budget was spent
+/// sleeping (or spinning) to honor `conf::RunSetup::fps_limit`.
+/// The rest of `get_frame_budget()` went to actually running
+/// your game; if this is near zero, you're about to start
+/// missing frames.
+pub fn get_frame_sleep_time(ctx: &Context) -> time::Duration {
+    ctx.timer_context.frame_sleep_time
+}
+
+/// Sets how fast the named clock runs compared to the real clock:
+/// 0.5 for half speed, 2.0 for double.  Clocks are created the
+/// first time they're used, running at normal speed.
+///
+/// The new speed takes effect from the next frame on.
+pub fn set_clock_time_scale(ctx: &mut Context, clock: &str, time_scale: f64) {
+    ctx.timer_context.clock_mut(clock).time_scale = time_scale.max(0.0);
+}
+
+/// Returns how fast the named clock runs compared to the real clock.
+pub fn get_clock_time_scale(ctx: &Context, clock: &str) -> f64 {
+    ctx.timer_context
+        .clocks
+        .get(clock)
+        .map(|c| c.time_scale)
+        .unwrap_or(1.0)
+}
+
+/// Pauses or unpauses the named clock.  While it's paused, its delta
+/// is zero and `check_clock_update_time()` never returns `true`.
+///
+/// This takes effect from the next frame on.
+pub fn set_clock_paused(ctx: &mut Context, clock: &str, paused: bool) {
+    ctx.timer_context.clock_mut(clock).paused = paused;
+}
+
+/// Returns whether the named clock is paused.
+pub fn is_clock_paused(ctx: &Context, clock: &str) -> bool {
+    ctx.timer_context
+        .clocks
+        .get(clock)
+        .map(|c| c.paused)
+        .unwrap_or(false)
+}
+
+/// Like `get_delta()`, but for the named clock: how much time passed
+/// on it during the last frame, after its time scale and pausing.
+pub fn get_clock_delta(ctx: &Context, clock: &str) -> time::Duration {
+    ctx.timer_context
+        .clocks
+        .get(clock)
+        .map(|c| c.delta)
+        .unwrap_or_else(|| get_delta(ctx))
+}
+
+/// Returns how much time has passed on the named clock since the
+/// game started.
+pub fn get_clock_time(ctx: &Context, clock: &str) -> time::Duration {
+    let tc = &ctx.timer_context;
+    tc.clocks
+        .get(clock)
+        .map(|c| c.elapsed)
+        .unwrap_or(tc.elapsed)
+}
+
+/// Like `check_update_time()`, but going by the named clock, so fixed
+/// updates slow down, speed up and pause along with it.  Each clock
+/// keeps its own residual time, separate from the real clock's.
+pub fn check_clock_update_time(ctx: &mut Context, clock: &str, target_fps: u32) -> bool {
+    let clock = ctx.timer_context.clock_mut(clock);
+    take_update_step(&mut clock.residual_update_dt, target_fps)
+}
+
+/// Makes `event::run()` time its `UpdateMode::Fixed` updates by the
+/// named clock instead of the real one, so they slow down, speed up
+/// and pause along with it; `None` goes back to the real clock.
+/// `get_update_alpha()` follows the same clock.
+///
+/// Don't also call `check_clock_update_time()` on that clock
+/// yourself; the two would fight over its residual time.
+pub fn set_update_clock(ctx: &mut Context, clock: Option<&str>) {
+    let tc = &mut ctx.timer_context;
+    if let Some(clock) = clock {
+        let _ = tc.clock_mut(clock);
+    }
+    tc.update_clock = clock.map(|clock| clock.to_owned());
+}
+
+/// Returns the clock `UpdateMode::Fixed` updates go by, or `None`
+/// for the real clock.
+pub fn get_update_clock(ctx: &Context) -> Option<&str> {
+    ctx.timer_context
+        .update_clock
+        .as_ref()
+        .map(|clock| clock.as_str())
+}
+
 /// Pauses the current thread for the target duration.
 /// Just calls `std::thread::sleep()` so it's as accurate
 /// as that is (which is usually not very).
@@ -258,3 +834,66 @@ pub fn yield_now() {
 pub fn get_ticks(ctx: &Context) -> usize {
     ctx.timer_context.frame_count
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(tc: &mut TimeContext, millis: u64) {
+        tc.tick();
+        tc.override_delta(time::Duration::from_millis(millis));
+    }
+
+    #[test]
+    fn headless_log_buffer_keeps_order() {
+        let mut buffer = LogBuffer::new(3, 0);
+        assert!(buffer.samples().is_empty());
+        buffer.push(1);
+        buffer.push(2);
+        assert_eq!(buffer.samples(), &[1, 2]);
+        assert_eq!(buffer.ordered(), vec![1, 2]);
+        buffer.push(3);
+        buffer.push(4);
+        assert_eq!(buffer.samples().len(), 3);
+        assert_eq!(buffer.ordered(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn headless_profile_scopes_are_totalled_per_frame() {
+        let mut tc = TimeContext::new();
+        for _ in 0..3 {
+            let _scope = profile_scope("loop");
+        }
+        {
+            let _scope = profile_scope("sleep");
+            thread::sleep(time::Duration::from_millis(2));
+        }
+        tc.tick();
+        assert_eq!(tc.last_profile.len(), 2);
+        assert_eq!(tc.last_profile[0].name, "loop");
+        assert_eq!(tc.last_profile[0].calls, 3);
+        assert!(tc.last_profile[1].total >= time::Duration::from_millis(2));
+        tc.tick();
+        assert!(tc.last_profile.is_empty());
+    }
+
+    #[test]
+    fn headless_clocks_scale_and_pause() {
+        let mut tc = TimeContext::new();
+        frame(&mut tc, 100);
+        tc.clock_mut("game").time_scale = 0.5;
+        frame(&mut tc, 100);
+        assert_eq!(tc.clocks["game"].delta, time::Duration::from_millis(50));
+        assert_eq!(tc.clocks["game"].elapsed, time::Duration::from_millis(150));
+        assert_eq!(tc.elapsed, time::Duration::from_millis(200));
+
+        tc.clock_mut("game").paused = true;
+        frame(&mut tc, 100);
+        assert_eq!(tc.clocks["game"].delta, time::Duration::from_secs(0));
+        assert_eq!(tc.clocks["game"].elapsed, time::Duration::from_millis(150));
+        assert_eq!(
+            tc.clocks["game"].residual_update_dt,
+            time::Duration::from_millis(50)
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! Tweening: smoothly animating a value from one thing to another
+//! over time, such as sliding a menu onto the screen or fading a
+//! sprite out.
+//!
+//! A `Tween` goes from a start value to an end value over a set
+//! duration, following one of the standard `Ease` curves.  It can be
+//! advanced by hand with `Tween::advance()`, or handed to a
+//! `schedule::Scheduler` which advances it every frame and applies
+//! the value to your game state for you.
+//!
+//! For the shapes of the easing curves, see <https://easings.net/>.
+
+use std::f32::consts::PI;
+use std::time;
+
+use graphics::Color;
+use nalgebra as na;
+use timer;
+
+/// An easing curve, which says how a tween moves between its start
+/// and end values.  `In` curves start slowly, `Out` curves end
+/// slowly, and `InOut` curves do both.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Ease {
+    /// Constant speed.
+    Linear,
+    /// Accelerates, quadratically.
+    QuadIn,
+    /// Decelerates, quadratically.
+    QuadOut,
+    /// Accelerates then decelerates, quadratically.
+    QuadInOut,
+    /// Accelerates, cubically.
+    CubicIn,
+    /// Decelerates, cubically.
+    CubicOut,
+    /// Accelerates then decelerates, cubically.
+    CubicInOut,
+    /// Accelerates, to the fourth power.
+    QuartIn,
+    /// Decelerates, to the fourth power.
+    QuartOut,
+    /// Accelerates then decelerates, to the fourth power.
+    QuartInOut,
+    /// Accelerates, to the fifth power.
+    QuintIn,
+    /// Decelerates, to the fifth power.
+    QuintOut,
+    /// Accelerates then decelerates, to the fifth power.
+    QuintInOut,
+    /// Accelerates along a sine curve.
+    SineIn,
+    /// Decelerates along a sine curve.
+    SineOut,
+    /// Accelerates then decelerates along a sine curve.
+    SineInOut,
+    /// Accelerates exponentially.
+    ExpoIn,
+    /// Decelerates exponentially.
+    ExpoOut,
+    /// Accelerates then decelerates exponentially.
+    ExpoInOut,
+    /// Accelerates along a quarter circle.
+    CircIn,
+    /// Decelerates along a quarter circle.
+    CircOut,
+    /// Accelerates then decelerates along two quarter circles.
+    CircInOut,
+    /// Pulls back a little before starting.
+    BackIn,
+    /// Overshoots the end a little before settling.
+    BackOut,
+    /// Pulls back, then overshoots.
+    BackInOut,
+    /// Wobbles like a spring before starting.
+    ElasticIn,
+    /// Wobbles like a spring at the end.
+    ElasticOut,
+    /// Wobbles at both ends.
+    ElasticInOut,
+    /// Bounces before starting.
+    BounceIn,
+    /// Bounces at the end, like a dropped ball.
+    BounceOut,
+    /// Bounces at both ends.
+    BounceInOut,
+}
+
+impl Default for Ease {
+    fn default() -> Self {
+        Ease::Linear
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984_375
+    }
+}
+
+impl Ease {
+    /// Applies the curve to `t`, which is how far along the tween is
+    /// from 0.0 to 1.0 and gets clamped to that range.  The result is
+    /// 0.0 at the start and 1.0 at the end, but in between it can go
+    /// outside that range for the `Back` and `Elastic` curves.
+    pub fn apply(self, t: f32) -> f32 {
+        const BACK: f32 = 1.701_58;
+        const BACK_IN_OUT: f32 = BACK * 1.525;
+        const ELASTIC: f32 = 2.0 * PI / 3.0;
+        const ELASTIC_IN_OUT: f32 = 2.0 * PI / 4.5;
+
+        let t = t.max(0.0).min(1.0);
+        // The curves that can't quite reach the ends on their own.
+        if t == 0.0 || t == 1.0 {
+            return t;
+        }
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => t * (2.0 - t),
+            Ease::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Ease::CubicIn => t.powi(3),
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Ease::QuartIn => t.powi(4),
+            Ease::QuartOut => 1.0 - (1.0 - t).powi(4),
+            Ease::QuartInOut => {
+                if t < 0.5 {
+                    8.0 * t.powi(4)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(4) / 2.0
+                }
+            }
+            Ease::QuintIn => t.powi(5),
+            Ease::QuintOut => 1.0 - (1.0 - t).powi(5),
+            Ease::QuintInOut => {
+                if t < 0.5 {
+                    16.0 * t.powi(5)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(5) / 2.0
+                }
+            }
+            Ease::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Ease::SineOut => (t * PI / 2.0).sin(),
+            Ease::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Ease::ExpoIn => 2.0f32.powf(10.0 * t - 10.0),
+            Ease::ExpoOut => 1.0 - 2.0f32.powf(-10.0 * t),
+            Ease::ExpoInOut => {
+                if t < 0.5 {
+                    2.0f32.powf(20.0 * t - 10.0) / 2.0
+                } else {
+                    (2.0 - 2.0f32.powf(-20.0 * t + 10.0)) / 2.0
+                }
+            }
+            Ease::CircIn => 1.0 - (1.0 - t * t).sqrt(),
+            Ease::CircOut => (1.0 - (t - 1.0).powi(2)).sqrt(),
+            Ease::CircInOut => {
+                if t < 0.5 {
+                    (1.0 - (1.0 - (2.0 * t).powi(2)).sqrt()) / 2.0
+                } else {
+                    ((1.0 - (-2.0 * t + 2.0).powi(2)).sqrt() + 1.0) / 2.0
+                }
+            }
+            Ease::BackIn => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Ease::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Ease::BackInOut => {
+                if t < 0.5 {
+                    (2.0 * t).powi(2) * ((BACK_IN_OUT + 1.0) * 2.0 * t - BACK_IN_OUT) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2)
+                        * ((BACK_IN_OUT + 1.0) * (t * 2.0 - 2.0) + BACK_IN_OUT)
+                        + 2.0)
+                        / 2.0
+                }
+            }
+            Ease::ElasticIn => {
+                -(2.0f32.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * ELASTIC).sin()
+            }
+            Ease::ElasticOut => 2.0f32.powf(-10.0 * t) * ((10.0 * t - 0.75) * ELASTIC).sin() + 1.0,
+            Ease::ElasticInOut => {
+                let wave = ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin();
+                if t < 0.5 {
+                    -(2.0f32.powf(20.0 * t - 10.0) * wave) / 2.0
+                } else {
+                    2.0f32.powf(-20.0 * t + 10.0) * wave / 2.0 + 1.0
+                }
+            }
+            Ease::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Ease::BounceOut => bounce_out(t),
+            Ease::BounceInOut => {
+                if t < 0.5 {
+                    (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0
+                } else {
+                    (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// A value that can be tweened, by blending between two of them.
+pub trait Tweenable: Clone {
+    /// Returns a blend of `self` and `other`: `self` when `t` is 0.0,
+    /// `other` when it's 1.0, and in between (or beyond, for `t`
+    /// outside that range) otherwise.
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for na::Point2<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for na::Vector2<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+/// Colors are blended component by component, in sRGB space.
+impl Tweenable for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Color::new(
+            self.r.lerp(&other.r, t),
+            self.g.lerp(&other.g, t),
+            self.b.lerp(&other.b, t),
+            self.a.lerp(&other.a, t),
+        )
+    }
+}
+
+/// Animates a value from `from` to `to` over `duration`.
+///
+/// ```rust,ignore
+/// let mut fade = Tween::new(1.0, 0.0, Duration::from_secs(2), Ease::QuadOut);
+/// // Then each frame:
+/// fade.advance(timer::get_delta(ctx));
+/// sprite_alpha = fade.value();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    duration: f64,
+    elapsed: f64,
+    ease: Ease,
+}
+
+impl<T> Tween<T>
+where
+    T: Tweenable,
+{
+    /// Creates a new tween, starting at `from`.
+    pub fn new(from: T, to: T, duration: time::Duration, ease: Ease) -> Self {
+        Tween {
+            from,
+            to,
+            duration: timer::duration_to_f64(duration),
+            elapsed: 0.0,
+            ease,
+        }
+    }
+
+    /// Moves the tween along by the given amount of time.
+    pub fn advance(&mut self, dt: time::Duration) {
+        self.advance_secs(timer::duration_to_f64(dt));
+    }
+
+    /// Moves the tween along by the given number of seconds.
+    pub(crate) fn advance_secs(&mut self, dt: f64) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// Returns how far along the tween is, from 0.0 at the start to
+    /// 1.0 at the end, before easing.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            (self.elapsed / self.duration) as f32
+        }
+    }
+
+    /// Returns the current value.
+    pub fn value(&self) -> T {
+        self.from.lerp(&self.to, self.ease.apply(self.progress()))
+    }
+
+    /// Returns whether the tween has reached its end value.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Starts the tween over from the beginning.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    /// Swaps the start and end values, and starts over, for tweens
+    /// that go back and forth.
+    pub fn reverse(&mut self) {
+        ::std::mem::swap(&mut self.from, &mut self.to);
+        self.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_EASES: [Ease; 31] = [
+        Ease::Linear,
+        Ease::QuadIn,
+        Ease::QuadOut,
+        Ease::QuadInOut,
+        Ease::CubicIn,
+        Ease::CubicOut,
+        Ease::CubicInOut,
+        Ease::QuartIn,
+        Ease::QuartOut,
+        Ease::QuartInOut,
+        Ease::QuintIn,
+        Ease::QuintOut,
+        Ease::QuintInOut,
+        Ease::SineIn,
+        Ease::SineOut,
+        Ease::SineInOut,
+        Ease::ExpoIn,
+        Ease::ExpoOut,
+        Ease::ExpoInOut,
+        Ease::CircIn,
+        Ease::CircOut,
+        Ease::CircInOut,
+        Ease::BackIn,
+        Ease::BackOut,
+        Ease::BackInOut,
+        Ease::ElasticIn,
+        Ease::ElasticOut,
+        Ease::ElasticInOut,
+        Ease::BounceIn,
+        Ease::BounceOut,
+        Ease::BounceInOut,
+    ];
+
+    #[test]
+    fn headless_eases_are_continuous_and_hit_their_ends() {
+        for &ease in ALL_EASES.iter() {
+            assert_eq!(ease.apply(0.0), 0.0, "{:?}", ease);
+            assert_eq!(ease.apply(1.0), 1.0, "{:?}", ease);
+            assert_eq!(ease.apply(-1.0), 0.0, "{:?}", ease);
+            assert_eq!(ease.apply(2.0), 1.0, "{:?}", ease);
+            let mut last = 0.0;
+            for i in 1..=1000 {
+                let value = ease.apply(i as f32 / 1000.0);
+                assert!((value - last).abs() < 0.05, "{:?} jumps at {}", ease, i);
+                last = value;
+            }
+        }
+        assert_eq!(Ease::QuadIn.apply(0.5), 0.25);
+        assert_eq!(Ease::QuadInOut.apply(0.5), 0.5);
+        assert!(Ease::BackIn.apply(0.2) < 0.0);
+        assert!(Ease::BackOut.apply(0.8) > 1.0);
+    }
+
+    #[test]
+    fn headless_tweens_follow_their_curve() {
+        let mut tween = Tween::new(
+            na::Point2::new(0.0, 10.0),
+            na::Point2::new(10.0, 0.0),
+            time::Duration::from_secs(2),
+            Ease::QuadIn,
+        );
+        assert_eq!(tween.value(), na::Point2::new(0.0, 10.0));
+        tween.advance(time::Duration::from_secs(1));
+        assert_eq!(tween.value(), na::Point2::new(2.5, 7.5));
+        assert!(!tween.is_finished());
+        tween.advance(time::Duration::from_secs(5));
+        assert!(tween.is_finished());
+        assert_eq!(tween.value(), na::Point2::new(10.0, 0.0));
+        tween.reverse();
+        assert_eq!(tween.value(), na::Point2::new(10.0, 0.0));
+
+        let mut fade = Tween::new(
+            Color::new(1.0, 1.0, 1.0, 1.0),
+            Color::new(0.0, 0.5, 1.0, 0.0),
+            time::Duration::from_millis(100),
+            Ease::Linear,
+        );
+        fade.advance(time::Duration::from_millis(50));
+        assert_eq!(fade.value(), Color::new(0.5, 0.75, 1.0, 0.5));
+    }
+}
@@ -15,6 +15,8 @@ use std::fmt::{self, Debug};
 use std::fs;
 use std::io::{self, Read, Seek, Write};
 use std::path::{self, Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
 
 use zip;
 
@@ -27,11 +29,11 @@ fn convenient_path_to_str(path: &path::Path) -> GameResult<&str> {
     })
 }
 
-pub trait VFile: Read + Write + Seek + Debug {}
+pub trait VFile: Read + Write + Seek + Debug + Send {}
 
 impl<T> VFile for T
 where
-    T: Read + Write + Seek + Debug,
+    T: Read + Write + Seek + Debug + Send,
 {
 }
 
@@ -103,6 +105,11 @@ pub trait VFS: Debug {
     fn open(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
         self.open_options(path, OpenOptions::new().read(true))
     }
+    /// Open the file at this path for reading, without reading it all
+    /// into memory first if that's what `open()` would do
+    fn open_stream(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
+        self.open(path)
+    }
     /// Open the file at this path for writing, truncating it if it exists already
     fn create(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
         self.open_options(
@@ -441,6 +448,26 @@ impl VFS for OverlayFS {
         Err(GameError::ResourceNotFound(errmessage, tried))
     }
 
+    /// Open the file at this path for streaming
+    fn open_stream(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
+        let mut tried: Vec<(PathBuf, GameError)> = vec![];
+
+        for vfs in &self.roots {
+            match vfs.open_stream(path) {
+                Err(e) => {
+                    if let Some(vfs_path) = vfs.to_path_buf() {
+                        tried.push((vfs_path, e));
+                    } else {
+                        tried.push((PathBuf::from("<invalid path>"), e));
+                    }
+                }
+                f => return f,
+            }
+        }
+        let errmessage = String::from(convenient_path_to_str(path)?);
+        Err(GameError::ResourceNotFound(errmessage, tried))
+    }
+
     /// Create a directory at the location by this path
     fn mkdir(&self, path: &Path) -> GameResult {
         for vfs in &self.roots {
@@ -618,6 +645,149 @@ impl Debug for ZipFileWrapper {
     }
 }
 
+/// How many bytes a `ZipFileStream` decompresses at a time.
+const ZIP_STREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// How many chunks a `ZipFileStream` decompresses ahead of what's
+/// been read.
+const ZIP_STREAM_CHUNKS_AHEAD: usize = 4;
+
+/// A zipfile that's decompressed a bit at a time as it's read,
+/// unlike `ZipFileWrapper`, for big files like music that we'd rather
+/// not hold in memory all at once.
+///
+/// A `ZipFile` borrows the `ZipArchive` it came from, so we can't
+/// keep the two together in a struct.  Instead a thread opens the
+/// archive itself and sends the decompressed data over in chunks.
+/// Seeking forwards reads and throws away data; seeking backwards
+/// starts decompressing over again from the start of the file.
+pub struct ZipFileStream {
+    source: PathBuf,
+    name: String,
+    len: u64,
+    pos: u64,
+    chunks: Option<mpsc::Receiver<io::Result<Vec<u8>>>>,
+    chunk: io::Cursor<Vec<u8>>,
+}
+
+impl ZipFileStream {
+    fn new(source: &Path, name: &str, len: u64) -> Self {
+        Self {
+            source: source.to_owned(),
+            name: name.to_owned(),
+            len,
+            pos: 0,
+            chunks: None,
+            chunk: io::Cursor::new(Vec::new()),
+        }
+    }
+
+    /// Starts a thread decompressing the file from the beginning.
+    /// Any thread that was already running stops once it notices
+    /// nobody's listening.
+    fn restart(&mut self) {
+        let (sender, receiver) = mpsc::sync_channel(ZIP_STREAM_CHUNKS_AHEAD);
+        let source = self.source.clone();
+        let name = self.name.clone();
+        let _ = thread::spawn(move || {
+            if let Err(e) = Self::send_chunks(&source, &name, &sender) {
+                let _ = sender.send(Err(e));
+            }
+        });
+        self.chunks = Some(receiver);
+        self.chunk = io::Cursor::new(Vec::new());
+        self.pos = 0;
+    }
+
+    fn send_chunks(
+        source: &Path,
+        name: &str,
+        sender: &mpsc::SyncSender<io::Result<Vec<u8>>>,
+    ) -> io::Result<()> {
+        let zip_error = |e: zip::result::ZipError| io::Error::new(io::ErrorKind::Other, e);
+        let mut archive = zip::ZipArchive::new(fs::File::open(source)?).map_err(zip_error)?;
+        let mut zipfile = archive.by_name(name).map_err(zip_error)?;
+        loop {
+            let mut chunk = vec![0; ZIP_STREAM_CHUNK_SIZE];
+            let len = zipfile.read(&mut chunk)?;
+            if len == 0 {
+                return Ok(());
+            }
+            chunk.truncate(len);
+            if sender.send(Ok(chunk)).is_err() {
+                // The stream was dropped or restarted.
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl io::Read for ZipFileStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.chunks.is_none() {
+            self.restart();
+        }
+        loop {
+            let len = self.chunk.read(buf)?;
+            if len > 0 || buf.is_empty() {
+                self.pos += len as u64;
+                return Ok(len);
+            }
+            let next = match self.chunks {
+                Some(ref chunks) => chunks.recv(),
+                None => return Ok(0),
+            };
+            match next {
+                Ok(Ok(chunk)) => self.chunk = io::Cursor::new(chunk),
+                Ok(Err(e)) => return Err(e),
+                // The thread's finished, so that's the end of the file.
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+impl io::Write for ZipFileStream {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        panic!("Cannot write to a zip file!")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Seek for ZipFileStream {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            io::SeekFrom::Start(offset) => offset as i64,
+            io::SeekFrom::Current(offset) => self.pos as i64 + offset,
+            io::SeekFrom::End(offset) => self.len as i64 + offset,
+        };
+        if target < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Tried to seek before the start of a zip file",
+            ));
+        }
+        let target = target as u64;
+        if target < self.pos || self.chunks.is_none() {
+            self.restart();
+        }
+        let skip = target - self.pos;
+        let _ = io::copy(&mut Read::by_ref(self).take(skip), &mut io::sink())?;
+        // Like a file, we can seek past the end; reads from there
+        // just don't return anything.
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+impl Debug for ZipFileStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<Zipfile stream: {}>", self.name)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 struct ZipMetadata {
     len: u64,
@@ -682,6 +852,16 @@ impl VFS for ZipFS {
         Ok(Box::new(zipfile) as Box<dyn VFile>)
     }
 
+    fn open_stream(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
+        let name = convenient_path_to_str(path)?;
+        let mut stupid_archive_borrow = self.archive
+            .try_borrow_mut()
+            .expect("Couldn't borrow ZipArchive in ZipFS::open_stream(); should never happen! Report a bug at https://github.com/ggez/ggez/");
+        let len = stupid_archive_borrow.by_name(name)?.size();
+        let stream = ZipFileStream::new(&self.source, name, len);
+        Ok(Box::new(stream) as Box<dyn VFile>)
+    }
+
     fn mkdir(&self, path: &Path) -> GameResult {
         let msg = format!(
             "Cannot mkdir {:?} in zipfile {:?}, filesystem read-only",
@@ -751,6 +931,7 @@ impl VFS for ZipFS {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
     use std::io::{self, BufRead};
 
     #[test]
@@ -890,5 +1071,59 @@ mod tests {
         assert!(!fs.exists(testdir));
     }
 
+    /// Writes a file into a zip with the given compression method,
+    /// then reads it back through a `ZipFileStream`, seeking around.
+    fn check_zip_stream(method: zip::CompressionMethod, zip_name: &str) {
+        let contents: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+        let mut zip_path = env::temp_dir();
+        zip_path.push(zip_name);
+        {
+            let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+            let options = zip::write::FileOptions::default().compression_method(method);
+            writer.start_file("music.ogg", options).unwrap();
+            writer.write_all(&contents).unwrap();
+            let _ = writer.finish().unwrap();
+        }
+        let fs = ZipFS::new(&zip_path).unwrap();
+        let mut f = fs.open_stream(Path::new("music.ogg")).unwrap();
+
+        let mut buf = Vec::new();
+        let _ = f.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, contents);
+
+        let mut buf = [0; 10];
+        assert_eq!(f.seek(io::SeekFrom::Start(100_000)).unwrap(), 100_000);
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &contents[100_000..100_010]);
+        assert_eq!(f.seek(io::SeekFrom::Current(-20)).unwrap(), 99_990);
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &contents[99_990..100_000]);
+        assert_eq!(f.seek(io::SeekFrom::End(-10)).unwrap(), 299_990);
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &contents[299_990..]);
+
+        assert_eq!(f.seek(io::SeekFrom::End(50)).unwrap(), 300_050);
+        assert_eq!(f.read(&mut buf).unwrap(), 0);
+        assert_eq!(f.seek(io::SeekFrom::Current(-60)).unwrap(), 299_990);
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &contents[299_990..]);
+
+        fs::remove_file(&zip_path).unwrap();
+    }
+
+    #[test]
+    fn headless_test_zip_stream() {
+        check_zip_stream(zip::CompressionMethod::Stored, "ggez_test_zip_stream.zip");
+    }
+
+    #[test]
+    #[cfg(feature = "deflate")]
+    fn headless_test_deflated_zip_stream() {
+        check_zip_stream(
+            zip::CompressionMethod::Deflated,
+            "ggez_test_deflated_zip_stream.zip",
+        );
+    }
+
     // BUGGO: TODO: Make sure all functions are tested for OverlayFS and ZipFS!!
 }
@@ -0,0 +1,1000 @@
+//! Tests that run game logic on a headless `Context`,
+//! so they don't need a display or sound card.
+
+extern crate ggez;
+use ggez::audio::SoundSource;
+use ggez::*;
+
+fn make_headless_context() -> Context {
+    ContextBuilder::new("ggez_unit_tests", "ggez")
+        .with_conf_file(false)
+        .build_headless()
+        .unwrap()
+}
+
+struct CountingState {
+    updates: usize,
+    draws: usize,
+}
+
+impl event::EventHandler for CountingState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.updates += 1;
+        if self.updates == 10 {
+            ctx.quit();
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        self.draws += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn headless_context_runs_updates() {
+    let ctx = &mut make_headless_context();
+    assert!(ctx.is_headless());
+    let state = &mut CountingState {
+        updates: 0,
+        draws: 0,
+    };
+    event::run_headless(ctx, state).unwrap();
+    assert_eq!(state.updates, 10);
+    assert_eq!(state.draws, 0);
+    assert_eq!(timer::get_ticks(ctx), 10);
+}
+
+#[test]
+fn headless_context_graphics_return_errors() {
+    let ctx = &mut make_headless_context();
+    assert!(graphics::present(ctx).is_err());
+    assert!(graphics::get_renderer_info(ctx).is_err());
+    assert!(timer::draw_profiler_overlay(ctx, [0.0, 0.0]).is_err());
+    assert_eq!(
+        graphics::get_render_stats(ctx),
+        graphics::RenderStats::default()
+    );
+    assert_eq!(graphics::get_gpu_frame_time(ctx), None);
+}
+
+#[test]
+fn headless_context_rejects_zero_update_rate() {
+    let built = ContextBuilder::new("ggez_unit_tests", "ggez")
+        .with_conf_file(false)
+        .run_setup(conf::RunSetup::default().fixed_update(0, 5))
+        .build_headless();
+    assert!(built.is_err());
+}
+
+#[test]
+fn headless_context_has_null_audio() {
+    let ctx = &mut make_headless_context();
+    let data = audio::SoundData::from_bytes(&[]);
+    let source = audio::Source::from_data(ctx, data).unwrap();
+    assert!(!source.paused());
+}
+
+#[test]
+fn headless_streaming_source_decodes_from_file() {
+    let ctx = &mut make_headless_context();
+    let resources = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
+    filesystem::mount(ctx, &resources, true);
+
+    let mut music = audio::StreamingSource::new(ctx, "/sound.ogg").unwrap();
+    music.set_repeat(true);
+    music.play().unwrap();
+    assert!(music.playing());
+    // Playing again starts over rather than fighting over the file.
+    music.play().unwrap();
+
+    let not_audio = filesystem::open_stream(ctx, "/player.png").unwrap();
+    let broken = audio::StreamingSource::from_file(ctx, not_audio).unwrap();
+    assert!(broken.play().is_err());
+}
+
+#[test]
+fn headless_spatial_source_follows_listener() {
+    let ctx = &mut make_headless_context();
+    let data = audio::SoundData::from_bytes(&[]);
+    let mut explosion = audio::SpatialSource::from_data(ctx, data).unwrap();
+    explosion.set_attenuation(audio::Attenuation::Linear {
+        reference_distance: 100.0,
+        max_distance: 300.0,
+    });
+    explosion.set_position([500.0, 300.0, 0.0]);
+    ctx.audio_context.set_listener_position([400.0, 300.0, 0.0]);
+    let (left, right) = explosion.channel_volumes();
+    assert!(left < 0.01 && right > 0.99);
+
+    ctx.audio_context.set_listener_position([700.0, 300.0, 0.0]);
+    let (left, right) = explosion.channel_volumes();
+    assert!(right < 0.01 && (left - 0.5).abs() < 0.01);
+
+    ctx.audio_context.set_listener_position([900.0, 300.0, 0.0]);
+    assert_eq!(explosion.channel_volumes(), (0.0, 0.0));
+}
+
+struct InputState {
+    x: f32,
+    keys_down: usize,
+}
+
+impl event::EventHandler for InputState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if input::keyboard::is_key_pressed(ctx, event::KeyCode::Left) {
+            self.x -= 1.0;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _keycode: event::KeyCode,
+        _keymods: event::KeyMods,
+        _repeat: bool,
+    ) {
+        self.keys_down += 1;
+    }
+}
+
+#[test]
+fn headless_synthetic_events_update_input_state() {
+    use event::{GameEvent, KeyCode, KeyMods, MouseButton};
+    let ctx = &mut make_headless_context();
+    let state = &mut InputState {
+        x: 0.0,
+        keys_down: 0,
+    };
+
+    event::send_event(ctx, state, GameEvent::key_down(KeyCode::A, KeyMods::SHIFT));
+    assert_eq!(state.keys_down, 1);
+    assert!(input::keyboard::is_key_pressed(ctx, KeyCode::A));
+    assert!(input::keyboard::is_mod_active(ctx, KeyMods::SHIFT));
+
+    event::send_event(
+        ctx,
+        state,
+        GameEvent::MouseMotion {
+            x: 10.0,
+            y: 20.0,
+            dx: 10.0,
+            dy: 20.0,
+        },
+    );
+    event::send_event(
+        ctx,
+        state,
+        GameEvent::MouseButtonDown {
+            button: MouseButton::Left,
+            x: 10.0,
+            y: 20.0,
+        },
+    );
+    assert_eq!(
+        input::mouse::get_position(ctx),
+        nalgebra::Point2::new(10.0, 20.0)
+    );
+    assert!(input::mouse::get_button_pressed(ctx, MouseButton::Left));
+
+    event::send_event(ctx, state, GameEvent::Quit);
+    assert!(!ctx.continuing);
+}
+
+#[test]
+fn headless_raw_mouse_motion_sums_over_a_frame() {
+    use event::GameEvent;
+    let ctx = &mut make_headless_context();
+    let state = &mut InputState {
+        x: 0.0,
+        keys_down: 0,
+    };
+    input::mouse::set_relative_mode(ctx, true).unwrap();
+    let motion = |dx, dy| GameEvent::RawMouseMotion { dx, dy };
+
+    event::run_script(ctx, state, vec![vec![motion(3.0, -1.0), motion(2.0, 4.0)]]).unwrap();
+    assert_eq!(
+        input::mouse::get_delta(ctx),
+        nalgebra::Point2::new(5.0, 3.0)
+    );
+
+    event::run_script(ctx, state, vec![vec![]]).unwrap();
+    assert_eq!(
+        input::mouse::get_delta(ctx),
+        nalgebra::Point2::new(0.0, 0.0)
+    );
+
+    // With fixed updates, the delta lasts the whole frame, however
+    // many updates it runs.
+    let ctx = &mut ContextBuilder::new("ggez_unit_tests", "ggez")
+        .with_conf_file(false)
+        .run_setup(conf::RunSetup::default().fixed_update(1000, 10))
+        .build_headless()
+        .unwrap();
+    input::mouse::set_relative_mode(ctx, true).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    event::run_script(ctx, state, vec![vec![motion(3.0, -1.0), motion(2.0, 4.0)]]).unwrap();
+    assert_eq!(
+        input::mouse::get_delta(ctx),
+        nalgebra::Point2::new(5.0, 3.0)
+    );
+}
+
+#[test]
+fn headless_scripted_events_drive_updates() {
+    use event::{GameEvent, KeyCode, KeyMods};
+    let ctx = &mut make_headless_context();
+    let state = &mut InputState {
+        x: 0.0,
+        keys_down: 0,
+    };
+    let press = GameEvent::key_down(KeyCode::Left, KeyMods::NONE);
+    let release = GameEvent::key_up(KeyCode::Left, KeyMods::NONE);
+    let script = vec![vec![press], vec![], vec![], vec![release], vec![]];
+    event::run_script(ctx, state, script).unwrap();
+    assert_eq!(state.x, -3.0);
+    assert_eq!(state.keys_down, 1);
+    assert_eq!(timer::get_ticks(ctx), 5);
+}
+
+#[test]
+fn headless_recorded_input_replays_identically() {
+    use event::{GameEvent, KeyCode, KeyMods};
+    use input::recording;
+    let ctx = &mut make_headless_context();
+    let press = GameEvent::key_down(KeyCode::Left, KeyMods::NONE);
+    let release = GameEvent::key_up(KeyCode::Left, KeyMods::NONE);
+
+    let state = &mut InputState {
+        x: 0.0,
+        keys_down: 0,
+    };
+    recording::start_recording(ctx);
+    let script = vec![vec![], vec![press], vec![], vec![release], vec![]];
+    event::run_script(ctx, state, script).unwrap();
+    let recording = recording::stop_recording(ctx).unwrap();
+    assert_eq!(recording.frames().len(), 5);
+    assert_eq!(state.x, -2.0);
+
+    let replayed_state = &mut InputState {
+        x: 0.0,
+        keys_down: 0,
+    };
+    let recorded_deltas: Vec<_> = recording.frames().iter().map(|f| f.delta).collect();
+    recording::start_replay(ctx, recording);
+    let mut replayed_deltas = Vec::new();
+    for _ in 0..5 {
+        event::run_script(ctx, replayed_state, vec![vec![]]).unwrap();
+        replayed_deltas.push(timer::get_delta(ctx));
+    }
+    assert_eq!(replayed_state.x, -2.0);
+    assert_eq!(replayed_state.keys_down, 1);
+    assert_eq!(recorded_deltas, replayed_deltas);
+}
+
+struct ActionState {
+    jumps: usize,
+    releases: usize,
+    held_updates: usize,
+}
+
+impl event::EventHandler for ActionState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        use input::actions;
+        if actions::is_action_just_pressed(ctx, "jump") {
+            self.jumps += 1;
+        }
+        if actions::is_action_just_released(ctx, "jump") {
+            self.releases += 1;
+        }
+        if actions::is_action_pressed(ctx, "jump") {
+            self.held_updates += 1;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+}
+
+#[test]
+fn headless_actions_follow_bound_inputs() {
+    use event::{GameEvent, KeyCode, KeyMods};
+    use input::actions::{self, AxisBinding, Binding, InputMap};
+    let ctx = &mut make_headless_context();
+    let map = InputMap::new()
+        .action("jump", Binding::key(KeyCode::Space))
+        .axis("move_x", AxisBinding::keys(KeyCode::A, KeyCode::D));
+    actions::set_input_map(ctx, map);
+    let state = &mut ActionState {
+        jumps: 0,
+        releases: 0,
+        held_updates: 0,
+    };
+
+    let press = GameEvent::key_down(KeyCode::Space, KeyMods::NONE);
+    let release = GameEvent::key_up(KeyCode::Space, KeyMods::NONE);
+    let script = vec![vec![press], vec![], vec![release], vec![]];
+    event::run_script(ctx, state, script).unwrap();
+    assert_eq!(state.jumps, 1);
+    assert_eq!(state.releases, 1);
+    assert_eq!(state.held_updates, 2);
+
+    // Rebinding takes effect right away.
+    let _ = actions::unbind_action(ctx, "jump");
+    actions::bind_action(ctx, "jump", Binding::key(KeyCode::W));
+    let press_w = GameEvent::key_down(KeyCode::W, KeyMods::NONE);
+    let press_d = GameEvent::key_down(KeyCode::D, KeyMods::NONE);
+    event::run_script(ctx, state, vec![vec![press, press_w, press_d]]).unwrap();
+    assert_eq!(state.jumps, 2);
+    assert_eq!(actions::get_axis(ctx, "move_x"), 1.0);
+    assert_eq!(actions::get_axis(ctx, "no_such_axis"), 0.0);
+}
+
+#[test]
+fn headless_injected_gamepad_events_update_gamepad_state() {
+    use event::{Axis, Button, GameEvent};
+    use input::actions::{self, AxisBinding, Binding, InputMap};
+    use input::gamepad::{self, GamepadId};
+    let ctx = &mut make_headless_context();
+    let map = InputMap::new()
+        .action("jump", Binding::gamepad_button(Button::South))
+        .axis("move_x", AxisBinding::gamepad(Axis::LeftStickX));
+    actions::set_input_map(ctx, map);
+    let state = &mut ActionState {
+        jumps: 0,
+        releases: 0,
+        held_updates: 0,
+    };
+    let id = GamepadId::from(3);
+    assert!(gamepad::list_gamepads(ctx).is_empty());
+
+    let connect = GameEvent::ControllerConnected { id };
+    let press = GameEvent::ControllerButtonDown {
+        button: Button::South,
+        id,
+    };
+    let tilt = GameEvent::ControllerAxis {
+        axis: Axis::LeftStickX,
+        value: -0.5,
+        id,
+    };
+    event::run_script(ctx, state, vec![vec![connect, press, tilt]]).unwrap();
+    assert_eq!(gamepad::list_gamepads(ctx), vec![id]);
+    assert!(gamepad::get_button_pressed(ctx, id, Button::South));
+    assert!(gamepad::is_button_just_pressed(ctx, id, Button::South));
+    assert_eq!(gamepad::get_axis(ctx, id, Axis::LeftStickX), -0.5);
+    assert_eq!(state.jumps, 1);
+    assert_eq!(actions::get_axis(ctx, "move_x"), -0.5);
+
+    let snapshot = gamepad::get_snapshot(ctx, id).unwrap();
+    assert_eq!(snapshot.pressed_buttons(), vec![Button::South]);
+    assert_eq!(snapshot.axis(Axis::LeftStickX), -0.5);
+    assert_eq!(snapshot.name, "");
+
+    let disconnect = GameEvent::ControllerDisconnected { id };
+    event::run_script(ctx, state, vec![vec![disconnect]]).unwrap();
+    assert!(!gamepad::is_connected(ctx, id));
+    assert!(!gamepad::get_button_pressed(ctx, id, Button::South));
+    assert_eq!(gamepad::get_snapshot(ctx, id), None);
+    assert_eq!(state.releases, 1);
+}
+
+struct TapState {
+    taps: usize,
+    clicks: usize,
+}
+
+impl event::EventHandler for TapState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if input::keyboard::is_key_just_pressed(ctx, event::KeyCode::Space) {
+            self.taps += 1;
+        }
+        if input::mouse::is_button_just_released(ctx, event::MouseButton::Left) {
+            self.clicks += 1;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+}
+
+#[test]
+fn headless_short_taps_are_not_missed() {
+    use event::{GameEvent, KeyCode, KeyMods, MouseButton};
+    let ctx = &mut make_headless_context();
+    let state = &mut TapState { taps: 0, clicks: 0 };
+    let tap = vec![
+        GameEvent::key_down(KeyCode::Space, KeyMods::NONE),
+        GameEvent::key_up(KeyCode::Space, KeyMods::NONE),
+        GameEvent::MouseButtonDown {
+            button: MouseButton::Left,
+            x: 0.0,
+            y: 0.0,
+        },
+        GameEvent::MouseButtonUp {
+            button: MouseButton::Left,
+            x: 0.0,
+            y: 0.0,
+        },
+    ];
+    event::run_script(ctx, state, vec![tap, vec![], vec![]]).unwrap();
+    assert_eq!(state.taps, 1);
+    assert_eq!(state.clicks, 1);
+    assert!(!input::keyboard::is_key_pressed(ctx, KeyCode::Space));
+}
+
+struct FixedTapState {
+    updates: usize,
+    taps: usize,
+    jumps: usize,
+}
+
+impl event::EventHandler for FixedTapState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.updates += 1;
+        if input::keyboard::is_key_just_pressed(ctx, event::KeyCode::Space) {
+            self.taps += 1;
+        }
+        if input::actions::is_action_just_pressed(ctx, "jump") {
+            self.jumps += 1;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+}
+
+#[test]
+fn headless_fixed_updates_see_taps_from_frames_without_updates() {
+    use event::{GameEvent, KeyCode, KeyMods};
+    use input::actions::{self, Binding, InputMap};
+    use std::{thread, time};
+    let ctx = &mut ContextBuilder::new("ggez_unit_tests", "ggez")
+        .with_conf_file(false)
+        .run_setup(conf::RunSetup::default().fixed_update(5, 5))
+        .build_headless()
+        .unwrap();
+    actions::set_input_map(
+        ctx,
+        InputMap::new().action("jump", Binding::key(KeyCode::Space)),
+    );
+    let state = &mut FixedTapState {
+        updates: 0,
+        taps: 0,
+        jumps: 0,
+    };
+    let tap = vec![
+        GameEvent::key_down(KeyCode::Space, KeyMods::NONE),
+        GameEvent::key_up(KeyCode::Space, KeyMods::NONE),
+    ];
+
+    // The tap's frame is over long before a 200ms update is due.
+    event::run_script(ctx, state, vec![tap, vec![]]).unwrap();
+    thread::sleep(time::Duration::from_millis(450));
+    event::run_script(ctx, state, vec![vec![]]).unwrap();
+    assert!(state.updates >= 2);
+    assert_eq!(state.taps, 1);
+    assert_eq!(state.jumps, 1);
+}
+
+struct ScancodeState {
+    keys: usize,
+    scancodes: Vec<event::ScanCode>,
+}
+
+impl event::EventHandler for ScancodeState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _keycode: event::KeyCode,
+        _keymods: event::KeyMods,
+        _repeat: bool,
+    ) {
+        self.keys += 1;
+    }
+
+    fn scancode_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        scancode: event::ScanCode,
+        _keymods: event::KeyMods,
+        _repeat: bool,
+    ) {
+        self.scancodes.push(scancode);
+    }
+}
+
+#[test]
+fn headless_keys_without_keycodes_still_report_scancodes() {
+    use event::{GameEvent, KeyCode, KeyMods};
+    use input::keyboard;
+    let ctx = &mut make_headless_context();
+    let state = &mut ScancodeState {
+        keys: 0,
+        scancodes: vec![],
+    };
+    let w = keyboard::us_layout_scancode(KeyCode::W).unwrap();
+    // Until the key is pressed, its name is a guess from a US layout.
+    assert_eq!(keyboard::get_scancode_key(ctx, w), Some(KeyCode::W));
+    // An AZERTY keyboard reports Z for the key where W is on QWERTY.
+    let azerty_z = GameEvent::KeyDown {
+        keycode: Some(KeyCode::Z),
+        scancode: w,
+        keymods: KeyMods::NONE,
+        repeat: false,
+    };
+    let unknown = GameEvent::KeyDown {
+        keycode: None,
+        scancode: 0x1234,
+        keymods: KeyMods::NONE,
+        repeat: false,
+    };
+    event::send_event(ctx, state, azerty_z);
+    event::send_event(ctx, state, unknown);
+    assert_eq!(state.keys, 1);
+    assert_eq!(state.scancodes, vec![w, 0x1234]);
+    assert!(keyboard::is_scancode_pressed(ctx, 0x1234));
+    assert_eq!(keyboard::get_scancode_key(ctx, w), Some(KeyCode::Z));
+    assert_eq!(keyboard::get_scancode_name(ctx, w), Some("Z".to_owned()));
+    assert_eq!(keyboard::get_scancode_key(ctx, 0x1234), None);
+}
+
+struct TouchState {
+    touches: Vec<(event::TouchPhase, u64)>,
+    clicks: usize,
+}
+
+impl event::EventHandler for TouchState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn touch_event(
+        &mut self,
+        _ctx: &mut Context,
+        phase: event::TouchPhase,
+        id: u64,
+        _x: f32,
+        _y: f32,
+    ) {
+        self.touches.push((phase, id));
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _button: event::MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+        self.clicks += 1;
+    }
+}
+
+#[test]
+fn headless_touches_are_tracked_and_can_emulate_the_mouse() {
+    use event::{GameEvent, MouseButton, TouchPhase};
+    use input::{mouse, touch};
+    let ctx = &mut make_headless_context();
+    let state = &mut TouchState {
+        touches: vec![],
+        clicks: 0,
+    };
+    let touch_event = |id, phase, x, y| GameEvent::Touch { id, phase, x, y };
+
+    event::send_event(ctx, state, touch_event(1, TouchPhase::Started, 5.0, 5.0));
+    assert_eq!(touch::get_touch_count(ctx), 1);
+    assert_eq!(state.clicks, 0);
+    event::send_event(ctx, state, touch_event(1, TouchPhase::Ended, 5.0, 5.0));
+    assert_eq!(touch::get_touch_count(ctx), 0);
+
+    touch::set_mouse_emulation(ctx, true);
+    event::send_event(ctx, state, touch_event(2, TouchPhase::Started, 10.0, 20.0));
+    event::send_event(ctx, state, touch_event(3, TouchPhase::Started, 50.0, 50.0));
+    event::send_event(ctx, state, touch_event(2, TouchPhase::Moved, 15.0, 25.0));
+    assert_eq!(state.clicks, 1);
+    assert!(mouse::get_button_pressed(ctx, MouseButton::Left));
+    assert_eq!(mouse::get_position(ctx), nalgebra::Point2::new(15.0, 25.0));
+    assert_eq!(
+        touch::get_touch_position(ctx, 3),
+        Some(nalgebra::Point2::new(50.0, 50.0))
+    );
+    assert_eq!(touch::get_touches(ctx).len(), 2);
+
+    event::send_event(
+        ctx,
+        state,
+        touch_event(2, TouchPhase::Cancelled, 15.0, 25.0),
+    );
+    assert!(!mouse::get_button_pressed(ctx, MouseButton::Left));
+    assert_eq!(state.touches.len(), 6);
+    assert_eq!(state.touches[5], (TouchPhase::Cancelled, 2));
+}
+
+struct UserEventState {
+    messages: Vec<String>,
+}
+
+impl event::EventHandler<String> for UserEventState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn user_event(&mut self, _ctx: &mut Context, message: String) {
+        self.messages.push(message);
+    }
+}
+
+#[test]
+fn headless_user_events_arrive_from_other_threads() {
+    use std::thread;
+    let ctx = &mut make_headless_context();
+    let state = &mut UserEventState { messages: vec![] };
+    let sender = event::UserEventSender::new_headless::<UserEventState>(ctx);
+    let other_sender = sender.clone();
+    thread::spawn(move || {
+        other_sender.send("loaded".to_owned()).unwrap();
+    })
+    .join()
+    .unwrap();
+    sender.send("connected".to_owned()).unwrap();
+    event::run_script(ctx, state, vec![vec![]]).unwrap();
+    assert_eq!(state.messages, vec!["loaded", "connected"]);
+
+    // Events meant for some other type of state are dropped.
+    event::UserEventSender::new_headless::<OtherUserEventState>(ctx)
+        .send(5)
+        .unwrap();
+    event::run_script(ctx, state, vec![vec![]]).unwrap();
+    assert_eq!(state.messages, vec!["loaded", "connected"]);
+}
+
+struct OtherUserEventState;
+
+impl event::EventHandler<u32> for OtherUserEventState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+}
+
+/// A scene that logs the keys it gets to the world, and switches
+/// scenes when told to by the key pressed.
+struct KeyScene(&'static str);
+
+impl scene::Scene<Vec<String>> for KeyScene {
+    fn update(
+        &mut self,
+        world: &mut Vec<String>,
+        ctx: &mut Context,
+    ) -> GameResult<scene::SceneSwitch<Vec<String>>> {
+        use event::KeyCode;
+        use input::keyboard;
+        world.push(format!("{} update", self.0));
+        let switch = if keyboard::is_key_just_pressed(ctx, KeyCode::P) {
+            scene::SceneSwitch::push(KeyScene("pause"))
+        } else if keyboard::is_key_just_pressed(ctx, KeyCode::R) {
+            scene::SceneSwitch::replace(KeyScene("menu"))
+        } else if keyboard::is_key_just_pressed(ctx, KeyCode::Q) {
+            scene::SceneSwitch::Pop
+        } else {
+            scene::SceneSwitch::None
+        };
+        Ok(switch)
+    }
+
+    fn draw(&mut self, _world: &mut Vec<String>, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        world: &mut Vec<String>,
+        _ctx: &mut Context,
+        keycode: event::KeyCode,
+        _keymods: event::KeyMods,
+        _repeat: bool,
+    ) {
+        world.push(format!("{} {:?}", self.0, keycode));
+    }
+
+    fn controller_connected_event(
+        &mut self,
+        world: &mut Vec<String>,
+        _ctx: &mut Context,
+        id: input::gamepad::GamepadId,
+    ) {
+        world.push(format!("{} gamepad {}", self.0, id));
+    }
+
+    fn focus_event(&mut self, world: &mut Vec<String>, _ctx: &mut Context, gained: bool) {
+        world.push(format!("{} focus {}", self.0, gained));
+    }
+}
+
+#[test]
+fn headless_scene_stack_switches_and_forwards_input() {
+    use event::{GameEvent, KeyCode, KeyMods};
+    let ctx = &mut make_headless_context();
+    let stack = &mut scene::SceneStack::new(vec![], KeyScene("game"));
+    let press = |k| vec![GameEvent::key_down(k, KeyMods::NONE)];
+    event::run_script(
+        ctx,
+        stack,
+        vec![press(KeyCode::P), press(KeyCode::R), press(KeyCode::Q)],
+    )
+    .unwrap();
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.current().map(|s| s.name()), Some("<unnamed scene>"));
+    assert_eq!(
+        stack.world,
+        vec![
+            "game P",
+            "game update",
+            "pause R",
+            "pause update",
+            "menu Q",
+            "menu update",
+        ]
+    );
+    event::run_script(ctx, stack, vec![press(KeyCode::Q), vec![]]).unwrap();
+    assert!(stack.is_empty());
+    assert!(!ctx.continuing);
+}
+
+#[test]
+fn headless_window_events_reach_every_scene() {
+    use event::{GameEvent, KeyCode, KeyMods};
+    let ctx = &mut make_headless_context();
+    let stack = &mut scene::SceneStack::new(vec![], KeyScene("game"));
+    let press_p = GameEvent::key_down(KeyCode::P, KeyMods::NONE);
+    event::run_script(ctx, stack, vec![vec![press_p]]).unwrap();
+    stack.world.clear();
+
+    event::send_event(ctx, stack, GameEvent::Focus(false));
+    let id = input::gamepad::GamepadId::from(0);
+    event::send_event(ctx, stack, GameEvent::ControllerConnected { id });
+    assert_eq!(
+        stack.world,
+        vec!["game focus false", "pause focus false", "pause gamepad 0"]
+    );
+}
+
+#[test]
+fn headless_scheduler_runs_tasks_by_group() {
+    use schedule::{Scheduler, DEFAULT_GROUP};
+    use std::time::Duration;
+    use tween::{Ease, Tween};
+
+    #[derive(Default)]
+    struct World {
+        log: Vec<&'static str>,
+        ticks: u32,
+        x: f32,
+    }
+
+    let ctx = &mut make_headless_context();
+    let world = &mut World::default();
+    let scheduler = &mut Scheduler::new();
+    let ms = Duration::from_millis;
+
+    let _ = scheduler.after(ms(250), |world: &mut World, _ctx| world.log.push("boom"));
+    let ticker = scheduler.every(ms(100), |world: &mut World, _ctx| world.ticks += 1);
+    let slide = Tween::new(0.0, 100.0, ms(1000), Ease::Linear);
+    let slider = scheduler.tween(slide, |world: &mut World, x| world.x = x);
+    scheduler.set_task_group(slider, "ui");
+
+    scheduler.advance(ctx, world, ms(200));
+    assert_eq!(world.ticks, 2);
+    assert!(world.log.is_empty());
+    assert_eq!(world.x, 20.0);
+
+    scheduler.set_paused(DEFAULT_GROUP, true);
+    scheduler.set_time_scale("ui", 0.5);
+    scheduler.advance(ctx, world, ms(400));
+    assert_eq!(world.ticks, 2);
+    assert_eq!(world.x, 40.0);
+
+    scheduler.set_paused(DEFAULT_GROUP, false);
+    scheduler.advance(ctx, world, ms(100));
+    assert_eq!(world.log, vec!["boom"]);
+    assert_eq!(world.ticks, 3);
+
+    assert!(scheduler.cancel(ticker));
+    assert!(!scheduler.is_scheduled(ticker));
+    scheduler.set_time_scale("ui", 1.0);
+    scheduler.advance(ctx, world, ms(2000));
+    assert_eq!(world.x, 100.0);
+    assert!(scheduler.is_empty());
+}
+
+#[test]
+fn headless_scheduler_groups_and_fixed_updates_follow_clocks() {
+    use schedule::{Scheduler, DEFAULT_GROUP};
+    use std::{thread, time::Duration};
+    let ctx = &mut ContextBuilder::new("ggez_unit_tests", "ggez")
+        .with_conf_file(false)
+        .run_setup(conf::RunSetup::default().fixed_update(100, 5))
+        .build_headless()
+        .unwrap();
+    let ms = Duration::from_millis;
+
+    let ticks = &mut 0;
+    let scheduler = &mut Scheduler::new();
+    let _ = scheduler.every(ms(100), |ticks: &mut u32, _ctx| *ticks += 1);
+    scheduler.set_group_clock(DEFAULT_GROUP, Some("game"));
+    assert_eq!(scheduler.get_group_clock(DEFAULT_GROUP), Some("game"));
+    timer::set_clock_time_scale(ctx, "game", 0.5);
+    scheduler.advance(ctx, ticks, ms(300));
+    assert_eq!(*ticks, 1);
+    timer::set_clock_paused(ctx, "game", true);
+    scheduler.advance(ctx, ticks, ms(1000));
+    assert_eq!(*ticks, 1);
+
+    // Fixed updates stop while their clock is paused.
+    timer::set_update_clock(ctx, Some("game"));
+    let state = &mut FixedTapState {
+        updates: 0,
+        taps: 0,
+        jumps: 0,
+    };
+    thread::sleep(ms(50));
+    event::run_script(ctx, state, vec![vec![]]).unwrap();
+    assert_eq!(state.updates, 0);
+
+    timer::set_clock_paused(ctx, "game", false);
+    timer::set_clock_time_scale(ctx, "game", 1.0);
+    event::run_script(ctx, state, vec![vec![]]).unwrap();
+    thread::sleep(ms(50));
+    event::run_script(ctx, state, vec![vec![]]).unwrap();
+    assert!(state.updates > 0);
+}
+
+#[test]
+fn headless_frame_stats_summarize_frame_times() {
+    use std::time::Duration;
+    let ctx = &mut make_headless_context();
+    assert_eq!(timer::get_frame_stats(ctx), timer::FrameStats::default());
+    let recording = input::recording::Recording::from_frames(
+        (1..=100)
+            .map(|ms| input::recording::RecordedFrame {
+                delta: Duration::from_millis(ms),
+                events: vec![],
+            })
+            .collect(),
+    );
+    input::recording::start_replay(ctx, recording);
+    let state = &mut UserEventState { messages: vec![] };
+    event::run_script(ctx, state, vec![vec![]; 100]).unwrap();
+    let stats = timer::get_frame_stats(ctx);
+    assert_eq!(stats.min, Duration::from_millis(1));
+    assert_eq!(stats.max, Duration::from_millis(100));
+    assert_eq!(stats.median, Duration::from_millis(50));
+    assert_eq!(stats.p95, Duration::from_millis(95));
+    let histogram = timer::get_frame_time_histogram(ctx, Duration::from_millis(25), 3);
+    assert_eq!(histogram, vec![24, 25, 51]);
+}
+
+#[test]
+fn headless_mixer_buses_load_from_conf() {
+    let setup = conf::AudioSetup::default()
+        .master_volume(0.5)
+        .bus_volume(audio::MUSIC_BUS, 0.25);
+    let ctx = &mut ContextBuilder::new("ggez_unit_tests", "ggez")
+        .with_conf_file(false)
+        .audio_setup(setup.clone())
+        .build_headless()
+        .unwrap();
+    assert_eq!(ctx.audio_context.master_volume(), 0.5);
+    assert_eq!(ctx.audio_context.bus_volume(audio::MUSIC_BUS), 0.25);
+    assert_eq!(ctx.audio_context.bus_volume(audio::SFX_BUS), 1.0);
+
+    let data = audio::SoundData::from_bytes(&[]);
+    let mut blip = audio::Source::from_data(ctx, data).unwrap();
+    assert_eq!(blip.bus(), audio::SFX_BUS);
+    blip.set_bus("voice");
+    assert_eq!(blip.bus(), "voice");
+
+    ctx.audio_context.set_bus_muted("voice", true);
+    ctx.audio_context.set_bus_paused(audio::SFX_BUS, true);
+    assert!(ctx.audio_context.is_bus_muted("voice"));
+    assert!(ctx.audio_context.is_bus_paused(audio::SFX_BUS));
+    let saved = ctx.audio_context.audio_setup();
+    assert_eq!(saved, setup.bus_muted("voice", true));
+}
+
+#[test]
+fn headless_oneshots_steal_voices() {
+    let ctx = &mut make_headless_context();
+    let resources = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
+    filesystem::mount(ctx, &resources, true);
+    let pew = audio::SoundData::new(ctx, "/pew.ogg").unwrap();
+
+    let params = audio::PlayParams::new().max_voices(2, audio::VoiceStealing::Oldest);
+    let first = audio::play_oneshot(ctx, &pew, params.clone()).unwrap();
+    let second = audio::play_oneshot(ctx, &pew, params.clone()).unwrap();
+    let third = audio::play_oneshot(ctx, &pew, params.clone()).unwrap();
+    assert!(first.stopped());
+    assert!(!second.stopped() && !third.stopped());
+
+    let mut quiet = second;
+    quiet.set_volume(0.1);
+    let params = params.max_voices(2, audio::VoiceStealing::Quietest);
+    let fourth = audio::play_oneshot(ctx, &pew, params.clone()).unwrap();
+    assert!(quiet.stopped());
+    assert!(!third.stopped() && !fourth.stopped());
+
+    let params = params.max_voices(2, audio::VoiceStealing::Reject);
+    let rejected = audio::play_oneshot(ctx, &pew, params).unwrap();
+    assert!(rejected.stopped());
+
+    let mut fading = fourth;
+    fading.fade_out(std::time::Duration::from_secs(0));
+    assert!(fading.stopped());
+    let fifth = audio::play_oneshot(ctx, &pew, audio::PlayParams::new().pitch(1.5)).unwrap();
+    assert_eq!(fifth.pitch(), 1.5);
+
+    let not_audio = audio::SoundData::from_bytes(&[1, 2, 3]);
+    assert!(audio::play_oneshot(ctx, &not_audio, audio::PlayParams::new()).is_err());
+}
+
+#[test]
+fn headless_source_playback_controls() {
+    let ctx = &mut make_headless_context();
+    let resources = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
+    filesystem::mount(ctx, &resources, true);
+    let ms = std::time::Duration::from_millis;
+
+    let mut intro = audio::Source::new(ctx, "/sound.ogg").unwrap();
+    let length = intro.total_duration().unwrap();
+    assert!(length > ms(0));
+    assert_eq!(intro.total_duration().unwrap(), length);
+    assert_eq!(intro.elapsed(), ms(0));
+
+    intro.set_loop_points(ms(100), ms(300));
+    assert_eq!(intro.loop_points(), Some((ms(100), ms(300))));
+    intro.set_loop_points(ms(300), ms(100));
+    assert_eq!(intro.loop_points(), None);
+
+    intro.set_pitch(0.5);
+    intro.set_pitch_variation(0.1);
+    assert_eq!(intro.pitch(), 0.5);
+    assert_eq!(intro.pitch_variation(), 0.1);
+    intro.seek(ms(200));
+    intro.play().unwrap();
+
+    let mut level = audio::Source::new(ctx, "/sound.ogg").unwrap();
+    intro.crossfade_to(&mut level, ms(500)).unwrap();
+    assert!(level.playing());
+}